@@ -21,7 +21,8 @@ match sys.platform:
         raise Exception(f'unsupported platform {sys.platform}')
 
 def main():
-    cargo = subprocess.run(['cargo', 'build', '--release'])
+    # extra arguments go to cargo, so that features can be enabled with something like `./build.py --features semantic-search`
+    cargo = subprocess.run(['cargo', 'build', '--release', *sys.argv[1:]])
     if cargo.returncode != 0:
         raise Exception('cargo build failed')
     shutil.copy(os.path.join('target', 'release', LIB_SOURCE_NAME), os.path.join('lua', LIB_TARGET_NAME))
@@ -1,5 +1,7 @@
 local config = nil
 local internal = require("wikiplugin_internal")
+-- autogenerate commands registered with register_autogenerate, by name
+local custom_autogenerate = {}
 
 local function setup(config_local)
     local function check_key_present(key)
@@ -7,42 +9,100 @@ local function setup(config_local)
             error("wikiplugin setup config missing key '" .. key .. "'")
         end
     end
+    -- everything else has a default
     check_key_present('home_path')
-    check_key_present('note_id_timestamp_format')
-    check_key_present('date_format')
-    check_key_present('time_format')
 
     config = config_local
 
-    local autocmd_fname_pattern = vim.fn.fnamemodify(config_local.home_path, ":p") .. "*" -- use :p to make sure that there is a / at the end because the autocommand wont work if the path has a double slash
+    -- use :p to make sure that there is a / at the end because the autocommand wont work if the path has a double slash
+    local autocmd_fname_pattern = { vim.fn.fnamemodify(config_local.home_path, ":p") .. "*" }
+    -- each wiki in wikis is either its home path or a table with home_path and the settings that it has different from the main config
+    for _, wiki in pairs(config_local.wikis or {}) do
+        local wiki_home_path = type(wiki) == "table" and wiki.home_path or wiki
+        table.insert(autocmd_fname_pattern, vim.fn.fnamemodify(wiki_home_path, ":p") .. "*")
+    end
     local augroup = vim.api.nvim_create_augroup("wikiplugin", {})
-    vim.api.nvim_create_autocmd({ "BufNewFile", "BufRead", "BufWritePre" }, {
+    vim.api.nvim_create_autocmd({ "BufNewFile", "BufRead" }, {
         group = augroup,
         pattern = autocmd_fname_pattern,
         callback = function(ev)
             internal.regenerate_autogenerated_sections(config)
         end,
     })
+    -- regenerating when notes are saved is opt in with regenerate_on_save, which is either true or a list of autogenerate commands
+    internal.register_save_autocmd(config)
+
+    vim.api.nvim_create_autocmd({ "BufNewFile", "BufRead" }, {
+        group = augroup,
+        pattern = autocmd_fname_pattern,
+        callback = function(ev)
+            vim.bo[ev.buf].omnifunc = "v:lua.require'wikiplugin'.omnifunc"
+            -- buffer_options is a table of local options like conceallevel or spell that every note gets
+            internal.apply_buffer_options(config)
+        end,
+    })
+
+    -- reminder_check_interval is in seconds; reminders are not checked unless it is set
+    if config_local.reminder_check_interval ~= nil then
+        local timer = vim.uv.new_timer()
+        timer:start(0, config_local.reminder_check_interval * 1000, vim.schedule_wrap(function()
+            internal.check_reminders(config)
+        end))
+    end
 end
 
-local function insert_link_attach_mappings(prompt_bufnr, map)
-    local actions = require "telescope.actions"
-    local action_state = require "telescope.actions.state"
+-- the wiki that functions use when the current buffer is not in any of them; "main" is the one configured at the top level of the config
+local function select_wiki(name)
+    if name ~= "main" and (config.wikis == nil or config.wikis[name] == nil) then
+        error("wikiplugin: no wiki named '" .. name .. "'")
+    end
+    config.active_wiki = name
+    print("selected wiki '" .. name .. "'")
+end
 
-    actions.select_default:replace(function()
-        actions.close(prompt_bufnr)
-        local selection = action_state.get_selected_entry()
-        local note_path
-        if selection then
-            note_path = selection.note_path or selection.path or nil
-        else
-            note_path = nil
+-- vim.ui.input gives nil when the prompt is cancelled, in which case nothing happens
+local function prompt_note_title(callback)
+    vim.ui.input({ prompt = "note name: " }, function(title)
+        if title ~= nil then
+            callback(title)
         end
-
-        internal.insert_link_to_path_at_cursor_or_create(config, note_path, nil)
     end)
-    return true
 end
+
+-- note_path is nil if nothing was selected
+local function note_path_attach_mappings(on_select)
+    return function(prompt_bufnr, map)
+        local actions = require "telescope.actions"
+        local action_state = require "telescope.actions.state"
+
+        actions.select_default:replace(function()
+            actions.close(prompt_bufnr)
+            local selection = action_state.get_selected_entry()
+            local note_path
+            if selection then
+                note_path = selection.note_path or selection.path or nil
+            else
+                note_path = nil
+            end
+
+            on_select(note_path)
+        end)
+        return true
+    end
+end
+-- if nothing was selected, the link goes to a new note whose title is asked for
+local insert_link_attach_mappings = note_path_attach_mappings(function(note_path)
+    if note_path ~= nil then
+        internal.insert_link_to_path_at_cursor_or_create(config, note_path, nil, nil)
+        return
+    end
+    prompt_note_title(function(title)
+        internal.insert_link_to_path_at_cursor_or_create(config, nil, nil, title)
+    end)
+end)
+local insert_link_around_selection_attach_mappings = note_path_attach_mappings(function(note_path)
+    internal.insert_link_around_selection(config, note_path)
+end)
 local function search_by_title(attach_mappings, opts)
     local pickers = require "telescope.pickers"
     local finders = require "telescope.finders"
@@ -81,26 +141,439 @@ local function search_by_content(attach_mappings, opts)
         attach_mappings = attach_mappings,
     }):find()
 end
+-- each result is labelled with the wiki that it is in; picking one opens it
+local function search_all_wikis_picker(query, opts)
+    local pickers = require "telescope.pickers"
+    local finders = require "telescope.finders"
+    local conf = require("telescope.config").values
+
+    opts = opts or {}
+    pickers.new(opts, {
+        prompt_title = "search all wikis",
+
+        finder = finders.new_table({
+            results = internal.search_all_wikis(config, query or ""),
+            entry_maker = function(x) return x end,
+        }),
+        sorter = conf.generic_sorter(opts),
+        previewer = conf.grep_previewer(opts),
+    }):find()
+end
 local function insert_link_by_title()
     search_by_title(insert_link_attach_mappings)
 end
 local function insert_link_by_content()
     search_by_content(insert_link_attach_mappings)
 end
+local function insert_link_around_selection()
+    -- leave visual mode so that the '< and '> marks are set to the current selection
+    vim.api.nvim_feedkeys(vim.api.nvim_replace_termcodes("<Esc>", true, false, true), "nx", false)
+    search_by_title(insert_link_around_selection_attach_mappings)
+end
+-- called by the pre-commit hook from install_git_hooks, which passes the staged files (relative to the repository root) in an environment
+-- variable and expects the exit code to say whether they are fine
+local function check_for_commit()
+    local files = vim.split(os.getenv("WIKIPLUGIN_CHECK_FILES") or "", "\n", { trimempty = true })
+    local problems = internal.check_staged_files(config, vim.tbl_map(function(file) return vim.fn.fnamemodify(file, ":p") end, files))
+    if problems == nil then
+        vim.cmd("cquit 1")
+        return
+    end
+    for _, problem in ipairs(problems) do
+        io.stderr:write(problem .. "\n")
+    end
+    vim.cmd(#problems == 0 and "qall!" or "cquit 1")
+end
+-- with live_reload, the preview is rendered again every time the note is saved, and the page in the browser reloads itself to pick it up
+local function preview_note(live_reload)
+    internal.preview_note(config, live_reload == true)
+    if live_reload then
+        vim.api.nvim_create_autocmd("BufWritePost", {
+            group = vim.api.nvim_create_augroup("wikiplugin_preview_" .. vim.api.nvim_get_current_buf(), { clear = true }),
+            buffer = 0,
+            callback = function() internal.refresh_preview(config, true) end,
+        })
+    end
+end
+-- the float is focused so that the quick actions can be used straight away; each one closes the float and then acts on the note
+local function show_note_info()
+    local lines = internal.note_info(config)
+    if lines == nil then
+        return
+    end
+    local actions = {
+        { key = "b", name = "backlinks", run = function() internal.open_backlinks_panel(config) end },
+        { key = "p", name = "preview", run = function() preview_note(false) end },
+        { key = "c", name = "copy context", run = function() internal.copy_context(config, nil, 1, "+", nil) end },
+        { key = "r", name = "regenerate", run = function() internal.regenerate_autogenerated_sections(config) end },
+    }
+    table.insert(lines, "")
+    table.insert(lines, table.concat(vim.tbl_map(function(action) return action.key .. " " .. action.name end, actions), "  "))
+
+    local buf, win = vim.lsp.util.open_floating_preview(lines, "markdown", { border = "rounded", focus_id = "wikiplugin_note_info" })
+    vim.api.nvim_set_current_win(win)
+    for _, action in ipairs(actions) do
+        vim.keymap.set("n", action.key, function()
+            vim.api.nvim_win_close(win, true)
+            action.run()
+        end, { buffer = buf, nowait = true })
+    end
+end
+-- completes note ids, paths, and tags with <C-x><C-o>; set as the omnifunc of every note buffer in setup
+local function omnifunc(findstart, base)
+    if findstart == 1 then
+        return internal.omnifunc_start(config) or -3
+    end
+    return internal.omnifunc_complete(config, base) or {}
+end
+-- the suggestions go in the quickfix list, where pressing s on one splits that section off into its own note
+local function suggest_splits()
+    internal.suggest_splits(config)
+    if #vim.fn.getqflist() == 0 then
+        return
+    end
+    vim.cmd("copen")
+    vim.keymap.set("n", "s", function()
+        vim.cmd("cc " .. vim.fn.line("."))
+        internal.extract_section(config)
+    end, { buffer = true, desc = "split off the suggested section" })
+end
+-- the callback gets the arguments of the autogenerate marker (split on ;) and a context table with home_path, note_path, note_id,
+-- directories (the last three are missing in scratch buffers), and list_notes, which returns every note as a table with value (the id),
+-- display, ordinal, and path; it returns the lines to put in the section. names can only have letters, digits, and underscores, and
+-- the built in commands take precedence
+local function register_autogenerate(name, callback)
+    custom_autogenerate[name] = callback
+end
+-- called from regenerate_autogenerated_sections; returns nil if nothing is registered under the name
+local function run_custom_autogenerate(name, arguments, context)
+    local callback = custom_autogenerate[name]
+    if callback == nil then
+        return nil
+    end
+    context.list_notes = function() return internal.list_notes_and_titles_for_search(config) end
+    return callback(arguments, context) or {}
+end
+-- with no range given, this uses the visual selection
+local function follow_links_in_range(start, end_, open_mode)
+    if start == nil or end_ == nil then
+        vim.api.nvim_feedkeys(vim.api.nvim_replace_termcodes("<Esc>", true, false, true), "nx", false)
+        start, end_ = vim.fn.line("'<"), vim.fn.line("'>")
+    end
+    internal.follow_links_in_range(config, start, end_, open_mode or "args")
+end
+-- asks whether to create the link target first if it does not exist and create_missing_link_targets is "ask"
+local function follow_link()
+    local count = vim.v.count
+    local ask = internal.should_ask_to_create_link_target(config, count)
+    -- nil means there was an error, which has already been shown
+    if ask == nil then
+        return
+    elseif not ask then
+        internal.follow_link(config, count, false)
+        return
+    end
+    vim.ui.select({ "yes", "no" }, { prompt = "link target does not exist, create it?" }, function(choice)
+        internal.follow_link(config, count, choice == "yes")
+    end)
+end
+local function preview_link()
+    local lines = internal.preview_link(config, vim.v.count)
+    if lines ~= nil then
+        vim.lsp.util.open_floating_preview(lines, "markdown", { border = "rounded", focus_id = "wikiplugin_preview_link" })
+    end
+end
+local function suggest_tags()
+    local candidates = internal.suggest_tags(config)
+    if candidates == nil then
+        return
+    end
+
+    -- vim.ui.select only picks one item at a time, so keep asking until the user picks done
+    local chosen = {}
+    local function pick()
+        local remaining = vim.tbl_filter(function(candidate) return not vim.tbl_contains(chosen, candidate) end, candidates)
+        table.insert(remaining, 1, "[done]")
+        vim.ui.select(remaining, { prompt = "add tag (" .. #chosen .. " chosen so far)" }, function(choice)
+            if choice == nil then
+                return
+            elseif choice == "[done]" then
+                internal.add_tags_to_current_note(config, chosen)
+            else
+                table.insert(chosen, choice)
+                pick()
+            end
+        end)
+    end
+    pick()
+end
+
+local function new_note(template, directories, focus)
+    prompt_note_title(function(title)
+        internal.new_note(config, template, directories, focus, title)
+    end)
+end
+
+-- TODO: figure out a way to allow the user control over these arguments without having to put it everywhere
+local function new_note_and_insert_link()
+    prompt_note_title(function(title)
+        internal.new_note_and_insert_link(config, nil, {}, title)
+    end)
+end
+
+-- jots text down in the capture note without leaving the current buffer, asking for the text if it is not given
+local function capture(text)
+    if text ~= nil then
+        internal.capture(config, text)
+        return
+    end
+    vim.ui.input({ prompt = "capture: " }, function(input)
+        if input ~= nil and input ~= "" then
+            internal.capture(config, input)
+        end
+    end)
+end
+
+-- asks before running delete unless the confirm config says not to, then runs after (if given) whatever the answer was
+local function confirm_delete(question, delete, after)
+    after = after or function() end
+    -- should_confirm gives nil if the config has an error, which has to ask rather than delete
+    if internal.should_confirm(config, "delete") == false then
+        delete()
+        after()
+        return
+    end
+    vim.ui.select({ "yes", "no" }, { prompt = question }, function(choice)
+        if choice == "yes" then
+            delete()
+        else
+            print("not deleting")
+        end
+        after()
+    end)
+end
+
+-- notes go to the trash, where restore_note can get them back from, unless permanent is true
+-- path is absolute or relative to the home path, and is the current note if it is not given
+local function delete_note(permanent, path)
+    permanent = permanent == true
+    -- the path is taken now so that switching buffers while the prompt is open does not change which note is deleted
+    path = path or vim.fn.expand("%:p")
+    local question = permanent and "are you sure you want to delete " .. path .. " for good?" or "are you sure you want to delete " .. path .. "?"
+    -- the titles of the notes that will be left with broken links, of which the first few are named
+    local linking = internal.incoming_link_titles(config, path) or {}
+    if #linking > 0 then
+        local shown = table.concat(vim.list_slice(linking, 1, 3), ", ") .. (#linking > 3 and ", ..." or "")
+        question = #linking .. " note(s) link to this note (" .. shown .. "), " .. question
+    end
+    confirm_delete(question, function() internal.delete_note(config, path, permanent) end)
+end
+
+-- directories is a list or a path like "projects/garden" relative to the home path, and is asked for if it is not given
+local function move_note(directories)
+    local function move(path)
+        internal.move_note(config, vim.split(path, "/", { trimempty = true }))
+    end
+    if type(directories) == "table" then
+        internal.move_note(config, directories)
+    elseif directories ~= nil then
+        move(directories)
+    else
+        vim.ui.input({ prompt = "move to directory: " }, function(input)
+            if input ~= nil then
+                move(input)
+            end
+        end)
+    end
+end
+
+local function empty_trash()
+    confirm_delete("are you sure you want to delete everything in the trash for good?", function() internal.empty_trash(config) end)
+end
+
+-- opens the notes in the inbox directory one at a time and asks whether to move, tag, merge, delete, or skip each one
+local function process_inbox()
+    local paths = internal.inbox_notes(config)
+    if paths == nil then
+        return
+    end
+    local argument_prompts = { move = "move to directory: ", tag = "tags: ", merge = "merge into (path inside the wiki): " }
+
+    local function step(i)
+        if i > #paths then
+            print("done with the inbox")
+            return
+        end
+        local path = paths[i]
+        local function next_note() step(i + 1) end
+        vim.cmd.edit(vim.fn.fnameescape(path))
+        vim.cmd("redraw")
+        vim.ui.select({ "move", "tag", "merge", "delete", "skip", "quit" }, { prompt = "inbox note " .. i .. " of " .. #paths }, function(action)
+            if action == nil or action == "quit" then
+                return
+            elseif action == "skip" then
+                next_note()
+            elseif action == "delete" then
+                confirm_delete("delete " .. path .. "?", function() internal.process_inbox_note(config, path, "delete", "") end, next_note)
+            else
+                vim.ui.input({ prompt = argument_prompts[action] }, function(argument)
+                    if argument ~= nil then
+                        internal.process_inbox_note(config, path, action, argument)
+                    end
+                    next_note()
+                end)
+            end
+        end)
+    end
+    step(1)
+end
+
+-- asks for each setting in turn with vim.ui, then makes the wiki and shows a config snippet with the answers
+-- setup does not have to have been called first
+local function setup_wizard()
+    local answers = {}
+    local function cancelled()
+        print("setup wizard cancelled")
+    end
+
+    -- asks for a format until the user likes what the current time looks like in it
+    local function ask_format(key, prompt, default, next)
+        vim.ui.input({ prompt = prompt, default = default }, function(format)
+            if format == nil then
+                return cancelled()
+            end
+            -- format_example gives nil and shows the error if the format is invalid
+            local example = internal.format_example({ home_path = answers.home_path }, format)
+            if example == nil then
+                return ask_format(key, prompt, default, next)
+            end
+            vim.ui.select({ "use it", "try another" }, { prompt = "looks like: " .. example }, function(choice)
+                if choice == "use it" then
+                    answers[key] = format
+                    next()
+                elseif choice == "try another" then
+                    ask_format(key, prompt, format, next)
+                else
+                    cancelled()
+                end
+            end)
+        end)
+    end
+
+    local function ask_template()
+        vim.ui.select({ "yes", "no" }, { prompt = "make a default template for new notes?" }, function(choice)
+            if choice == nil then
+                return cancelled()
+            end
+            internal.finish_setup_wizard(answers, answers.link_style, choice == "yes")
+        end)
+    end
+
+    local function ask_link_style()
+        local styles = { "relative", "absolute", "home-relative", "id-only", "wikilink" }
+        vim.ui.select(styles, { prompt = "link style" }, function(choice)
+            if choice == nil then
+                return cancelled()
+            end
+            answers.link_style = choice
+            ask_template()
+        end)
+    end
+
+    vim.ui.input({ prompt = "wiki location: ", default = "~/wiki", completion = "dir" }, function(home_path)
+        if home_path == nil or home_path == "" then
+            return cancelled()
+        end
+        answers.home_path = vim.fn.fnamemodify(vim.fn.expand(home_path), ":p")
+        ask_format("note_id_timestamp_format", "note id format: ", "%Y%m%d%H%M%S", function()
+            ask_format("date_format", "date format: ", "%Y-%m-%d", function()
+                ask_format("time_format", "time format: ", "%H:%M:%S", ask_link_style)
+            end)
+        end)
+    end)
+end
 
 return {
     setup = setup,
+    select_wiki = select_wiki,
+    setup_wizard = setup_wizard,
 
-    new_note = function(directories, focus) internal.new_note(config, nil, directories, focus) end,
-    new_note_from_template = function(template, directories, focus) internal.new_note(config, template, directories, focus) end,
+    new_note = function(directories, focus) new_note(nil, directories, focus) end,
+    new_note_from_template = new_note,
+    preview_template = function(template) internal.preview_template(config, template) end,
+    lint_templates = function() internal.lint_templates(config) end,
     open_index = function() internal.open_index(config) end,
-    new_note_and_insert_link = function() internal.new_note_and_insert_link(config, nil, {}) end, -- TODO: figure out a way to allow the user control over these arguments without having to put it everywhere
-    delete_note = function() internal.delete_note(config) end,
-    open_tag_index = function() internal.open_tag_index(config) end,
-    follow_link = function() internal.follow_link(config) end,
+    open_directory_index = function(directories) internal.open_directory_index(config, directories or {}) end,
+    new_note_and_insert_link = new_note_and_insert_link,
+    delete_note = delete_note,
+    move_note = move_note,
+    restore_note = function(path) internal.restore_note(config, path) end,
+    empty_trash = empty_trash,
+    capture = capture,
+    process_inbox = process_inbox,
+    open_tag_index = function(tag) internal.open_tag_index(config, tag) end,
+    notes_with_tag = function(tag) return internal.notes_with_tag(config, tag) end,
+    refresh_tag_index = function() internal.refresh_tag_index(config) end,
+    open_backlinks_panel = function() internal.open_backlinks_panel(config) end,
+    open_timeline = function(range) internal.open_timeline(config, range) end,
+    show_link_heatmap = function() internal.show_link_heatmap(config) end,
+    clear_link_heatmap = function() internal.clear_link_heatmap(config) end,
+    find_similar_notes = function() internal.find_similar_notes(config) end,
+    follow_link = follow_link,
+    follow_links_in_range = follow_links_in_range,
+    goto_next_link = function() internal.goto_next_link(config, vim.v.count) end,
+    goto_prev_link = function() internal.goto_prev_link(config, vim.v.count) end,
+    preview_link = preview_link,
+    show_image_info_under_cursor = function() internal.show_image_info_under_cursor(config) end,
+    lint_images = function() internal.lint_images(config) end,
+    lint_anchors = function() internal.lint_anchors(config) end,
+    fix_anchor_under_cursor = function() internal.fix_anchor_under_cursor(config) end,
+    suggest_splits = suggest_splits,
+    extract_section = function() internal.extract_section(config) end,
+    split_note_by_headings = function(level) internal.split_note_by_headings(config, level) end,
+    install_git_hooks = function() internal.install_git_hooks(config) end,
+    check_for_commit = check_for_commit,
+    go_back = function() internal.go_back(config) end,
+    go_forward = function() internal.go_forward(config) end,
     regenerate_autogenerated_sections = function() internal.regenerate_autogenerated_sections(config) end,
+    regenerate_all = function() internal.regenerate_all(config) end,
+    list_autogenerated_sections = function() internal.list_autogenerated_sections(config) end,
+    remap_note_ids = function(directory) internal.remap_note_ids(config, directory) end,
+    register_autogenerate = register_autogenerate,
+    run_custom_autogenerate = run_custom_autogenerate,
+    -- only available if the plugin was built with the semantic-search feature
+    update_semantic_index = function() internal.update_semantic_index(config) end,
+    semantic_search = function(query, count) return internal.semantic_search(config, query, count or 10) end,
+    -- opts: note (path, defaults to the current note), depth (defaults to 1), register (defaults to "+"), file (written instead of the
+    -- register if given)
+    copy_context = function(opts)
+        opts = opts or {}
+        internal.copy_context(config, opts.note, opts.depth or 1, opts.register or "+", opts.file)
+    end,
+    add_tag = function(tag) internal.add_tag(config, tag) end,
+    remove_tag = function(tag) internal.remove_tag(config, tag) end,
+    advance_status = function() internal.advance_status(config) end,
+    rename_tag = function(old, new) internal.rename_tag(config, old, new) end,
+    resolve_conflicts = function() internal.resolve_conflicts(config) end,
+    next_conflict = function() internal.next_conflict(config) end,
+    resolve_conflict_hunk = function(resolution) internal.resolve_conflict_hunk(config, resolution) end,
+    export_html = function(output_dir) internal.export_html(config, output_dir) end,
+    export_profile = function(profile) internal.export_profile(config, profile) end,
+    export_obsidian = function(output_dir) internal.export_obsidian(config, output_dir) end,
+    compile_note = function(note, dest) internal.compile_note(config, note, dest) end,
+    renumber_sequence_section = function(sync_frontmatter) internal.renumber_sequence_section(config, sync_frontmatter == true) end,
+    preview_note = preview_note,
+    show_note_info = show_note_info,
+    omnifunc = omnifunc,
+    diff_since = function(since) internal.diff_since(config, since) end,
+    convert_links = function(style, dry_run) internal.convert_links(config, style, dry_run == true) end,
+    check_reminders = function() internal.check_reminders(config) end,
     search_by_title = search_by_title,
     search_by_content = search_by_content,
+    search_all_wikis = function(query) return internal.search_all_wikis(config, query or "") end,
+    search_all_wikis_picker = search_all_wikis_picker,
     insert_link_by_title = insert_link_by_title,
     insert_link_by_content = insert_link_by_content,
+    insert_link_around_selection = insert_link_around_selection,
+    suggest_tags = suggest_tags,
 }
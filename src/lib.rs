@@ -8,22 +8,36 @@ mod plugin;
 
 #[nvim_oxi::plugin]
 fn wikiplugin_internal() -> Dictionary {
-    flexi_logger::Logger::try_with_env()
-        .expect("could not initialize logger")
-        .log_to_file(flexi_logger::FileSpec::default())
-        .start()
-        .expect("could not initialize logger");
+    /// Starts logging into the state directory of the first wiki that the plugin is called with, since the home path is not known until
+    /// then. The handle is kept so that the log file stays open.
+    fn start_logger(config: &Config) {
+        thread_local! {
+            static LOGGER: std::cell::OnceCell<Option<flexi_logger::LoggerHandle>> = const { std::cell::OnceCell::new() };
+        }
+        LOGGER.with(|logger| {
+            logger.get_or_init(|| {
+                flexi_logger::Logger::try_with_env()
+                    .ok()?
+                    .log_to_file(flexi_logger::FileSpec::default().directory(config.state_dir()))
+                    .start()
+                    .ok()
+            });
+        });
+    }
 
     #[inline(always)]
     fn do_function<R, E: std::error::Error>(config: Dictionary, r: impl FnOnce(Config) -> Result<R, E>) -> Option<R> {
         match Config::parse_from_dict(config) {
-            Ok(config) => match r(config) {
-                Ok(r) => Some(r),
-                Err(e) => {
-                    error::print_error(&e as &dyn std::error::Error);
-                    None
+            Ok(config) => {
+                start_logger(&config);
+                match r(config) {
+                    Ok(r) => Some(r),
+                    Err(e) => {
+                        error::print_error(&e as &dyn std::error::Error);
+                        None
+                    }
                 }
-            },
+            }
             Err(e) => {
                 error::print_error(&e as &dyn std::error::Error);
                 None
@@ -31,21 +45,176 @@ fn wikiplugin_internal() -> Dictionary {
         }
     }
 
-    Dictionary::from_iter([
+    #[allow(unused_mut)]
+    let mut functions = vec![
         (
             "new_note",
-            Object::from(Function::from_fn(|(config, template, directories, focus): (Dictionary, Option<String>, Vec<String>, bool)| {
-                do_function(config, move |config| plugin::new_note(&config, template, directories, focus).map(|_| ()))
-            })),
+            Object::from(Function::from_fn(
+                |(config, template, directories, focus, title): (Dictionary, Option<String>, Vec<String>, bool, String)| {
+                    do_function(config, move |config| plugin::new_note(&config, template, directories, focus, title).map(|_| ()))
+                },
+            )),
+        ),
+        (
+            "capture",
+            Object::from(Function::from_fn(|(config, text): (Dictionary, String)| do_function(config, |config| plugin::capture(&config, text)))),
         ),
         ("open_index", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::open_index(&config))))),
-        ("delete_note", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::delete_note())))),
+        (
+            "format_example",
+            Object::from(Function::from_fn(|(config, format): (Dictionary, String)| {
+                do_function(config, |config| plugin::format_example(&config, format))
+            })),
+        ),
+        (
+            "finish_setup_wizard",
+            Object::from(Function::from_fn(|(config, link_style, with_template): (Dictionary, String, bool)| {
+                do_function(config, |config| plugin::finish_setup_wizard(&config, link_style, with_template))
+            })),
+        ),
+        (
+            "open_directory_index",
+            Object::from(Function::from_fn(|(config, directories): (Dictionary, Vec<String>)| {
+                do_function(config, |config| plugin::open_directory_index(&config, directories))
+            })),
+        ),
+        (
+            "delete_note",
+            Object::from(Function::from_fn(|(config, path, permanent): (Dictionary, String, bool)| {
+                do_function(config, |config| plugin::delete_note(&config, path, permanent))
+            })),
+        ),
+        (
+            "restore_note",
+            Object::from(Function::from_fn(|(config, path): (Dictionary, Option<String>)| {
+                do_function(config, |config| plugin::restore_note(&config, path))
+            })),
+        ),
+        (
+            "move_note",
+            Object::from(Function::from_fn(|(config, directories): (Dictionary, Vec<String>)| {
+                do_function(config, |config| plugin::move_note(&config, directories))
+            })),
+        ),
+        ("empty_trash", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::empty_trash(&config))))),
+        (
+            "incoming_link_titles",
+            Object::from(Function::from_fn(|(config, path): (Dictionary, String)| {
+                do_function(config, |config| plugin::incoming_link_titles(&config, path))
+            })),
+        ),
+        (
+            "should_confirm",
+            Object::from(Function::from_fn(|(config, operation): (Dictionary, String)| {
+                do_function(config, |config| plugin::should_confirm(&config, operation))
+            })),
+        ),
         (
             "new_note_and_insert_link",
-            Object::from(Function::from_fn(|(config, template, directories): (Dictionary, Option<String>, Vec<String>)| do_function(config, |config| plugin::new_note_and_insert_link(&config, template, directories)))),
+            Object::from(Function::from_fn(|(config, template, directories, title): (Dictionary, Option<String>, Vec<String>, String)| {
+                do_function(config, |config| plugin::new_note_and_insert_link(&config, template, directories, title))
+            })),
+        ),
+        (
+            "open_tag_index",
+            Object::from(Function::from_fn(|(config, tag): (Dictionary, Option<String>)| {
+                do_function(config, |config| plugin::open_tag_index(&config, tag))
+            })),
+        ),
+        (
+            "notes_with_tag",
+            Object::from(Function::from_fn(|(config, tag): (Dictionary, String)| {
+                do_function(config, |config| {
+                    Ok::<Vec<Dictionary>, plugin::TagIndexError>(
+                        plugin::notes_with_tag(&config, tag)?.into_iter().map(|dict_iter| dict_iter.into_iter().collect::<Dictionary>()).collect(),
+                    )
+                })
+            })),
+        ),
+        (
+            "refresh_tag_index",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::refresh_tag_index(&config)))),
+        ),
+        (
+            "should_ask_to_create_link_target",
+            Object::from(Function::from_fn(|(config, count): (Dictionary, usize)| {
+                do_function(config, |config| plugin::should_ask_to_create_link_target(&config, count))
+            })),
+        ),
+        (
+            "follow_link",
+            Object::from(Function::from_fn(|(config, count, create): (Dictionary, usize, bool)| {
+                do_function(config, |config| plugin::follow_link(&config, count, create))
+            })),
+        ),
+        (
+            "goto_next_link",
+            Object::from(Function::from_fn(|(config, count): (Dictionary, usize)| do_function(config, |_| plugin::goto_next_link(count)))),
+        ),
+        (
+            "goto_prev_link",
+            Object::from(Function::from_fn(|(config, count): (Dictionary, usize)| do_function(config, |_| plugin::goto_prev_link(count)))),
+        ),
+        (
+            "follow_links_in_range",
+            Object::from(Function::from_fn(|(config, start, end, open_mode): (Dictionary, usize, usize, String)| {
+                do_function(config, |config| plugin::follow_links_in_range(&config, start, end, open_mode))
+            })),
+        ),
+        (
+            "show_image_info_under_cursor",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::show_image_info_under_cursor(&config)))),
+        ),
+        ("lint_images", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::lint_images(&config))))),
+        (
+            "preview_template",
+            Object::from(Function::from_fn(|(config, template): (Dictionary, String)| {
+                do_function(config, |config| plugin::preview_template(&config, template))
+            })),
+        ),
+        ("lint_anchors", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::lint_anchors(&config))))),
+        (
+            "fix_anchor_under_cursor",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::fix_anchor_under_cursor(&config)))),
+        ),
+        ("lint_templates", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::lint_templates(&config))))),
+        (
+            "check_staged_files",
+            Object::from(Function::from_fn(|(config, paths): (Dictionary, Vec<String>)| {
+                do_function(config, |config| plugin::check_staged_files(&config, paths))
+            })),
+        ),
+        ("install_git_hooks", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::install_git_hooks(&config))))),
+        ("omnifunc_start", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |_| plugin::omnifunc_start())))),
+        (
+            "omnifunc_complete",
+            Object::from(Function::from_fn(|(config, base): (Dictionary, String)| {
+                do_function(config, |config| {
+                    Ok::<Vec<Dictionary>, plugin::OmnifuncError>(
+                        plugin::omnifunc_complete(&config, base)?
+                            .into_iter()
+                            .map(|dict_iter| dict_iter.into_iter().collect::<Dictionary>())
+                            .collect(),
+                    )
+                })
+            })),
+        ),
+        ("inbox_notes", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::inbox_notes(&config))))),
+        (
+            "process_inbox_note",
+            Object::from(Function::from_fn(|(config, path, action, argument): (Dictionary, String, String, String)| {
+                do_function(config, |config| plugin::process_inbox_note(&config, path, action, argument))
+            })),
+        ),
+        ("note_info", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::note_info(&config))))),
+        ("go_back", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::go_back(&config))))),
+        ("go_forward", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::go_forward(&config))))),
+        (
+            "preview_link",
+            Object::from(Function::from_fn(|(config, count): (Dictionary, usize)| {
+                do_function(config, |config| plugin::preview_link(&config, count))
+            })),
         ),
-        ("open_tag_index", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::open_tag_index(&config))))),
-        ("follow_link", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::follow_link(&config))))),
         (
             "insert_link_at_cursor",
             Object::from(Function::from_fn(
@@ -60,7 +229,13 @@ fn wikiplugin_internal() -> Dictionary {
         (
             "insert_link_at_cursor_or_create",
             Object::from(Function::from_fn(
-                |(config, link_to_directories, link_to_id, link_text): (Dictionary, Vec<String>, Option<String>, Option<String>)| {
+                |(config, link_to_directories, link_to_id, link_text, title): (
+                    Dictionary,
+                    Vec<String>,
+                    Option<String>,
+                    Option<String>,
+                    Option<String>,
+                )| {
                     let n;
                     let note = match link_to_id {
                         Some(link_to_id) => {
@@ -70,20 +245,145 @@ fn wikiplugin_internal() -> Dictionary {
                         None => None,
                     };
 
-                    do_function(config, |config| plugin::insert_link_at_cursor_or_create(&config, note, link_text))
+                    do_function(config, |config| plugin::insert_link_at_cursor_or_create(&config, note, link_text, title))
                 },
             )),
         ),
         (
             "insert_link_to_path_at_cursor_or_create",
-            Object::from(Function::from_fn(|(config, link_to_path, link_text): (Dictionary, Option<String>, Option<String>)| {
-                do_function(config, |config| plugin::insert_link_to_path_at_cursor_or_create(&config, link_to_path, link_text))
+            Object::from(Function::from_fn(
+                |(config, link_to_path, link_text, title): (Dictionary, Option<String>, Option<String>, Option<String>)| {
+                    do_function(config, |config| plugin::insert_link_to_path_at_cursor_or_create(&config, link_to_path, link_text, title))
+                },
+            )),
+        ),
+        (
+            "open_backlinks_panel",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::open_backlinks_panel(&config)))),
+        ),
+        (
+            "insert_link_around_selection",
+            Object::from(Function::from_fn(|(config, link_to_path): (Dictionary, Option<String>)| {
+                do_function(config, |config| plugin::insert_link_around_selection(&config, link_to_path))
             })),
         ),
         (
             "regenerate_autogenerated_sections",
             Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::regenerate_autogenerated_sections(&config)))),
         ),
+        (
+            "remap_note_ids",
+            Object::from(Function::from_fn(|(config, directory): (Dictionary, Option<String>)| {
+                do_function(config, |config| plugin::remap_note_ids(&config, directory.unwrap_or_default()))
+            })),
+        ),
+        ("regenerate_all", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::regenerate_all(&config))))),
+        (
+            "list_autogenerated_sections",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::list_autogenerated_sections(&config)))),
+        ),
+        (
+            "open_timeline",
+            Object::from(Function::from_fn(|(config, range): (Dictionary, Option<String>)| {
+                do_function(config, |config| plugin::open_timeline(&config, range))
+            })),
+        ),
+        (
+            "apply_buffer_options",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::apply_buffer_options(&config)))),
+        ),
+        (
+            "renumber_sequence_section",
+            Object::from(Function::from_fn(|(config, sync_frontmatter): (Dictionary, bool)| {
+                do_function(config, |config| plugin::renumber_sequence_section(&config, sync_frontmatter))
+            })),
+        ),
+        (
+            "register_save_autocmd",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::register_save_autocmd(&config)))),
+        ),
+        (
+            "find_similar_notes",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::find_similar_notes(&config)))),
+        ),
+        ("suggest_tags", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::suggest_tags(&config))))),
+        (
+            "add_tags_to_current_note",
+            Object::from(Function::from_fn(|(config, tags): (Dictionary, Vec<String>)| {
+                do_function(config, |_| plugin::add_tags_to_current_note(tags))
+            })),
+        ),
+        (
+            "rename_tag",
+            Object::from(Function::from_fn(|(config, old, new): (Dictionary, String, String)| {
+                do_function(config, |config| plugin::rename_tag(&config, old, new))
+            })),
+        ),
+        (
+            "copy_context",
+            Object::from(Function::from_fn(
+                |(config, note_path, depth, register, output_file): (Dictionary, Option<String>, usize, String, Option<String>)| {
+                    do_function(config, |config| plugin::copy_context(&config, note_path, depth, register, output_file))
+                },
+            )),
+        ),
+        ("add_tag", Object::from(Function::from_fn(|(config, tag): (Dictionary, String)| do_function(config, |_| plugin::add_tag(tag))))),
+        ("advance_status", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::advance_status(&config))))),
+        ("remove_tag", Object::from(Function::from_fn(|(config, tag): (Dictionary, String)| do_function(config, |_| plugin::remove_tag(tag))))),
+        ("resolve_conflicts", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::resolve_conflicts(&config))))),
+        ("next_conflict", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::next_conflict(&config))))),
+        (
+            "resolve_conflict_hunk",
+            Object::from(Function::from_fn(|(config, resolution): (Dictionary, String)| {
+                do_function(config, |_| plugin::resolve_conflict_hunk(resolution))
+            })),
+        ),
+        ("check_reminders", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::check_reminders(&config))))),
+        (
+            "convert_links",
+            Object::from(Function::from_fn(|(config, style, dry_run): (Dictionary, String, bool)| {
+                do_function(config, |config| plugin::convert_links::convert_links(&config, style, dry_run))
+            })),
+        ),
+        (
+            "diff_since",
+            Object::from(Function::from_fn(|(config, since): (Dictionary, String)| do_function(config, |config| plugin::diff_since(&config, since)))),
+        ),
+        (
+            "export_html",
+            Object::from(Function::from_fn(|(config, output_dir): (Dictionary, String)| {
+                do_function(config, |config| plugin::export::export_html(&config, output_dir))
+            })),
+        ),
+        ("suggest_splits", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::suggest_splits(&config))))),
+        ("extract_section", Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::extract_section(&config))))),
+        (
+            "split_note_by_headings",
+            Object::from(Function::from_fn(|(config, level): (Dictionary, Option<usize>)| {
+                do_function(config, |config| plugin::split_note_by_headings(&config, level))
+            })),
+        ),
+        (
+            "show_link_heatmap",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::show_link_heatmap(&config)))),
+        ),
+        (
+            "clear_link_heatmap",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |_| plugin::clear_link_heatmap()))),
+        ),
+        (
+            "search_all_wikis",
+            Object::from(Function::from_fn(|(config, query): (Dictionary, String)| {
+                do_function(config, |config| {
+                    Ok::<Vec<Dictionary>, plugin::ListAllPhysicalNotesError>(
+                        plugin::search_all_wikis(&config, query)?
+                            .into_iter()
+                            .map(|dict_iter| dict_iter.into_iter().collect::<Dictionary>())
+                            .collect(),
+                    )
+                })
+            })),
+        ),
         (
             "list_notes_and_titles_for_search",
             Object::from(Function::from_fn(|config: Dictionary| {
@@ -101,5 +401,58 @@ fn wikiplugin_internal() -> Dictionary {
             "list_notes_lines_for_search",
             Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::list_notes_lines_for_search(&config)))),
         ),
-    ])
+        (
+            "export_obsidian",
+            Object::from(Function::from_fn(|(config, output_dir): (Dictionary, String)| {
+                do_function(config, |config| plugin::export::export_obsidian(&config, output_dir))
+            })),
+        ),
+        (
+            "preview_note",
+            Object::from(Function::from_fn(|(config, live_reload): (Dictionary, bool)| {
+                do_function(config, |config| plugin::export::preview_note(&config, live_reload))
+            })),
+        ),
+        (
+            "refresh_preview",
+            Object::from(Function::from_fn(|(config, live_reload): (Dictionary, bool)| {
+                do_function(config, |config| plugin::export::refresh_preview(&config, live_reload))
+            })),
+        ),
+        (
+            "compile_note",
+            Object::from(Function::from_fn(|(config, note, dest): (Dictionary, Option<String>, String)| {
+                do_function(config, |config| plugin::export::compile_note(&config, note, dest))
+            })),
+        ),
+        (
+            "export_profile",
+            Object::from(Function::from_fn(|(config, profile): (Dictionary, String)| {
+                do_function(config, |config| plugin::export::export_profile(&config, profile))
+            })),
+        ),
+    ];
+
+    #[cfg(feature = "semantic-search")]
+    functions.extend([
+        (
+            "update_semantic_index",
+            Object::from(Function::from_fn(|config: Dictionary| do_function(config, |config| plugin::semantic::update_semantic_index(&config)))),
+        ),
+        (
+            "semantic_search",
+            Object::from(Function::from_fn(|(config, query, count): (Dictionary, String, usize)| {
+                do_function(config, |config| {
+                    Ok::<Vec<Dictionary>, plugin::semantic::SemanticSearchError>(
+                        plugin::semantic::semantic_search(&config, query, count)?
+                            .into_iter()
+                            .map(|dict_iter| dict_iter.into_iter().collect::<Dictionary>())
+                            .collect(),
+                    )
+                })
+            })),
+        ),
+    ]);
+
+    Dictionary::from_iter(functions)
 }
@@ -1,18 +1,62 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     path::{Path, PathBuf},
 };
 
 use nvim_oxi::{
-    api::{self, opts::{CmdOpts, OptionOpts, OptionScope}, types::CmdInfos},
-    Dictionary,
+    api::{
+        self,
+        opts::{CmdOpts, CreateAugroupOpts, CreateAutocmdOpts, OptionOpts, OptionScope},
+        types::{AutocmdCallbackArgs, CmdInfos},
+    },
+    Array, Dictionary, Object, ObjectKind,
 };
 
-use crate::plugin::note::{Note, PhysicalNote, Tag};
+use crate::plugin::{
+    conflicts::Resolution,
+    note::{Note, PhysicalNote, ScratchNote, Tag},
+};
 
+mod anchors;
+mod autogenerate;
+mod compile;
+mod completion;
+mod confirm;
+mod conflicts;
+mod context;
+mod deferred;
+mod filter;
+pub mod convert_links;
+pub mod export;
+mod frontmatter;
+mod heatmap;
+mod icons;
+mod ignore;
+mod images;
 mod links;
 mod markdown;
+mod navigation;
 pub mod note;
+mod note_id;
+mod note_index;
+mod query;
+mod relations;
+mod reminders;
+mod remap;
+#[cfg(feature = "semantic-search")]
+pub mod semantic;
+mod sequence;
+mod snapshot;
+mod splits;
+mod state;
+mod store;
+mod tag_index;
+mod templates;
+mod tfidf;
+mod timeline;
+mod trash;
+mod wiki_config;
+mod wizard;
 
 #[derive(Debug)]
 pub struct ConfigDictMissingKey(&'static str);
@@ -23,6 +67,25 @@ impl std::fmt::Display for ConfigDictMissingKey {
     }
 }
 #[derive(Debug)]
+pub struct ConflictingEncryptionKeys;
+impl std::error::Error for ConflictingEncryptionKeys {}
+impl std::fmt::Display for ConflictingEncryptionKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "only one of encryption_keyfile and encryption_passphrase can be specified")
+    }
+}
+#[derive(Debug)]
+pub struct InvalidConfigValue {
+    key: &'static str,
+    value: String,
+}
+impl std::error::Error for InvalidConfigValue {}
+impl std::fmt::Display for InvalidConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid value '{}' for config key {}", self.value, self.key)
+    }
+}
+#[derive(Debug)]
 pub struct HomePathNotAbsolute;
 impl std::error::Error for HomePathNotAbsolute {}
 impl std::fmt::Display for HomePathNotAbsolute {
@@ -30,11 +93,54 @@ impl std::fmt::Display for HomePathNotAbsolute {
         write!(f, "home path should be absolute")
     }
 }
+/// More than one problem with the config.
+#[derive(Debug)]
+pub struct ConfigProblems(Vec<ConfigParseError>);
+impl std::error::Error for ConfigProblems {}
+impl std::fmt::Display for ConfigProblems {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} problems in config:", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
 error_union! {
     pub enum ConfigParseError {
         ConversionError(nvim_oxi::conversion::Error),
         ConfigDictMissingKey(ConfigDictMissingKey),
         HomePathNotAbsolute(HomePathNotAbsolute),
+        ConflictingEncryptionKeys(ConflictingEncryptionKeys),
+        InvalidConfigValue(InvalidConfigValue),
+        ConfigProblems(ConfigProblems),
+        WikiConfigFileError(wiki_config::WikiConfigFileError),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CreateMissingLinkTargets {
+    Always,
+    Ask,
+    Never,
+}
+
+/// Which autogenerated sections are regenerated when a note is saved.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RegenerateOnSave {
+    Never,
+    All,
+    /// only the sections made by these commands, for when the others are too slow to run on every save
+    Only(Vec<String>),
+}
+
+impl RegenerateOnSave {
+    fn includes(&self, command: &str) -> bool {
+        match self {
+            RegenerateOnSave::Never => false,
+            RegenerateOnSave::All => true,
+            RegenerateOnSave::Only(commands) => commands.iter().any(|c| c == command),
+        }
     }
 }
 
@@ -42,28 +148,475 @@ error_union! {
 pub struct Config {
     home_path: PathBuf,
     note_id_timestamp_format: String,
+    id_strategy: note_id::IdStrategy,
     date_format: String,
     time_format: String,
+    locale: Option<chrono::Locale>,
+    author: Option<String>,
+    encryption: Option<state::EncryptionKeySource>,
+    default_template: Option<String>,
+    /// the lifecycle states that `advance_status` moves notes through, in order
+    statuses: Vec<String>,
+    /// where new notes go if no directories are given
+    inbox_directory: Option<Vec<String>>,
+    create_missing_link_targets: CreateMissingLinkTargets,
+    confirm: confirm::ConfirmPolicies,
+    deleted_note_links: trash::DeletedNoteLinks,
+    external_opener: Option<String>,
+    resolve_links_by_id: bool,
+    resolve_links_by_alias: bool,
+    resolve_links_by_title: bool,
+    /// whether links that do not resolve are looked up in the table of paths that `remap_note_ids` changed
+    resolve_links_by_remap: bool,
+    alias_as_link_text: bool,
+    link_style: links::LinkStyle,
+    reminder_command: Option<String>,
+    index_template: Option<String>,
+    /// the name of the index file of the wiki and of each directory that has one
+    index_filename: String,
+    directory_index_template: Option<String>,
+    /// the note, relative to the home path, that `capture` adds to, and the template that it is made from if it does not exist yet
+    capture_note: String,
+    capture_template: Option<String>,
+    /// the directory, relative to the home path, that `lint_templates` checks every template in, along with the configured ones
+    template_directory: Option<String>,
+    preview_line_count: usize,
+    max_image_size_kb: u64,
+    /// notes with more words or headings than these are flagged by `suggest_splits`
+    split_word_threshold: usize,
+    split_heading_threshold: usize,
+    note_icons: icons::NoteIcons,
+    #[cfg(feature = "semantic-search")]
+    embedding_command: Vec<String>,
+    tag_index_note: Option<String>,
+    tag_index_style: tag_index::TagIndexStyle,
+    export_profiles: BTreeMap<String, export::ExportProfile>,
+    /// every wiki by name, including the main one as `main` unless it has another name, which `search_all_wikis` looks in. Only the
+    /// active wiki's config has these.
+    wikis: BTreeMap<String, Config>,
+    regenerate_on_save: RegenerateOnSave,
+    /// whether autogenerated sections are shown as virtual lines under their markers instead of being written into the note
+    virtual_sections: bool,
+    timeline_grouping: timeline::TimelineGrouping,
+    /// what the `{badges}` placeholder in autogenerated lists shows, which is nothing by default
+    autogenerate_badges: Vec<query::Badge>,
+    /// how markers are written when the plugin adds them to a note
+    autogenerate_marker_style: autogenerate::MarkerStyle,
+    /// lua functions that are given a table describing a note and return the text to use, or nil to use the default
+    format_link_text: Option<nvim_oxi::Function<Dictionary, Option<String>>>,
+    format_index_item: Option<nvim_oxi::Function<Dictionary, Option<String>>>,
+    /// buffer and window local options, like `conceallevel` or `spell`, that are set on notes and on the views that the plugin opens
+    buffer_options: Vec<(String, Object)>,
+    /// files under the home directory that are not listed as notes
+    ignore: ignore::IgnorePatterns,
+    /// whether the `.gitignore` at the home directory is added to `ignore`
+    respect_gitignore: bool,
+    /// where the notes are kept, which is the home directory unless a test says otherwise
+    store: std::rc::Rc<dyn store::NoteStore>,
+    /// the table of paths that `remap_note_ids` changed, which is read the first time that a link needs it and then kept for as long as
+    /// this config is, which is one call into the plugin
+    remap_table: std::rc::Rc<std::cell::OnceCell<Option<remap::RemapTable>>>,
+}
+#[cfg(test)]
+impl Default for Config {
+    /// An empty config for tests that only need to set a few fields. The note store is rooted at an empty path, so tests that read notes
+    /// should use `Config::new` instead.
+    fn default() -> Config {
+        Config::new(PathBuf::new(), String::new(), String::new(), String::new())
+    }
 }
 impl Config {
+    /// Creates a config with the required fields, leaving every optional field at its default.
+    pub fn new(home_path: PathBuf, note_id_timestamp_format: String, date_format: String, time_format: String) -> Config {
+        Config {
+            store: std::rc::Rc::new(store::FsStore::new(home_path.clone())),
+            remap_table: Default::default(),
+            home_path,
+            note_id_timestamp_format,
+            id_strategy: note_id::IdStrategy::Timestamp,
+            date_format,
+            time_format,
+            locale: None,
+            author: None,
+            encryption: None,
+            default_template: None,
+            inbox_directory: None,
+            statuses: ["seedling", "budding", "evergreen"].map(ToString::to_string).to_vec(),
+            create_missing_link_targets: CreateMissingLinkTargets::Ask,
+            confirm: confirm::ConfirmPolicies::default(),
+            deleted_note_links: trash::DeletedNoteLinks::Keep,
+            external_opener: None,
+            resolve_links_by_id: false,
+            resolve_links_by_alias: false,
+            resolve_links_by_title: false,
+            resolve_links_by_remap: false,
+            alias_as_link_text: false,
+            link_style: links::LinkStyle::Relative,
+            reminder_command: None,
+            index_template: None,
+            index_filename: "index.md".to_string(),
+            directory_index_template: None,
+            template_directory: None,
+            capture_note: "inbox.md".to_string(),
+            capture_template: None,
+            preview_line_count: 20,
+            max_image_size_kb: 1024,
+            split_word_threshold: 1500,
+            split_heading_threshold: 12,
+            note_icons: icons::NoteIcons::default(),
+            #[cfg(feature = "semantic-search")]
+            embedding_command: Vec::new(),
+            tag_index_note: None,
+            tag_index_style: tag_index::TagIndexStyle::Flat,
+            export_profiles: BTreeMap::new(),
+            wikis: BTreeMap::new(),
+            regenerate_on_save: RegenerateOnSave::Never,
+            virtual_sections: false,
+            timeline_grouping: timeline::TimelineGrouping::Month,
+            autogenerate_badges: Vec::new(),
+            autogenerate_marker_style: autogenerate::MarkerStyle::Plain,
+            format_link_text: None,
+            format_index_item: None,
+            ignore: ignore::IgnorePatterns::parse(&[".git".to_string(), ".obsidian".to_string(), ".trash".to_string()])
+                .expect("default ignore patterns should be valid globs"),
+            respect_gitignore: false,
+            buffer_options: Vec::new(),
+        }
+    }
+
+    /// Formats a date or time in the configured locale, so that the names of months and days come out in the right language.
+    // TODO: dates are parsed back without the locale, so a date_format with localized names cannot be read back from frontmatter
+    fn format_datetime(&self, datetime: &chrono::DateTime<chrono::Local>, format: &str) -> String {
+        match self.locale {
+            Some(locale) => datetime.format_localized(format, locale).to_string(),
+            None => datetime.format(format).to_string(),
+        }
+    }
+
+    /// The directory that the plugin keeps its own files in, such as its log.
+    pub fn state_dir(&self) -> PathBuf {
+        state::state_dir(self)
+    }
+
+    /// Parses the config for whichever wiki is active: the one that the current buffer is in, or else the one that was picked with
+    /// `select_wiki` (which sets `active_wiki`), or else the main one. Each wiki in `wikis` is either its home path or a table with its home
+    /// path and the settings that it has different from the main config.
     pub fn parse_from_dict(dict: Dictionary) -> Result<Config, ConfigParseError> {
-        fn get_from_dict<T: nvim_oxi::conversion::FromObject>(dict: &Dictionary, key: &'static str) -> Result<T, ConfigParseError> {
-            Ok(T::from_object(dict.get(key).ok_or(ConfigDictMissingKey(key))?.clone())?)
+        let main = Config::parse_wiki_from_dict(dict.clone())?;
+        let mut wikis = BTreeMap::new();
+        for (name, wiki) in get_optional_from_dict::<Dictionary>(&dict, "wikis")?.into_iter().flatten() {
+            let mut wiki_dict = dict.clone();
+            match <String as nvim_oxi::conversion::FromObject>::from_object(wiki.clone()) {
+                Ok(home_path) => wiki_dict.insert("home_path", home_path),
+                Err(_) => {
+                    for (key, value) in <Dictionary as nvim_oxi::conversion::FromObject>::from_object(wiki)? {
+                        wiki_dict.insert(key, value);
+                    }
+                }
+            }
+            wikis.insert(name.to_string_lossy().into_owned(), Config::parse_wiki_from_dict(wiki_dict)?);
+        }
+        if !wikis.values().any(|wiki: &Config| wiki.home_path == main.home_path) {
+            wikis.insert("main".to_string(), main.clone());
         }
+
+        let current_path = api::get_current_buf().get_name().unwrap_or_default();
+        // wikis can be inside each other, in which case the innermost one is the one that the buffer is in
+        let containing =
+            wikis.values().filter(|wiki| current_path.starts_with(&wiki.home_path)).max_by_key(|wiki| wiki.home_path.as_os_str().len());
+        let mut active = match (containing, get_optional_from_dict::<String>(&dict, "active_wiki")?) {
+            (Some(containing), _) => containing.clone(),
+            (None, Some(active_wiki)) if active_wiki != "main" => {
+                wikis.get(&active_wiki).cloned().ok_or(InvalidConfigValue { key: "active_wiki", value: active_wiki })?
+            }
+            (None, _) => main,
+        };
+        active.wikis = wikis;
+        Ok(active)
+    }
+
+    /// Parses the config of one wiki, without looking at `wikis`. Only `home_path` is required. Every problem with the config is collected
+    /// so that they can all be fixed at once.
+    fn parse_wiki_from_dict(dict: Dictionary) -> Result<Config, ConfigParseError> {
+        // nothing else can be checked without a home path
         let home_path: PathBuf = get_from_dict::<String>(&dict, "home_path")?.into();
+        // the wiki's own settings file wins over the lua config, so that a wiki that is synced between machines brings its settings along
+        let dict = match wiki_config::read_wiki_config(&home_path)? {
+            Some(settings) => merge_wiki_config(dict, settings),
+            None => dict,
+        };
+        let mut r = ConfigReader { dict: &dict, problems: Vec::new() };
         if !home_path.is_absolute() {
-            Err(HomePathNotAbsolute)?;
+            r.problem(HomePathNotAbsolute);
         }
-        let c = Config {
+        let mut format = |key: &'static str, default: &str| {
+            let format = r.optional::<String>(key).unwrap_or_else(|| default.to_string());
+            let is_valid = !format.trim().is_empty()
+                && !chrono::format::StrftimeItems::new(&format).any(|item| matches!(item, chrono::format::Item::Error))
+                // ids are file names
+                && !(key == "note_id_timestamp_format" && format.contains('/'));
+            if !is_valid {
+                r.problem(InvalidConfigValue { key, value: format.clone() });
+            }
+            format
+        };
+        let mut c = Config::new(
             home_path,
-            note_id_timestamp_format: get_from_dict(&dict, "note_id_timestamp_format")?,
-            date_format: get_from_dict(&dict, "date_format")?,
-            time_format: get_from_dict(&dict, "time_format")?,
+            format("note_id_timestamp_format", "%Y%m%d%H%M%S"),
+            format("date_format", "%Y-%m-%d"),
+            format("time_format", "%H:%M:%S"),
+        );
+
+        let encryption_keyfile = r.optional::<String>("encryption_keyfile");
+        let encryption_passphrase = r.optional::<String>("encryption_passphrase");
+        c.encryption = match (encryption_keyfile, encryption_passphrase) {
+            (None, None) => None,
+            (Some(keyfile), None) => Some(state::EncryptionKeySource::Keyfile(keyfile.into())),
+            (None, Some(passphrase)) => Some(state::EncryptionKeySource::Passphrase(passphrase)),
+            (Some(_), Some(_)) => {
+                r.problem(ConflictingEncryptionKeys);
+                None
+            }
         };
+
+        c.default_template = r.optional("default_template");
+        c.author = r.optional("author");
+        c.statuses = r.optional("statuses").unwrap_or(c.statuses);
+        c.inbox_directory = r
+            .optional::<String>("inbox_directory")
+            .map(|inbox| inbox.split('/').filter(|directory| !directory.is_empty()).map(ToString::to_string).collect());
+        c.index_template = r.optional("index_template");
+        c.index_filename = r.optional("index_filename").unwrap_or(c.index_filename);
+        c.directory_index_template = r.optional("directory_index_template");
+        c.template_directory = r.optional("template_directory");
+        c.capture_note = r.optional("capture_note").unwrap_or(c.capture_note);
+        c.capture_template = r.optional("capture_template");
+        c.tag_index_note = r.optional("tag_index_note");
+        c.external_opener = r.optional("external_opener");
+        c.reminder_command = r.optional("reminder_command");
+        c.resolve_links_by_id = r.optional("resolve_links_by_id").unwrap_or(c.resolve_links_by_id);
+        c.resolve_links_by_alias = r.optional("resolve_links_by_alias").unwrap_or(c.resolve_links_by_alias);
+        c.resolve_links_by_title = r.optional("resolve_links_by_title").unwrap_or(c.resolve_links_by_title);
+        c.resolve_links_by_remap = r.optional("resolve_links_by_remap").unwrap_or(c.resolve_links_by_remap);
+        c.alias_as_link_text = r.optional("alias_as_link_text").unwrap_or(c.alias_as_link_text);
+        c.respect_gitignore = r.optional("respect_gitignore").unwrap_or(c.respect_gitignore);
+        c.virtual_sections = r.optional("virtual_sections").unwrap_or(c.virtual_sections);
+        c.preview_line_count = r.optional("preview_line_count").unwrap_or(c.preview_line_count);
+        c.max_image_size_kb = r.optional("max_image_size_kb").unwrap_or(c.max_image_size_kb);
+        c.split_word_threshold = r.optional("split_word_threshold").unwrap_or(c.split_word_threshold);
+        c.split_heading_threshold = r.optional("split_heading_threshold").unwrap_or(c.split_heading_threshold);
+        c.format_link_text = r.optional("format_link_text");
+        c.format_index_item = r.optional("format_index_item");
+        #[cfg(feature = "semantic-search")]
+        {
+            c.embedding_command = r.optional("embedding_command").unwrap_or_default();
+        }
+        for (key, icons) in [("directory_icons", &mut c.note_icons.by_directory), ("kind_icons", &mut c.note_icons.by_kind)] {
+            for (name, icon) in r.optional::<Dictionary>(key).into_iter().flatten() {
+                match <String as nvim_oxi::conversion::FromObject>::from_object(icon) {
+                    Ok(icon) => {
+                        icons.insert(name.to_string_lossy().into_owned(), icon);
+                    }
+                    Err(e) => r.problem(e),
+                }
+            }
+        }
+        for (name, profile) in r.optional::<Dictionary>("export_profiles").into_iter().flatten() {
+            let profile = parse_export_profile(profile);
+            match profile {
+                Ok(profile) => {
+                    c.export_profiles.insert(name.to_string_lossy().into_owned(), profile);
+                }
+                Err(e) => r.problem(e),
+            }
+        }
+        if let Some(create_missing_link_targets) = r.optional::<String>("create_missing_link_targets") {
+            match create_missing_link_targets.as_str() {
+                "always" => c.create_missing_link_targets = CreateMissingLinkTargets::Always,
+                "ask" => c.create_missing_link_targets = CreateMissingLinkTargets::Ask,
+                "never" => c.create_missing_link_targets = CreateMissingLinkTargets::Never,
+                _ => r.problem(InvalidConfigValue { key: "create_missing_link_targets", value: create_missing_link_targets }),
+            }
+        }
+        // either true for every command or a list of the commands to run
+        if let Some(regenerate_on_save) = dict.get("regenerate_on_save") {
+            match <bool as nvim_oxi::conversion::FromObject>::from_object(regenerate_on_save.clone()) {
+                Ok(true) => c.regenerate_on_save = RegenerateOnSave::All,
+                Ok(false) => c.regenerate_on_save = RegenerateOnSave::Never,
+                Err(_) => match <Vec<String> as nvim_oxi::conversion::FromObject>::from_object(regenerate_on_save.clone()) {
+                    Ok(commands) => c.regenerate_on_save = RegenerateOnSave::Only(commands),
+                    Err(e) => r.problem(e),
+                },
+            }
+        }
+        if let Some(confirm) = dict.get("confirm") {
+            let confirm = parse_confirm_policies(confirm);
+            match confirm {
+                Ok(confirm) => c.confirm = confirm,
+                Err(e) => r.problem(e),
+            }
+        }
+        if let Some(locale) = r.optional::<String>("locale") {
+            match chrono::Locale::try_from(locale.as_str()) {
+                Ok(locale) => c.locale = Some(locale),
+                Err(_) => r.problem(InvalidConfigValue { key: "locale", value: locale }),
+            }
+        }
+        if let Some(tag_index_style) = r.optional::<String>("tag_index_style") {
+            match tag_index::TagIndexStyle::parse_from_str(&tag_index_style) {
+                Some(tag_index_style) => c.tag_index_style = tag_index_style,
+                None => r.problem(InvalidConfigValue { key: "tag_index_style", value: tag_index_style }),
+            }
+        }
+        for badge in r.optional::<Vec<String>>("autogenerate_badges").into_iter().flatten() {
+            match query::Badge::parse_from_str(&badge) {
+                Some(badge) => c.autogenerate_badges.push(badge),
+                None => r.problem(InvalidConfigValue { key: "autogenerate_badges", value: badge }),
+            }
+        }
+        if let Some(marker_style) = r.optional::<String>("autogenerate_marker_style") {
+            match autogenerate::MarkerStyle::parse_from_str(&marker_style) {
+                Some(marker_style) => c.autogenerate_marker_style = marker_style,
+                None => r.problem(InvalidConfigValue { key: "autogenerate_marker_style", value: marker_style }),
+            }
+        }
+        if let Some(timeline_grouping) = r.optional::<String>("timeline_grouping") {
+            match timeline::TimelineGrouping::parse_from_str(&timeline_grouping) {
+                Some(timeline_grouping) => c.timeline_grouping = timeline_grouping,
+                None => r.problem(InvalidConfigValue { key: "timeline_grouping", value: timeline_grouping }),
+            }
+        }
+        for (option, value) in r.optional::<Dictionary>("buffer_options").into_iter().flatten() {
+            let option = option.to_string_lossy().to_string();
+            match value.kind() {
+                ObjectKind::Boolean | ObjectKind::Integer | ObjectKind::String => c.buffer_options.push((option, value)),
+                _ => r.problem(InvalidConfigValue { key: "buffer_options", value: option }),
+            }
+        }
+        if let Some(ignore) = r.optional::<Vec<String>>("ignore") {
+            match ignore::IgnorePatterns::parse(&ignore) {
+                Ok(ignore) => c.ignore = ignore,
+                Err((pattern, _)) => r.problem(InvalidConfigValue { key: "ignore", value: pattern }),
+            }
+        }
+        if let Some(id_strategy) = r.optional::<String>("id_strategy") {
+            match note_id::IdStrategy::parse_from_str(&id_strategy) {
+                Some(id_strategy) => c.id_strategy = id_strategy,
+                None => r.problem(InvalidConfigValue { key: "id_strategy", value: id_strategy }),
+            }
+        }
+        if let Some(deleted_note_links) = r.optional::<String>("deleted_note_links") {
+            match trash::DeletedNoteLinks::parse_from_str(&deleted_note_links) {
+                Some(deleted_note_links) => c.deleted_note_links = deleted_note_links,
+                None => r.problem(InvalidConfigValue { key: "deleted_note_links", value: deleted_note_links }),
+            }
+        }
+        if let Some(link_style) = r.optional::<String>("link_style") {
+            match links::LinkStyle::parse_from_str(&link_style) {
+                Some(link_style) => c.link_style = link_style,
+                None => r.problem(InvalidConfigValue { key: "link_style", value: link_style }),
+            }
+        }
+
+        r.finish()?;
         Ok(c)
     }
 }
 
+/// Sets the keys in the wiki settings file on top of the lua config. Only the harmless ones that [`wiki_config::is_allowed_key`] lets
+/// through are used, and the rest are ignored. Tables replace tables instead of being merged into them.
+fn merge_wiki_config(mut dict: Dictionary, settings: yaml_rust::Yaml) -> Dictionary {
+    for (key, value) in settings.into_hash().into_iter().flatten() {
+        let Some(key) = key.into_string() else { continue };
+        if !wiki_config::is_allowed_key(&key) {
+            continue;
+        }
+        if let Some(value) = yaml_to_object(value) {
+            dict.insert(key, value);
+        }
+    }
+    dict
+}
+
+fn yaml_to_object(yaml: yaml_rust::Yaml) -> Option<Object> {
+    use yaml_rust::Yaml;
+    Some(match yaml {
+        Yaml::String(s) => Object::from(s),
+        Yaml::Integer(i) => Object::from(i),
+        Yaml::Real(real) => Object::from(real.parse::<f64>().ok()?),
+        Yaml::Boolean(b) => Object::from(b),
+        Yaml::Array(items) => Object::from(Array::from_iter(items.into_iter().filter_map(yaml_to_object))),
+        Yaml::Hash(hash) => Object::from(Dictionary::from_iter(
+            hash.into_iter().filter_map(|(key, value)| Some((key.into_string()?, yaml_to_object(value)?))),
+        )),
+        Yaml::Null => Object::nil(),
+        Yaml::Alias(_) | Yaml::BadValue => return None,
+    })
+}
+
+fn parse_export_profile(profile: Object) -> Result<export::ExportProfile, ConfigParseError> {
+    let profile = <Dictionary as nvim_oxi::conversion::FromObject>::from_object(profile)?;
+    Ok(export::ExportProfile {
+        output_dir: get_from_dict::<String>(&profile, "output_dir")?.into(),
+        audiences: get_optional_from_dict(&profile, "audiences")?,
+    })
+}
+
+/// Either one policy for everything or a table of policies by operation.
+fn parse_confirm_policies(confirm: &Object) -> Result<confirm::ConfirmPolicies, ConfigParseError> {
+    let parse_policy = |policy: String| confirm::ConfirmPolicy::parse_from_str(&policy).ok_or(InvalidConfigValue { key: "confirm", value: policy });
+    let mut policies = confirm::ConfirmPolicies::default();
+    match <String as nvim_oxi::conversion::FromObject>::from_object(confirm.clone()) {
+        Ok(policy) => policies.set_all(parse_policy(policy)?),
+        Err(_) => {
+            for (operation, policy) in <Dictionary as nvim_oxi::conversion::FromObject>::from_object(confirm.clone())? {
+                let operation = operation.to_string_lossy().into_owned();
+                let operation = confirm::Operation::parse_from_str(&operation).ok_or(InvalidConfigValue { key: "confirm", value: operation })?;
+                policies.set(operation, parse_policy(<String as nvim_oxi::conversion::FromObject>::from_object(policy)?)?);
+            }
+        }
+    }
+    Ok(policies)
+}
+
+/// Reads keys out of the config dict, collecting every problem instead of stopping at the first one.
+struct ConfigReader<'a> {
+    dict: &'a Dictionary,
+    problems: Vec<ConfigParseError>,
+}
+
+impl ConfigReader<'_> {
+    /// The value of a key that does not have to be there. A value of the wrong type is a problem, and is treated as if it were not there.
+    fn optional<T: nvim_oxi::conversion::FromObject>(&mut self, key: &'static str) -> Option<T> {
+        match get_optional_from_dict(self.dict, key) {
+            Ok(value) => value,
+            Err(e) => {
+                self.problems.push(e);
+                None
+            }
+        }
+    }
+
+    fn problem(&mut self, problem: impl Into<ConfigParseError>) {
+        self.problems.push(problem.into());
+    }
+
+    fn finish(mut self) -> Result<(), ConfigParseError> {
+        match self.problems.len() {
+            0 => Ok(()),
+            1 => Err(self.problems.remove(0)),
+            _ => Err(ConfigProblems(self.problems).into()),
+        }
+    }
+}
+
+fn get_from_dict<T: nvim_oxi::conversion::FromObject>(dict: &Dictionary, key: &'static str) -> Result<T, ConfigParseError> {
+    Ok(T::from_object(dict.get(key).ok_or(ConfigDictMissingKey(key))?.clone())?)
+}
+fn get_optional_from_dict<T: nvim_oxi::conversion::FromObject>(dict: &Dictionary, key: &'static str) -> Result<Option<T>, ConfigParseError> {
+    Ok(dict.get(key).map(|value| T::from_object(value.clone())).transpose()?)
+}
+
 #[derive(Debug)]
 pub struct NonUtf8Path;
 impl std::error::Error for NonUtf8Path {}
@@ -72,6 +625,13 @@ impl std::fmt::Display for NonUtf8Path {
         write!(f, "path is not utf8")
     }
 }
+error_union! {
+    pub enum GotoLinkError {
+        ApiError(api::Error),
+        MdParseError(markdown::MdParseError),
+    }
+}
+
 error_union! {
     pub enum ApiErrorOrNonUtf8Path {
         ApiError(api::Error),
@@ -79,6 +639,14 @@ error_union! {
     }
 }
 
+error_union! {
+    pub enum OpenIndexError {
+        ApiError(api::Error),
+        NonUtf8Path(NonUtf8Path),
+        IoError(std::io::Error),
+    }
+}
+
 #[derive(Debug)]
 pub struct CannotLinkToScratchNote;
 impl std::error::Error for CannotLinkToScratchNote {}
@@ -95,6 +663,7 @@ error_union! {
         ApiError(api::Error),
         NonUtf8Path(NonUtf8Path),
         CannotLinkToScratchNote(CannotLinkToScratchNote),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
         IoError(std::io::Error),
     }
 }
@@ -102,6 +671,7 @@ convert_error_union! {
     NewNoteError => InsertLinkError {
         ApiError => ApiError,
         NonUtf8Path => NonUtf8Path,
+        ListAllPhysicalNotesError => ListAllPhysicalNotesError,
         IoError => IoError,
     }
 }
@@ -109,12 +679,9 @@ convert_error_union! {
 error_union! {
     pub enum TagIndexError {
         ListAllPhysicalNotesError(ListAllPhysicalNotesError),
-        ReadContentsError(note::ReadContentsError),
-        GetCurrentNoteError(note::GetCurrentNoteError),
         ApiError(api::Error),
         NonUtf8Path(NonUtf8Path),
-        ParseMarkdownError(markdown::MdParseError), // TODO: remove these? if the frontmatter or title is incorrect just put nothing
-        InvalidFrontmatter(markdown::InvalidFrontmatter),
+        IoError(std::io::Error),
     }
 }
 
@@ -126,6 +693,69 @@ impl std::fmt::Display for NotOnALink {
         write!(f, "not on a link")
     }
 }
+#[derive(Debug)]
+pub struct OpenExternallyFailed(String);
+impl std::error::Error for OpenExternallyFailed {}
+impl std::fmt::Display for OpenExternallyFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not open externally: {}", self.0)
+    }
+}
+error_union! {
+    pub enum LinkUnderCursorError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ReadContentsError(note::ReadContentsError),
+        ParseMarkdownError(markdown::MdParseError),
+        NotOnALink(NotOnALink),
+    }
+}
+convert_error_union! {
+    LinkUnderCursorError => FollowLinkError {
+        ApiError => ApiError,
+        GetCurrentNoteError => GetCurrentNoteError,
+        ReadContentsError => ReadContentsError,
+        ParseMarkdownError => ParseMarkdownError,
+        NotOnALink => NotOnALink,
+    }
+}
+error_union! {
+    pub enum PreviewLinkError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ReadContentsError(note::ReadContentsError),
+        ParseMarkdownError(markdown::MdParseError),
+        NotOnALink(NotOnALink),
+        ResolveLinkPathError(links::ResolveLinkPathError),
+    }
+}
+error_union! {
+    pub enum OmnifuncError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        FormatLinkPathError(links::FormatLinkPathError),
+    }
+}
+error_union! {
+    pub enum NoteInfoError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        ParseMarkdownError(markdown::MdParseError),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+    }
+}
+convert_error_union! {
+    LinkUnderCursorError => PreviewLinkError {
+        ApiError => ApiError,
+        GetCurrentNoteError => GetCurrentNoteError,
+        ReadContentsError => ReadContentsError,
+        ParseMarkdownError => ParseMarkdownError,
+        NotOnALink => NotOnALink,
+    }
+}
+
 error_union! {
     pub enum FollowLinkError {
         ApiError(api::Error),
@@ -136,12 +766,79 @@ error_union! {
         NotOnALink(NotOnALink),
         ResolveLinkPathError(links::ResolveLinkPathError),
         NonUtf8Path(NonUtf8Path),
+        IoError(std::io::Error),
+        OpenExternallyFailed(OpenExternallyFailed),
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidOpenMode(String);
+impl std::error::Error for InvalidOpenMode {}
+impl std::fmt::Display for InvalidOpenMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid open mode '{}' (expected 'args' or 'tabs')", self.0)
+    }
+}
+error_union! {
+    pub enum FollowLinksInRangeError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ReadContentsError(note::ReadContentsError),
+        ParseMarkdownError(markdown::MdParseError),
+        NonUtf8Path(NonUtf8Path),
+        InvalidOpenMode(InvalidOpenMode),
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidConfirmOperation(String);
+impl std::error::Error for InvalidConfirmOperation {}
+impl std::fmt::Display for InvalidConfirmOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no operation named '{}' can ask for confirmation", self.0)
     }
 }
 
 error_union! {
     pub enum DeleteNoteError {
         ApiError(api::Error),
+        NonUtf8Path(NonUtf8Path),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        IoError(std::io::Error),
+    }
+}
+
+#[derive(Debug)]
+pub struct NothingToRestore;
+impl std::error::Error for NothingToRestore {}
+impl std::fmt::Display for NothingToRestore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no deleted note to restore")
+    }
+}
+#[derive(Debug)]
+pub struct RestoreTargetExists(PathBuf);
+impl std::error::Error for RestoreTargetExists {}
+impl std::fmt::Display for RestoreTargetExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot restore to {} because there is already a note there", self.0.display())
+    }
+}
+error_union! {
+    pub enum RestoreNoteError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        NothingToRestore(NothingToRestore),
+        RestoreTargetExists(RestoreTargetExists),
+        IoError(std::io::Error),
+    }
+}
+
+error_union! {
+    pub enum EmptyTrashError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
         IoError(std::io::Error),
     }
 }
@@ -153,7 +850,6 @@ error_union! {
         MdParseError(markdown::MdParseError), // TODO: remove most of these errors and just dont list files that trigger them?
         ReadContentsError(note::ReadContentsError),
         InvalidFrontmatter(markdown::InvalidFrontmatter),
-        GetFrontmatterFieldError(markdown::GetFrontmatterFieldError),
         GetTimestampError(markdown::GetTimestampError),
         FormatLinkPathError(links::FormatLinkPathError),
         ResolveLinkPathError(links::ResolveLinkPathError),
@@ -162,412 +858,3807 @@ error_union! {
     }
 }
 
+#[derive(Debug)]
+pub struct InvalidTimelineRange(String);
+impl std::error::Error for InvalidTimelineRange {}
+impl std::fmt::Display for InvalidTimelineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid timeline range '{}', expected 'from..until' in the configured date format", self.0)
+    }
+}
 error_union! {
-    pub enum ListAllPhysicalNotesError {
-        NonUtf8Path(NonUtf8Path),
-        GlobPatternError(glob::PatternError),
-        GlobError(glob::GlobError),
-        ParseFromFilepathError(note::ParseFromFilepathError),
+    pub enum TimelineError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        FormatLinkPathError(links::FormatLinkPathError),
+        InvalidTimelineRange(InvalidTimelineRange),
     }
 }
 
 error_union! {
-    pub enum NewNoteError {
+    pub enum RemapNoteIdsError {
         ApiError(api::Error),
-        NonUtf8Path(NonUtf8Path),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
         IoError(std::io::Error),
+        StateFileError(state::StateFileError),
     }
 }
-pub fn new_note(config: &Config, template: Option<String>, directories: Vec<String>, focus: bool) -> Result<Note, NewNoteError> {
-    let title: String = nvim_oxi::api::eval(r#"input("note name: ")"#)?;
-
-    let now = chrono::Local::now();
-    let note_id = now.format(&config.note_id_timestamp_format).to_string();
 
-    let note_path = {
-        let mut p = config.home_path.clone();
-        p.extend(&directories);
-        p.push(&note_id);
-        p.set_extension("md");
-        p
-    };
-
-    let note_contents = if let Some(template) = template {
-        let template_path = config.home_path.join(template);
-        let mut template_contents = std::fs::read_to_string(template_path)?;
-
-        let substitutions =
-            [("title", title), ("date", now.format(&config.date_format).to_string()), ("time", now.format(&config.time_format).to_string())];
+error_union! {
+    pub enum RegenerateAllError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+    }
+}
 
-        for (sub, repl) in substitutions {
-            template_contents = template_contents.replace(&("{".to_string() + sub + "}"), &repl);
-        }
+error_union! {
+    pub enum ListAutogeneratedSectionsError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+    }
+}
 
-        template_contents
-    } else {
+error_union! {
+    pub enum ListAllPhysicalNotesError {
+        NonUtf8Path(NonUtf8Path),
+        GlobPatternError(glob::PatternError),
+        GlobError(glob::GlobError),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+    }
+}
+
+error_union! {
+    pub enum ResolveConflictsError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidResolution(String);
+impl std::error::Error for InvalidResolution {}
+impl std::fmt::Display for InvalidResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid conflict resolution '{}' (expected 'ours', 'theirs', or 'both')", self.0)
+    }
+}
+#[derive(Debug)]
+pub struct NotInConflictHunk;
+impl std::error::Error for NotInConflictHunk {}
+impl std::fmt::Display for NotInConflictHunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cursor is not in a conflict hunk")
+    }
+}
+error_union! {
+    pub enum ResolveConflictHunkError {
+        ApiError(api::Error),
+        InvalidResolution(InvalidResolution),
+        NotInConflictHunk(NotInConflictHunk),
+    }
+}
+
+error_union! {
+    pub enum FindSimilarNotesError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+error_union! {
+    pub enum SuggestTagsError {
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+    }
+}
+
+error_union! {
+    pub enum CopyContextError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        IoError(std::io::Error),
+    }
+}
+
+error_union! {
+    pub enum RenameTagError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        IoError(std::io::Error),
+    }
+}
+
+error_union! {
+    pub enum CheckRemindersError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        StateFileError(state::StateFileError),
+        NonUtf8Path(NonUtf8Path),
+        IoError(std::io::Error),
+    }
+}
+
+#[derive(Debug)]
+pub struct NotOnAnImage;
+impl std::error::Error for NotOnAnImage {}
+impl std::fmt::Display for NotOnAnImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not on an image")
+    }
+}
+error_union! {
+    pub enum ImageInfoError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        ParseMarkdownError(markdown::MdParseError),
+        ResolveLinkPathError(links::ResolveLinkPathError),
+        NotOnAnImage(NotOnAnImage),
+    }
+}
+
+error_union! {
+    pub enum PreviewTemplateError {
+        ApiError(api::Error),
+        IoError(std::io::Error),
+    }
+}
+
+error_union! {
+    pub enum LintTemplatesError {
+        ApiError(api::Error),
+        IoError(std::io::Error),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+error_union! {
+    pub enum LintImagesError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+error_union! {
+    pub enum LintAnchorsError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+error_union! {
+    pub enum CheckStagedFilesError {
+        IoError(std::io::Error),
+        NonUtf8Path(NonUtf8Path),
+        GitError(GitError),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        MdParseError(markdown::MdParseError),
+    }
+}
+
+#[derive(Debug)]
+pub struct HookAlreadyExists(PathBuf);
+impl std::error::Error for HookAlreadyExists {}
+impl std::fmt::Display for HookAlreadyExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} already exists and was not installed by wikiplugin, so it was left alone", self.0.display())
+    }
+}
+error_union! {
+    pub enum InstallGitHooksError {
+        ApiError(api::Error),
+        GitError(GitError),
+        IoError(std::io::Error),
+        HookAlreadyExists(HookAlreadyExists),
+    }
+}
+
+#[derive(Debug)]
+pub struct GitCommandFailed(String);
+impl std::error::Error for GitCommandFailed {}
+impl std::fmt::Display for GitCommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "git command failed: {}", self.0)
+    }
+}
+error_union! {
+    pub enum GitError {
+        IoError(std::io::Error),
+        GitCommandFailed(GitCommandFailed),
+    }
+}
+
+#[derive(Debug)]
+pub struct NotAPhysicalNote;
+impl std::error::Error for NotAPhysicalNote {}
+impl std::fmt::Display for NotAPhysicalNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "current buffer is not a note in the wiki")
+    }
+}
+error_union! {
+    pub enum BacklinksPanelError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        FormatLinkPathError(links::FormatLinkPathError),
+        NonUtf8Path(NonUtf8Path),
+        NotAPhysicalNote(NotAPhysicalNote),
+    }
+}
+
+error_union! {
+    pub enum LinkHeatmapError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+    }
+}
+
+error_union! {
+    pub enum SuggestSplitsError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+#[derive(Debug)]
+pub struct NotInASection;
+impl std::error::Error for NotInASection {}
+impl std::fmt::Display for NotInASection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cursor is not under a heading")
+    }
+}
+#[derive(Debug)]
+pub struct NotInASequence;
+impl std::error::Error for NotInASequence {}
+impl std::fmt::Display for NotInASequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cursor is not in a numbered list")
+    }
+}
+error_union! {
+    pub enum RenumberSequenceError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotInASequence(NotInASequence),
+        IoError(std::io::Error),
+    }
+}
+error_union! {
+    pub enum ExtractSectionError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        NotInASection(NotInASection),
+        FormatLinkPathError(links::FormatLinkPathError),
+        NonUtf8Path(NonUtf8Path),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        IoError(std::io::Error),
+    }
+}
+convert_error_union! {
+    NewNoteError => ExtractSectionError {
+        ApiError => ApiError,
+        NonUtf8Path => NonUtf8Path,
+        ListAllPhysicalNotesError => ListAllPhysicalNotesError,
+        IoError => IoError,
+    }
+}
+
+#[derive(Debug)]
+pub struct NoSectionsToSplit;
+impl std::error::Error for NoSectionsToSplit {}
+impl std::fmt::Display for NoSectionsToSplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "note has no sections with anything in them to split off")
+    }
+}
+error_union! {
+    pub enum SplitNoteByHeadingsError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        NoSectionsToSplit(NoSectionsToSplit),
+        FormatLinkPathError(links::FormatLinkPathError),
+        NonUtf8Path(NonUtf8Path),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        IoError(std::io::Error),
+    }
+}
+convert_error_union! {
+    NewNoteError => SplitNoteByHeadingsError {
+        ApiError => ApiError,
+        NonUtf8Path => NonUtf8Path,
+        ListAllPhysicalNotesError => ListAllPhysicalNotesError,
+        IoError => IoError,
+    }
+}
+
+error_union! {
+    pub enum DiffSinceError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        GitError(GitError),
+        NonUtf8Path(NonUtf8Path),
+    }
+}
+
+error_union! {
+    pub enum NewNoteError {
+        ApiError(api::Error),
+        NonUtf8Path(NonUtf8Path),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        IoError(std::io::Error),
+    }
+}
+/// Creates a note with the given title. The title is asked for on the lua side with `vim.ui.input`, so that it goes through the user's UI
+/// plugins and can be cancelled.
+pub fn new_note(config: &Config, template: Option<String>, directories: Vec<String>, focus: bool, title: String) -> Result<Note, NewNoteError> {
+    create_note(config, template, directories, title, focus)
+}
+
+fn create_note(config: &Config, template: Option<String>, directories: Vec<String>, title: String, focus: bool) -> Result<Note, NewNoteError> {
+    let directories = match (directories.is_empty(), &config.inbox_directory) {
+        (true, Some(inbox)) => inbox.clone(),
+        _ => directories,
+    };
+    let now = chrono::Local::now();
+    let note = new_note_id(config, &directories, &title, &now.naive_local())?;
+    let note_path = note.path(config);
+
+    let note_contents = render_template(config, template.as_ref().or(config.default_template.as_ref()), title, &now)?;
+    // this makes the inbox directory in particular if it has not been made yet
+    config.store.write(&note, &note_contents)?;
+
+    if focus {
+        api::cmd(&CmdInfos::builder().cmd("edit").args([note_path.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+    }
+
+    Ok(Note::Physical(note))
+}
+
+error_union! {
+    pub enum CaptureError {
+        ApiError(api::Error),
+        ReadContentsError(note::ReadContentsError),
+        IoError(std::io::Error),
+    }
+}
+
+/// Adds `text` as a bullet with the date and time to the end of the `capture_note`, making the note from `capture_template` first if it does
+/// not exist, without opening it. The text is asked for on the lua side if it is not given.
+pub fn capture(config: &Config, text: String) -> Result<(), CaptureError> {
+    let mut directories: Vec<String> = config.capture_note.split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect();
+    let file_name = directories.pop().unwrap_or_else(|| "inbox.md".to_string());
+    let note = PhysicalNote { directories, id: file_name.strip_suffix(".md").unwrap_or(&file_name).to_string() };
+
+    let now = chrono::Local::now();
+    let contents = if config.store.exists(&note) {
+        note.read_contents(config)?
+    } else {
+        render_template(config, config.capture_template.as_ref(), note.id.clone(), &now)?
+    };
+
+    let mut lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+    let mut text_lines = text.lines();
+    let timestamp = format!("{} {}", config.format_datetime(&now, &config.date_format), config.format_datetime(&now, &config.time_format));
+    lines.push(format!("- {timestamp} {}", text_lines.next().unwrap_or_default()));
+    // more lines are indented so that they stay in the same bullet
+    lines.extend(text_lines.map(|line| format!("  {line}")));
+    config.store.write(&note, &(lines.join("\n") + "\n"))?;
+
+    // reload the note if it is open in a buffer
+    api::command("checktime")?;
+    api::command(&format!(r#"echo "captured to {}""#, config.capture_note))?;
+    Ok(())
+}
+
+fn render_template(
+    config: &Config,
+    template: Option<&String>,
+    title: String,
+    now: &chrono::DateTime<chrono::Local>,
+) -> Result<String, std::io::Error> {
+    let template_contents = template.map(|template| read_wiki_file(config, &config.home_path.join(template))).transpose()?;
+    Ok(render_template_contents(config, template_contents.as_deref(), title, now))
+}
+
+/// Like [`render_template`], for a template that has already been read.
+fn render_template_contents(config: &Config, template_contents: Option<&str>, title: String, now: &chrono::DateTime<chrono::Local>) -> String {
+    let author = note_author(config);
+
+    let contents = if let Some(template_contents) = template_contents {
+        let substitutions = [
+            ("title", title),
+            ("date", config.format_datetime(now, &config.date_format)),
+            ("time", config.format_datetime(now, &config.time_format)),
+            ("author", author.clone().unwrap_or_default()),
+        ];
+        templates::substitute(template_contents, &substitutions)
+    } else {
         String::new()
     };
 
-    std::fs::write(&note_path, note_contents)?;
+    // templates can put the author wherever they want, but if they do not, it still goes in the frontmatter
+    match author {
+        Some(author) => {
+            let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+            frontmatter::add_field_if_missing(&lines, "author", &author).join("\n") + "\n"
+        }
+        None => contents,
+    }
+}
+
+/// Shows what a new note made from the template (a path relative to the home path) would look like, in a scratch buffer.
+pub fn preview_template(config: &Config, template: String) -> Result<(), PreviewTemplateError> {
+    let contents = render_template(config, Some(&template), "Example Title".to_string(), &chrono::Local::now())?;
+    open_scratch_buffer(config, contents.lines().map(ToString::to_string).collect())?;
+    Ok(())
+}
+
+/// Puts every problem with the configured templates and the templates in `template_directory` in the quickfix list: placeholders that
+/// would not be filled in, and frontmatter that would not parse once the placeholders are filled in.
+pub fn lint_templates(config: &Config) -> Result<(), LintTemplatesError> {
+    let template_names = template_names(config)?;
+
+    let mut quickfix_items = Vec::new();
+    for template in &template_names {
+        let template_path = config.home_path.join(template);
+        let problems = match read_wiki_file(config, &template_path) {
+            Ok(contents) => template_problems(config, &contents),
+            Err(e) => vec![(0, format!("cannot read template: {e}"))],
+        };
+
+        for (line_index, problem) in problems {
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(template_path.to_str().ok_or(NonUtf8Path)?)),
+                ("lnum", Object::from(line_index as i64 + 1)),
+                ("text", Object::from(problem)),
+            ])));
+        }
+    }
+
+    let problem_count = quickfix_items.len();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    api::command(&format!(r#"echo "{problem_count} problem(s) in {} template(s)""#, template_names.len()))?;
+    Ok(())
+}
+
+/// The configured templates and the templates in `template_directory`, relative to the home path.
+fn template_names(config: &Config) -> Result<BTreeSet<String>, std::io::Error> {
+    let mut template_names: BTreeSet<String> = config.default_template.iter().chain(&config.index_template).cloned().collect();
+    if let Some(template_directory) = &config.template_directory {
+        for entry in std::fs::read_dir(config.home_path.join(template_directory))? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                template_names.insert(format!("{template_directory}/{}", entry.file_name().to_string_lossy()));
+            }
+        }
+    }
+    Ok(template_names)
+}
+
+/// The problems in a template, each with the index of the line that it is on.
+fn template_problems(config: &Config, contents: &str) -> Vec<(usize, String)> {
+    let mut problems: Vec<(usize, String)> = templates::unknown_placeholders(contents)
+        .into_iter()
+        .map(|(line_index, placeholder)| (line_index, format!("unknown placeholder {{{placeholder}}}")))
+        .collect();
+    let rendered = render_template_contents(config, Some(contents), "Example Title".to_string(), &chrono::Local::now());
+    match markdown::parse_markdown(&rendered).map(|md| markdown::parse_frontmatter(&md)) {
+        Ok(Ok(_) | Err(markdown::InvalidFrontmatter::NoFrontmatter(_))) => {}
+        Ok(Err(e)) => problems.push((0, format!("invalid frontmatter: {e}"))),
+        Err(e) => problems.push((0, format!("invalid markdown: {e}"))),
+    }
+    problems
+}
+
+/// The author to put in new notes: the configured `author`, or the git user name if `author` is set to `git`.
+fn note_author(config: &Config) -> Option<String> {
+    match config.author.as_deref()? {
+        "git" => run_git(config, &["config", "user.name"]).ok().map(|name| name.trim().to_string()).filter(|name| !name.is_empty()),
+        author => Some(author.to_string()),
+    }
+}
+
+/// The note that a file in the wiki is, if it is a markdown file inside the home path, so that files that are found by a path (such as the
+/// index, templates, and link targets) are read and written through the note store like every other note.
+fn note_at_path(config: &Config, path: &Path) -> Option<PhysicalNote> {
+    let relative = path.strip_prefix(&config.home_path).ok()?;
+    if relative.extension()? != "md" {
+        return None;
+    }
+    Some(PhysicalNote {
+        directories: relative.parent()?.iter().map(|directory| directory.to_str().map(ToString::to_string)).collect::<Option<_>>()?,
+        id: relative.file_stem()?.to_str()?.to_string(),
+    })
+}
+
+fn wiki_file_exists(config: &Config, path: &Path) -> bool {
+    match note_at_path(config, path) {
+        Some(note) => config.store.exists(&note),
+        None => path.is_file(),
+    }
+}
+
+fn read_wiki_file(config: &Config, path: &Path) -> Result<String, std::io::Error> {
+    match note_at_path(config, path) {
+        Some(note) => config.store.read(&note),
+        None => std::fs::read_to_string(path),
+    }
+}
+
+/// Creates the file if it does not exist yet, along with the directories that it is in.
+fn write_wiki_file(config: &Config, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+    match note_at_path(config, path) {
+        Some(note) => config.store.write(&note, contents),
+        None => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)
+        }
+    }
+}
+
+pub fn open_index(config: &Config) -> Result<(), OpenIndexError> {
+    let index_path = config.home_path.join(&config.index_filename);
+    if !wiki_file_exists(config, &index_path) {
+        // the autogenerated sections get filled in by the autocommand when the index is opened
+        let contents = match &config.index_template {
+            Some(_) => render_template(config, config.index_template.as_ref(), "index".to_string(), &chrono::Local::now())?,
+            None => config.autogenerate_marker_style.restyle(autogenerate::DEFAULT_INDEX_TEMPLATE),
+        };
+        write_wiki_file(config, &index_path, &contents)?;
+    }
+    let index_path: &str = index_path.to_str().ok_or(NonUtf8Path)?;
+    api::cmd(&api::types::CmdInfos::builder().cmd("edit").args([index_path]).build(), &api::opts::CmdOpts::default())?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct InvalidFormat(String);
+impl std::error::Error for InvalidFormat {}
+impl std::fmt::Display for InvalidFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid format '{}'", self.0)
+    }
+}
+
+/// What the current date and time look like in `format`, for `setup_wizard` to show while the formats are being picked.
+pub fn format_example(config: &Config, format: String) -> Result<String, InvalidFormat> {
+    if format.trim().is_empty() || chrono::format::StrftimeItems::new(&format).any(|item| matches!(item, chrono::format::Item::Error)) {
+        return Err(InvalidFormat(format));
+    }
+    Ok(config.format_datetime(&chrono::Local::now(), &format))
+}
+
+error_union! {
+    pub enum FinishSetupWizardError {
+        ApiError(api::Error),
+        NonUtf8Path(NonUtf8Path),
+        OpenIndexError(OpenIndexError),
+        IoError(std::io::Error),
+    }
+}
+
+/// The last step of `setup_wizard`, which is given the answers as the config: makes the home directory, the default template if
+/// `with_template` is set, and the index, then opens the index next to a lua config snippet with the answers in it.
+pub fn finish_setup_wizard(config: &Config, link_style: String, with_template: bool) -> Result<(), FinishSetupWizardError> {
+    let mut settings = vec![
+        ("home_path", config.home_path.to_str().ok_or(NonUtf8Path)?.to_string()),
+        ("note_id_timestamp_format", config.note_id_timestamp_format.clone()),
+        ("date_format", config.date_format.clone()),
+        ("time_format", config.time_format.clone()),
+        ("link_style", link_style),
+    ];
+    if with_template {
+        let template_path = config.home_path.join(wizard::DEFAULT_TEMPLATE_PATH);
+        // a template that is already there was probably made by hand and should not be lost
+        if !wiki_file_exists(config, &template_path) {
+            write_wiki_file(config, &template_path, wizard::DEFAULT_NOTE_TEMPLATE)?;
+        }
+        settings.push(("default_template", wizard::DEFAULT_TEMPLATE_PATH.to_string()));
+        settings.push(("template_directory", wizard::TEMPLATE_DIRECTORY.to_string()));
+    }
+
+    open_index(config)?;
+    api::command("vsplit")?;
+    let snippet = wizard::config_snippet(&settings);
+    let buffer = open_scratch_buffer(config, snippet.lines().map(ToString::to_string).collect())?;
+    api::set_option_value("filetype", "lua", &OptionOpts::builder().scope(OptionScope::Local).buffer(buffer).build())?;
+    api::command(r#"echo "wiki is set up, paste this into your config to keep these settings""#)?;
+    Ok(())
+}
+
+/// Opens the index file of a subdirectory, which is named `index_filename` like the index of the whole wiki. If it does not exist, it is
+/// made from `directory_index_template` (with the directory path as the title) or else a template that indexes the directory.
+pub fn open_directory_index(config: &Config, directories: Vec<String>) -> Result<(), OpenIndexError> {
+    if directories.is_empty() {
+        return open_index(config);
+    }
+    let mut index_path = config.home_path.clone();
+    index_path.extend(&directories);
+    index_path.push(&config.index_filename);
+    if !wiki_file_exists(config, &index_path) {
+        let contents = match &config.directory_index_template {
+            Some(_) => render_template(config, config.directory_index_template.as_ref(), directories.join("/"), &chrono::Local::now())?,
+            None => config.autogenerate_marker_style.restyle(&autogenerate::default_directory_index_template(&directories)),
+        };
+        write_wiki_file(config, &index_path, &contents)?;
+    }
+    let index_path: &str = index_path.to_str().ok_or(NonUtf8Path)?;
+    api::cmd(&api::types::CmdInfos::builder().cmd("edit").args([index_path]).build(), &api::opts::CmdOpts::default())?;
+
+    Ok(())
+}
+
+pub fn new_note_and_insert_link(config: &Config, template: Option<String>, directories: Vec<String>, title: String) -> Result<(), InsertLinkError> {
+    let new_note = new_note(config, template, directories, false, title)?;
+    insert_link_at_cursor(config, &new_note, None)?;
+    Ok(())
+}
+
+pub fn insert_link_to_path_at_cursor_or_create(
+    config: &Config,
+    link_to: Option<String>,
+    link_text: Option<String>,
+    title: Option<String>,
+) -> Result<(), InsertLinkError> {
+    let n;
+    let note = match link_to {
+        Some(link_to_path) => {
+            let path = Path::new(&link_to_path);
+            n = Note::Physical(PhysicalNote::parse_from_filepath(config, path)?);
+            Some(&n)
+        }
+        None => None,
+    };
+
+    insert_link_at_cursor_or_create(config, note, link_text, title)?;
+
+    Ok(())
+}
+
+/// Inserts a link to `link_to`, or if that is `None`, to a new note called `title`, which is asked for on the lua side. Nothing is
+/// inserted if there is neither, such as when the prompt for the title was cancelled.
+pub fn insert_link_at_cursor_or_create(
+    config: &Config,
+    link_to: Option<&Note>,
+    link_text: Option<String>,
+    title: Option<String>,
+) -> Result<(), InsertLinkError> {
+    let note = match (link_to, title) {
+        (Some(link_to), _) => link_to,
+        // TODO: figure out a cleaner way to pass these arguments instead of assuming a default
+        (None, Some(title)) => &create_note(config, None, Vec::new(), title, false)?,
+        (None, None) => return Ok(()),
+    };
+    insert_link_at_cursor(config, note, link_text)?;
+    Ok(())
+}
+
+/// Replaces the last visual selection with a link whose text is the selection. If `link_to` is `None`, the link goes to a new note that
+/// is titled after the selection.
+pub fn insert_link_around_selection(config: &Config, link_to: Option<String>) -> Result<(), InsertLinkError> {
+    let mut current_buf = api::get_current_buf();
+    let lines = get_buffer_lines(&current_buf)?;
+
+    // getpos() gives 1 indexed lines and columns, and line 0 for a mark that is not set because there has not been a visual selection in
+    // the buffer yet
+    let start: Vec<usize> = api::eval(r#"getpos("'<")"#)?;
+    let end: Vec<usize> = api::eval(r#"getpos("'>")"#)?;
+    let ((start_line, start_col), (end_line, end_col)) = match (start.get(1..3), end.get(1..3)) {
+        (Some(&[start_line, start_col]), Some(&[end_line, end_col])) if start_line > 0 && start_line <= end_line && end_line <= lines.len() => {
+            ((start_line - 1, start_col.saturating_sub(1)), (end_line - 1, end_col.saturating_sub(1)))
+        }
+        _ => {
+            api::command(r#"echo "no visual selection to turn into a link""#)?;
+            return Ok(());
+        }
+    };
+
+    let first_line = &lines[start_line];
+    let last_line = &lines[end_line];
+    let start_col = start_col.min(first_line.len());
+    // the end column is the first byte of the last selected character, or past the end of the line in linewise visual mode
+    let end_byte = last_line.get(end_col..).and_then(|rest| rest.chars().next()).map_or(last_line.len(), |c| end_col + c.len_utf8());
+    if start_line == end_line && start_col > end_byte {
+        api::command(r#"echo "no visual selection to turn into a link""#)?;
+        return Ok(());
+    }
+
+    let selected_text = if start_line == end_line {
+        first_line[start_col..end_byte].to_string()
+    } else {
+        std::iter::once(&first_line[start_col..])
+            .chain(lines[start_line + 1..end_line].iter().map(String::as_str))
+            .chain(std::iter::once(&last_line[..end_byte]))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let link_to = match link_to {
+        Some(link_to_path) => PhysicalNote::parse_from_filepath(config, Path::new(&link_to_path))?,
+        None => {
+            let title = selected_text.split_whitespace().collect::<Vec<_>>().join(" ");
+            match create_note(config, None, Vec::new(), title, false)? {
+                Note::Physical(note) => note,
+                Note::Scratch(_) => Err(CannotLinkToScratchNote)?,
+            }
+        }
+    };
+
+    let current_note = Note::get_current_note(config)?;
+    let link = links::format_link(config, &current_note, &link_to.path(config), &selected_text, None)?;
+
+    let replaced = format!("{}{}{}", &first_line[..start_col], link, &last_line[end_byte..]);
+    current_buf.set_lines(start_line..end_line + 1, false, replaced.split('\n'))?;
+
+    Ok(())
+}
+
+pub fn insert_link_at_cursor(config: &Config, link_to: &Note, link_text: Option<String>) -> Result<(), InsertLinkError> {
+    match link_to {
+        Note::Physical(link_to) => {
+            let link_text = match link_text {
+                Some(lt) => lt,
+                None => {
+                    let frontmatter = link_to
+                        .read_contents(config)
+                        .ok()
+                        .and_then(|contents| markdown::parse_markdown(&contents).ok())
+                        .and_then(|markdown| markdown::parse_frontmatter(&markdown).ok())
+                        .unwrap_or_default();
+                    let from_callback = run_format_callback(config.format_link_text.as_ref(), || note_meta(config, link_to, Some(&frontmatter)));
+                    let alias = if config.alias_as_link_text { frontmatter.aliases.into_iter().next() } else { None };
+                    from_callback.or(alias).or(frontmatter.title).unwrap_or_default()
+                }
+            };
+
+            let current_note = Note::get_current_note(config)?;
+            let link = links::format_link(config, &current_note, &link_to.path(config), &link_text, None)?;
+            // TODO: this is a workaround because calling api::put directly causes nvim to crash and i cannot figure out why
+            api::command(&format!(r##"lua vim.api.nvim_put({{ "{link}" }}, 'c', false, true)"##))?;
+            // api::put([link].into_iter(), api::types::RegisterType::Charwise, false, true)?;
+
+            Ok(())
+        }
+        Note::Scratch(_) => Err(CannotLinkToScratchNote)?,
+    }
+}
+
+/// Opens the tag index. If `tag_index_note` is configured, the tag index is kept in that note (created if it does not exist yet) as an
+/// autogenerated section; otherwise it is shown in a scratch buffer. If `tag` is given, only notes with that tag or tags nested inside it
+/// are shown, always in a scratch buffer.
+pub fn open_tag_index(config: &Config, tag: Option<String>) -> Result<(), TagIndexError> {
+    if let (Some(tag_index_note), None) = (&config.tag_index_note, &tag) {
+        let path = config.home_path.join(tag_index_note);
+        if !wiki_file_exists(config, &path) {
+            write_wiki_file(config, &path, &config.autogenerate_marker_style.restyle(tag_index::TAG_INDEX_NOTE_TEMPLATE))?;
+        }
+        // the autogenerated section gets filled in by the autocommand when the note is opened
+        api::cmd(&CmdInfos::builder().cmd("edit").args([path.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+        return Ok(());
+    }
+
+    // TODO: figure out how to get appropriate keymappings on this file
+    let filter = tag.as_deref().map(Tag::parse_from_str);
+    let buffer = find_or_create_named_buffer(&tag_index_buffer_name(filter.as_ref()))?;
+    api::set_current_buf(&buffer)?;
+    apply_buffer_options(config)?;
+    fill_tag_index_buffer(config, buffer, filter.as_ref())?;
+    fold_entries_in_current_window()?;
+
+    Ok(())
+}
+
+const TAG_INDEX_BUFFER_NAME: &str = "wikiplugin://tag-index";
+
+/// Each filter gets its own buffer, named after the tag, so that refreshing can tell which filter each open index was made with.
+fn tag_index_buffer_name(filter: Option<&Tag>) -> String {
+    match filter {
+        Some(filter) => format!("{TAG_INDEX_BUFFER_NAME}/{filter}"),
+        None => TAG_INDEX_BUFFER_NAME.to_string(),
+    }
+}
+
+/// Regenerates every open tag index buffer in place.
+pub fn refresh_tag_index(config: &Config) -> Result<(), TagIndexError> {
+    for buffer in api::list_bufs() {
+        let name = buffer.get_name()?;
+        let Some(name) = name.to_str() else { continue };
+        let filter = if name == TAG_INDEX_BUFFER_NAME {
+            None
+        } else if let Some(tag) = name.strip_prefix(TAG_INDEX_BUFFER_NAME).and_then(|rest| rest.strip_prefix('/')) {
+            Some(Tag::parse_from_str(tag))
+        } else {
+            continue;
+        };
+        fill_tag_index_buffer(config, buffer, filter.as_ref())?;
+    }
+    Ok(())
+}
+
+fn fill_tag_index_buffer(config: &Config, buffer: api::Buffer, filter: Option<&Tag>) -> Result<(), TagIndexError> {
+    let entries = tag_index_entries(config, filter)?;
+    let lines = tag_index::render_tag_index(&entries, config.tag_index_style, |entry| {
+        let mut entry_lines =
+            vec![format!("- {}[{}]({})", note_icon_prefix(config, &entry.note), entry.title, entry.note.path(config).to_str().ok_or(NonUtf8Path)?)];
+        entry_lines.extend(folded_entry_body(note_excerpt(config, &entry.note)));
+        Ok::<_, NonUtf8Path>(entry_lines)
+    })?;
+    set_view_lines(buffer, lines)?;
+    Ok(())
+}
+
+/// Reads the title and tags of every note, keeping only the notes that match `filter` if there is one. Notes that cannot be read or parsed
+/// are skipped.
+fn tag_index_entries(config: &Config, filter: Option<&Tag>) -> Result<Vec<tag_index::TagIndexEntry>, ListAllPhysicalNotesError> {
+    let mut entries = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let Some(frontmatter) = note
+            .read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+        else {
+            continue;
+        };
+        entries.push(tag_index::TagIndexEntry {
+            title: frontmatter.title.unwrap_or_default(),
+            tags: frontmatter.tags,
+            note,
+        });
+    }
+    Ok(match filter {
+        Some(filter) => tag_index::filter_entries(entries, filter),
+        None => entries,
+    })
+}
+
+/// Lists the path, id, and title of every note with `tag` or a tag nested inside it.
+pub(crate) fn notes_with_tag(config: &Config, tag: String) -> Result<Vec<[(&'static str, String); 3]>, TagIndexError> {
+    let mut notes = Vec::new();
+    for entry in tag_index_entries(config, Some(&Tag::parse_from_str(&tag)))? {
+        notes.push([
+            ("path", entry.note.path(config).to_str().ok_or(NonUtf8Path)?.to_string()),
+            ("id", entry.note.id),
+            ("title", entry.title),
+        ]);
+    }
+    Ok(notes)
+}
+
+pub fn find_similar_notes(config: &Config) -> Result<(), FindSimilarNotesError> {
+    let current_note = Note::get_current_note(config)?;
+    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+    let current_title =
+        markdown::parse_frontmatter(&current_md).ok().and_then(|frontmatter| frontmatter.title).unwrap_or_default();
+
+    let mut others = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        if current_note.as_physical() == Some(&note) {
+            continue;
+        }
+        let Some(md) = note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok()) else {
+            continue;
+        };
+        let title = markdown::parse_frontmatter(&md).ok().and_then(|frontmatter| frontmatter.title).unwrap_or_default();
+        others.push((note, title, markdown::plain_text(&md)));
+    }
+
+    let corpus = others.iter().map(|(_, _, text)| text.clone()).collect::<Vec<_>>();
+    let document_frequencies = tfidf::document_frequencies(&corpus);
+    let current_vector = tfidf::tfidf_vector(&markdown::plain_text(&current_md), &document_frequencies, corpus.len());
+
+    let mut scored = others
+        .into_iter()
+        .map(|(note, title, text)| {
+            let similarity = tfidf::cosine_similarity(&current_vector, &tfidf::tfidf_vector(&text, &document_frequencies, corpus.len()));
+            (similarity, note, title)
+        })
+        .filter(|(similarity, _, _)| *similarity > 0.0)
+        .collect::<Vec<_>>();
+    scored.sort_by(|(a_similarity, a_note, _), (b_similarity, b_note, _)| b_similarity.total_cmp(a_similarity).then_with(|| a_note.cmp(b_note)));
+
+    let mut lines = vec![format!("# notes similar to {current_title}"), "".to_string()];
+    for (similarity, note, title) in scored.into_iter().take(20) {
+        lines.push(format!("- [{}]({}) ({:.2})", title, note.path(config).to_str().ok_or(NonUtf8Path)?, similarity));
+        lines.extend(folded_entry_body(note_excerpt(config, &note)));
+    }
+
+    open_scratch_buffer(config, lines)?;
+    fold_entries_in_current_window()?;
+
+    Ok(())
+}
+
+/// Returns the current note along with the target and text of the link that the cursor refers to (see [`markdown::pick_link`]).
+fn link_under_cursor(config: &Config, count: usize) -> Result<(Note, String, String), LinkUnderCursorError> {
+    let current_note = Note::get_current_note(config)?;
+    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+
+    let cursor_byte_index: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) + col(".") - 1 - 1"#)?;
+    let line_start: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) - 1"#)?;
+    let line_length: usize = nvim_oxi::api::eval(r#"strlen(getline("."))"#)?;
+
+    let spans = markdown::get_all_link_spans(&current_md);
+    let span = markdown::pick_link(&spans, cursor_byte_index, line_start..line_start + line_length, count).ok_or(NotOnALink)?;
+
+    Ok((current_note, span.target.clone(), span.text.clone()))
+}
+
+/// Whether following the link under the cursor would need to ask about creating its target, which happens on the lua side with
+/// `vim.ui.select` before [`follow_link`] is called with the answer.
+pub fn should_ask_to_create_link_target(config: &Config, count: usize) -> Result<bool, FollowLinkError> {
+    if config.create_missing_link_targets != CreateMissingLinkTargets::Ask {
+        return Ok(false);
+    }
+    let (current_note, link_path, _) = link_under_cursor(config, count)?;
+    if links::is_external_link(config, &link_path) {
+        return Ok(false);
+    }
+    let target = links::resolve_link_path(config, &current_note, &link_path)?;
+    Ok(target.extension().is_none_or(|extension| extension == "md") && !wiki_file_exists(config, &target))
+}
+
+/// `create` is the answer to [`should_ask_to_create_link_target`], and is only used if `create_missing_link_targets` says to ask.
+pub fn follow_link(config: &Config, count: usize, create: bool) -> Result<(), FollowLinkError> {
+    let (current_note, link_path, link_text) = link_under_cursor(config, count)?;
+
+    if links::is_external_link(config, &link_path) {
+        return open_externally(config, &link_path);
+    }
+
+    let mut new_note_path = links::resolve_link_path(config, &current_note, &link_path)?;
+
+    // if the link was resolved by name and more than one note has that name, ask which one was meant
+    let candidates = links::find_notes_by_name(config, links::split_anchor(&link_path).0);
+    if candidates.len() > 1 && candidates.contains(&new_note_path) {
+        let prompt = std::iter::once(format!("more than one note is named '{}':", links::split_anchor(&link_path).0));
+        let choices = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, path)| format!("{}. {}", i + 1, path.strip_prefix(&config.home_path).unwrap_or(path).display()));
+        let choice = api::call_function::<_, i64>("inputlist", Array::from_iter([Array::from_iter(prompt.chain(choices))]))?;
+        match usize::try_from(choice).ok().and_then(|choice| candidates.get(choice.checked_sub(1)?)) {
+            Some(chosen) => new_note_path = chosen.clone(),
+            None => return Ok(()),
+        }
+    }
+
+    // things like pdfs and images should be opened in whatever application the system uses for them instead of being edited as text
+    if new_note_path.extension().is_some_and(|extension| extension != "md") {
+        return open_externally(config, new_note_path.to_str().ok_or(NonUtf8Path)?);
+    }
+
+    if !wiki_file_exists(config, &new_note_path) {
+        let create = match config.create_missing_link_targets {
+            CreateMissingLinkTargets::Always => true,
+            CreateMissingLinkTargets::Ask => create,
+            CreateMissingLinkTargets::Never => false,
+        };
+
+        if create {
+            let contents = render_template(config, config.default_template.as_ref(), link_text, &chrono::Local::now())?;
+            write_wiki_file(config, &new_note_path, &contents)?;
+        }
+    }
+
+    api::cmd(
+        &api::types::CmdInfos::builder().cmd("edit").args([new_note_path.to_str().ok_or(NonUtf8Path)?]).build(),
+        &api::opts::CmdOpts::default(),
+    )?;
+    if let Some(current_path) = current_note.path(config) {
+        navigation::with_history(config, |history| history.visit(current_path));
+    }
+
+    if let (_, Some(anchor)) = links::split_anchor(&link_path) {
+        let anchor = markdown::slugify(anchor);
+        let target_md = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))?;
+        if let Some((_, _, position)) = markdown::get_headings(&target_md).into_iter().find(|(_, text, _)| markdown::slugify(text) == anchor) {
+            api::get_current_win().set_cursor(position.start.line, 0)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the lines to show in a preview of the note that the link under the cursor points to: the title, followed by the start of the
+/// body (or the section that the link points to). The floating window itself is opened on the lua side.
+pub fn preview_link(config: &Config, count: usize) -> Result<Vec<String>, PreviewLinkError> {
+    let (current_note, link_path, _) = link_under_cursor(config, count)?;
+
+    if links::is_external_link(config, &link_path) {
+        return Ok(vec![link_path]);
+    }
+
+    let target_path = links::resolve_link_path(config, &current_note, &link_path)?;
+    let Ok(contents) = read_wiki_file(config, &target_path) else {
+        return Ok(vec![format!("{} does not exist", target_path.display())]);
+    };
+    let md = markdown::parse_markdown(&contents)?;
+
+    let mut preview = Vec::new();
+    if let Some(title) = markdown::parse_frontmatter(&md).ok().and_then(|frontmatter| frontmatter.title) {
+        preview.push(format!("# {title}"));
+        preview.push("".to_string());
+    }
+    let (_, anchor) = links::split_anchor(&link_path);
+    preview.extend(markdown::excerpt(&contents, &md, anchor, config.preview_line_count));
+
+    Ok(preview)
+}
+
+/// Moves the cursor to the start of the `count`th next link in the current buffer.
+pub fn goto_next_link(count: usize) -> Result<(), GotoLinkError> {
+    goto_link(count, true)
+}
+
+/// Moves the cursor to the start of the `count`th previous link in the current buffer.
+pub fn goto_prev_link(count: usize) -> Result<(), GotoLinkError> {
+    goto_link(count, false)
+}
+
+fn goto_link(count: usize, forward: bool) -> Result<(), GotoLinkError> {
+    // use the buffer instead of the file so that the offsets match even if there are unsaved changes
+    let md = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))?;
+    let cursor_byte_index: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) + col(".") - 1 - 1"#)?;
+
+    let spans = markdown::get_all_link_spans(&md);
+    let target = if forward {
+        spans.iter().filter(|span| span.start_offset > cursor_byte_index).nth(count.max(1) - 1)
+    } else {
+        spans.iter().rev().filter(|span| span.end_offset <= cursor_byte_index).nth(count.max(1) - 1)
+    };
+
+    match target {
+        Some(span) => {
+            let line: usize = api::call_function("byte2line", Array::from_iter([Object::from(span.start_offset as i64 + 1)]))?;
+            let line_start: usize = api::call_function("line2byte", Array::from_iter([Object::from(line as i64)]))?;
+            api::get_current_win().set_cursor(line, span.start_offset + 1 - line_start)?;
+        }
+        None => api::command(&format!(r#"echo "no {} link""#, if forward { "next" } else { "previous" }))?,
+    }
+
+    Ok(())
+}
+
+/// Shows whether the image under the cursor exists, and if it does, its dimensions and file size.
+pub fn show_image_info_under_cursor(config: &Config) -> Result<(), ImageInfoError> {
+    let current_note = Note::get_current_note(config)?;
+    let md = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))?;
+    let cursor_byte_index: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) + col(".") - 1 - 1"#)?;
+    let line_start: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) - 1"#)?;
+    let line_length: usize = nvim_oxi::api::eval(r#"strlen(getline("."))"#)?;
+
+    let spans: Vec<_> = markdown::get_all_images(&md)
+        .into_iter()
+        .map(|(url, alt, position)| markdown::LinkSpan {
+            target: url,
+            text: alt,
+            start_offset: position.start.offset,
+            end_offset: position.end.offset,
+        })
+        .collect();
+    let span = markdown::pick_link(&spans, cursor_byte_index, line_start..line_start + line_length, 0).ok_or(NotOnAnImage)?;
+
+    let info = if links::is_external_link(config, &span.target) {
+        format!("{}: external image", span.target)
+    } else {
+        let path = links::resolve_link_path(config, &current_note, &span.target)?;
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let dimensions = match images::image_dimensions(&bytes) {
+                    Some((width, height)) => format!("{width}x{height}"),
+                    None => "unknown dimensions".to_string(),
+                };
+                format!("{}: {dimensions}, {}", path.display(), images::format_size(bytes.len() as u64))
+            }
+            Err(_) => format!("{}: broken (file does not exist)", path.display()),
+        }
+    };
+    api::notify(&info, api::types::LogLevel::Info, &Dictionary::new())?;
+
+    Ok(())
+}
+
+/// Puts every broken image and every image bigger than `max_image_size_kb` in the quickfix list.
+pub fn lint_images(config: &Config) -> Result<(), LintImagesError> {
+    let mut quickfix_items = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let Ok(contents) = note.read_contents(config) else {
+            continue;
+        };
+        let note_path = note.path(config);
+        for (line, column, problem) in image_problems(config, &Note::Physical(note), &contents) {
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(note_path.to_str().ok_or(NonUtf8Path)?)),
+                ("lnum", Object::from(line as i64)),
+                ("col", Object::from(column as i64)),
+                ("text", Object::from(problem)),
+            ])));
+        }
+    }
+
+    let problem_count = quickfix_items.len();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    api::command(&format!(r#"echo "{problem_count} image problem(s)""#))?;
+
+    Ok(())
+}
+
+/// The broken images in a note with these contents and the ones bigger than `max_image_size_kb`, with the line and column of each. Notes that
+/// do not parse have none.
+fn image_problems(config: &Config, note: &Note, contents: &str) -> Vec<(usize, usize, String)> {
+    let Ok(md) = markdown::parse_markdown(contents) else {
+        return Vec::new();
+    };
+    markdown::get_all_images(&md)
+        .into_iter()
+        .filter(|(url, _, _)| !links::is_external_link(config, url))
+        .filter_map(|(url, _, position)| {
+            let image_path = links::resolve_link_path(config, note, &url).ok()?;
+            let problem = match std::fs::metadata(&image_path) {
+                Ok(metadata) if metadata.len() > config.max_image_size_kb * 1024 => format!(
+                    "image is {} (more than {})",
+                    images::format_size(metadata.len()),
+                    images::format_size(config.max_image_size_kb * 1024)
+                ),
+                Ok(_) => return None,
+                Err(_) => format!("broken image: {}", image_path.display()),
+            };
+            Some((position.start.line, position.start.column, problem))
+        })
+        .collect()
+}
+
+/// If the link `url` in `note` points at a heading or block in a note (possibly `note` itself) that does not have it, returns the anchor and
+/// the anchor in the target that is closest to it. Links that are broken altogether are left to the other checks. `contents` are the
+/// contents of `note`, for links to its own headings.
+fn dead_anchor(config: &Config, note: &Note, contents: &str, url: &str) -> Option<(String, Option<String>)> {
+    let (path, anchor) = links::split_anchor(url);
+    let anchor = anchor.filter(|anchor| !anchor.is_empty())?;
+    let contents = if path.is_empty() {
+        contents.to_string()
+    } else {
+        let target = links::resolve_link_path(config, note, url).ok().filter(|target| target.extension() == Some("md".as_ref()))?;
+        PhysicalNote::parse_from_filepath(config, &target).ok()?.read_contents(config).ok()?
+    };
+    let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+    let target_anchors = anchors::note_anchors(&lines);
+    if anchors::has_anchor(&target_anchors, anchor) {
+        return None;
+    }
+    Some((anchor.to_string(), anchors::closest_anchor(&target_anchors, anchor).map(ToString::to_string)))
+}
+
+fn dead_anchor_problem(url: &str, anchor: &str, closest: Option<&str>) -> String {
+    match closest {
+        Some(closest) => format!("dead anchor #{anchor} in link to {url} (closest: #{closest})"),
+        None => format!("dead anchor #{anchor} in link to {url}"),
+    }
+}
+
+/// The links in a note with these contents whose targets do not have the heading or block that they point at, with the line and column of
+/// each. Notes that do not parse have none.
+fn anchor_problems(config: &Config, note: &Note, contents: &str) -> Vec<(usize, usize, String)> {
+    let Ok(md) = markdown::parse_markdown(contents) else {
+        return Vec::new();
+    };
+    markdown::get_all_link_and_image_urls(&md)
+        .into_iter()
+        .filter(|(url, _)| !links::is_external_link(config, url))
+        .filter_map(|(url, position)| {
+            let (anchor, closest) = dead_anchor(config, note, contents, &url)?;
+            Some((position.start.line, position.start.column, dead_anchor_problem(&url, &anchor, closest.as_deref())))
+        })
+        .collect()
+}
+
+/// Puts every link whose target exists but does not have the heading or block that the link points at in the quickfix list, along with
+/// the closest anchor that it does have. [`fix_anchor_under_cursor`] changes a link to that anchor.
+pub fn lint_anchors(config: &Config) -> Result<(), LintAnchorsError> {
+    let mut quickfix_items = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let Ok(contents) = note.read_contents(config) else {
+            continue;
+        };
+        let note_path = note.path(config);
+        for (line, column, problem) in anchor_problems(config, &Note::Physical(note), &contents) {
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(note_path.to_str().ok_or(NonUtf8Path)?)),
+                ("lnum", Object::from(line as i64)),
+                ("col", Object::from(column as i64)),
+                ("text", Object::from(problem)),
+            ])));
+        }
+    }
+
+    let problem_count = quickfix_items.len();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    api::command(&format!(r#"echo "{problem_count} dead anchor(s)""#))?;
+
+    Ok(())
+}
+
+/// Points the link under the cursor at the anchor in its target that is closest to the dead one that it has.
+pub fn fix_anchor_under_cursor(config: &Config) -> Result<(), LinkUnderCursorError> {
+    let (current_note, link_path, _) = link_under_cursor(config, 0)?;
+    let contents = current_note.read_contents(config)?;
+    let Some((anchor, closest)) = dead_anchor(config, &current_note, &contents, &link_path) else {
+        api::command(r#"echo "the anchor of this link is fine""#)?;
+        return Ok(());
+    };
+    let Some(closest) = closest else {
+        api::command(&format!(r#"echo "the target of this link has nothing like #{anchor} to point at""#))?;
+        return Ok(());
+    };
+
+    let (path, _) = links::split_anchor(&link_path);
+    let new_link_path = format!("{path}#{closest}");
+    let line = api::get_current_line()?;
+    // the link is on the cursor line, since that is where link_under_cursor looks for it
+    api::set_current_line(line.replacen(&link_path, &new_link_path, 1))?;
+    api::command(&format!(r#"echo "changed #{anchor} to #{closest}""#))?;
+    Ok(())
+}
+
+/// Finds the problems that the pre-commit hook refuses commits for in the staged versions of the given files: links and images that point to
+/// files that do not exist, links to headings or blocks that are not there, images bigger than `max_image_size_kb`, and in templates, what
+/// [`lint_templates`] finds. Files outside of the wiki are skipped. Returns one `path:line:column: problem` line per problem.
+pub fn check_staged_files(config: &Config, paths: Vec<String>) -> Result<Vec<String>, CheckStagedFilesError> {
+    let template_names = template_names(config)?;
+    let mut problems = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        let Ok(relative) = path.strip_prefix(&config.home_path) else {
+            continue;
+        };
+        if path.extension() != Some("md".as_ref()) {
+            continue;
+        }
+        let relative = relative.to_str().ok_or(NonUtf8Path)?.replace('\\', "/");
+        // what is being committed, which is not necessarily what is in the working tree
+        let contents = run_git(config, &["show", format!(":./{relative}").as_str()])?;
+
+        if template_names.contains(&relative) {
+            for (line_index, problem) in template_problems(config, &contents) {
+                problems.push(format!("{}:{}:1: {problem}", path.display(), line_index + 1));
+            }
+            continue;
+        }
+
+        let note = Note::Physical(PhysicalNote::parse_from_filepath(config, &path)?);
+        let md = markdown::parse_markdown(&contents)?;
+        let mut note_problems: Vec<(usize, usize, String)> = markdown::get_all_link_and_image_urls(&md)
+            .into_iter()
+            .filter(|(url, _)| !links::is_external_link(config, url))
+            .filter(|(url, _)| !links::resolve_link_path(config, &note, url).is_ok_and(|target| wiki_file_exists(config, &target)))
+            .map(|(url, position)| (position.start.line, position.start.column, format!("broken link to {url}")))
+            .collect();
+        for (line, column, problem) in image_problems(config, &note, &contents) {
+            // a missing image is a broken link already
+            if !note_problems.iter().any(|(other_line, other_column, _)| (*other_line, *other_column) == (line, column)) {
+                note_problems.push((line, column, problem));
+            }
+        }
+        note_problems.extend(anchor_problems(config, &note, &contents));
+
+        note_problems.sort();
+        problems.extend(note_problems.into_iter().map(|(line, column, problem)| format!("{}:{line}:{column}: {problem}", path.display())));
+    }
+    Ok(problems)
+}
+
+// the first line after the shebang marks the hook as one that can be overwritten when the hooks are installed again
+const PRE_COMMIT_HOOK: &str = r#"#!/bin/sh
+# installed by wikiplugin: refuses commits that add problems to the wiki
+files=$(git diff --cached --name-only --diff-filter=ACMR -- '*.md')
+[ -z "$files" ] && exit 0
+WIKIPLUGIN_CHECK_FILES="$files" exec nvim --headless -c "lua require('wikiplugin').check_for_commit()"
+"#;
+
+/// Writes a pre-commit hook into the repository that the wiki is in, which checks the staged notes and templates with
+/// [`check_staged_files`] in a headless neovim (so the plugin has to be set up in the normal neovim config).
+pub fn install_git_hooks(config: &Config) -> Result<(), InstallGitHooksError> {
+    let hooks_dir = config.home_path.join(run_git(config, &["rev-parse", "--git-path", "hooks"])?.trim());
+    let hook_path = hooks_dir.join("pre-commit");
+    if let Ok(existing) = std::fs::read_to_string(&hook_path) {
+        if existing.lines().nth(1) != PRE_COMMIT_HOOK.lines().nth(1) {
+            Err(HookAlreadyExists(hook_path.clone()))?;
+        }
+    }
+
+    std::fs::create_dir_all(&hooks_dir)?;
+    std::fs::write(&hook_path, PRE_COMMIT_HOOK)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    api::command(&format!(r#"echo "installed {}""#, hook_path.display()))?;
+
+    Ok(())
+}
+
+/// Goes back to the note that was open before the last `follow_link`.
+pub fn go_back(config: &Config) -> Result<(), ApiErrorOrNonUtf8Path> {
+    navigate(config, navigation::NavigationHistory::go_back, "no previous note")
+}
+
+/// Undoes a `go_back`.
+pub fn go_forward(config: &Config) -> Result<(), ApiErrorOrNonUtf8Path> {
+    navigate(config, navigation::NavigationHistory::go_forward, "no next note")
+}
+
+fn navigate(
+    config: &Config,
+    step: fn(&mut navigation::NavigationHistory, Option<PathBuf>) -> Option<PathBuf>,
+    empty_message: &str,
+) -> Result<(), ApiErrorOrNonUtf8Path> {
+    let current_path = Note::get_current_note(config).ok().and_then(|note| note.path(config));
+    match navigation::with_history(config, |history| step(history, current_path)) {
+        Some(target) => {
+            api::cmd(&CmdInfos::builder().cmd("edit").args([target.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+        }
+        None => api::command(&format!(r#"echo "{empty_message}""#))?,
+    }
+    Ok(())
+}
+
+/// Opens every note linked to between lines `start` and `end` (1-indexed, inclusive). With the `args` open mode, the notes replace the
+/// argument list and the first one is edited; with `tabs`, each note is opened in a new tab. External links, links to files that are not
+/// notes, and links to notes that do not exist are skipped.
+pub fn follow_links_in_range(config: &Config, start: usize, end: usize, open_mode: String) -> Result<(), FollowLinksInRangeError> {
+    let current_note = Note::get_current_note(config)?;
+    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+
+    let range_start: usize = api::eval(&format!("line2byte({start}) - 1"))?;
+    let range_end: usize = api::eval(&format!("line2byte({end}) - 1 + strlen(getline({end}))"))?;
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for span in markdown::get_all_link_spans(&current_md) {
+        if !(range_start..=range_end).contains(&span.start_offset) || links::is_external_link(config, &span.target) {
+            continue;
+        }
+        let Ok(path) = links::resolve_link_path(config, &current_note, &span.target) else { continue };
+        if path.extension().is_some_and(|extension| extension == "md") && wiki_file_exists(config, &path) && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        api::command(r#"echo "no links to open""#)?;
+        return Ok(());
+    }
+    let paths = paths.iter().map(|path| path.to_str().ok_or(NonUtf8Path)).collect::<Result<Vec<_>, _>>()?;
+
+    match open_mode.as_str() {
+        "args" => {
+            api::cmd(&CmdInfos::builder().cmd("args").args(paths).build(), &CmdOpts::builder().build())?;
+        }
+        "tabs" => {
+            for path in paths {
+                api::cmd(&CmdInfos::builder().cmd("tabedit").args([path]).build(), &CmdOpts::builder().build())?;
+            }
+        }
+        _ => Err(InvalidOpenMode(open_mode))?,
+    }
+    if let Some(current_path) = current_note.path(config) {
+        navigation::with_history(config, |history| history.visit(current_path));
+    }
+
+    Ok(())
+}
+
+fn open_externally(config: &Config, target: &str) -> Result<(), FollowLinkError> {
+    match &config.external_opener {
+        Some(opener) => {
+            std::process::Command::new(opener).arg(target).spawn()?;
+        }
+        None => {
+            let error: Option<String> =
+                api::call_function("luaeval", Array::from_iter([Object::from("select(2, vim.ui.open(_A))"), Object::from(target)]))?;
+            if let Some(error) = error {
+                Err(OpenExternallyFailed(error))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the note at `path` by moving it into the trash, where [`restore_note`] can get it back from, or for good with `permanent`. Asking
+/// whether to go ahead happens on the lua side with `vim.ui.select`, before this is called, if [`should_confirm`] says to. `path` is
+/// absolute or relative to the home path and has to be in the wiki, and the buffer open on it, if there is one, is wiped.
+///
+/// The links to the note from other notes in the wiki are then dealt with the way `deleted_note_links` says: left alone and put in the
+/// quickfix list, struck through, or replaced by their text.
+pub fn delete_note(config: &Config, path: String, permanent: bool) -> Result<(), DeleteNoteError> {
+    let note = PhysicalNote::parse_from_filepath(config, Path::new(&path))?;
+    // this has to happen before the note is gone, since links by id are resolved by looking for the note
+    let linking = links_to_note(config, &note.path(config), false)?;
+
+    let note_path = note.path(config);
+    let mut message = if permanent {
+        config.store.delete(&note)?;
+        format!("{} deleted permanently", note_path.display())
+    } else {
+        let deleted_at = chrono::Local::now().format(trash::DELETED_AT_FORMAT).to_string();
+        config.store.rename(&note, &trash::trashed_note(&note, &deleted_at))?;
+        format!("{} moved to the trash", note_path.display())
+    };
+    // a buffer left open on the note would write it back into the wiki when it is saved
+    if let Some(buffer) = find_named_buffer(note_path.to_str().ok_or(NonUtf8Path)?)? {
+        api::command(&format!("bwipeout! {}", buffer.handle()))?;
+    }
+
+    let link_count: usize = linking.iter().map(|(_, _, spans)| spans.len()).sum();
+    if link_count > 0 {
+        match config.deleted_note_links {
+            trash::DeletedNoteLinks::Keep => {
+                let mut quickfix_items = Vec::new();
+                for (linking_note, contents, spans) in &linking {
+                    for span in spans {
+                        let line_start = contents[..span.start_offset].rfind('\n').map_or(0, |i| i + 1);
+                        quickfix_items.push(Object::from(Dictionary::from_iter([
+                            ("filename", Object::from(linking_note.path(config).to_str().ok_or(NonUtf8Path)?)),
+                            ("lnum", Object::from(contents[..span.start_offset].matches('\n').count() as i64 + 1)),
+                            ("col", Object::from((span.start_offset - line_start) as i64 + 1)),
+                            ("text", Object::from(format!("broken link to deleted note {}", span.target))),
+                        ])));
+                    }
+                }
+                api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+                message.push_str(&format!(", {link_count} link(s) to it are now broken and in the quickfix list"));
+            }
+            action => {
+                for (linking_note, contents, spans) in &linking {
+                    let links: Vec<_> = spans.iter().map(|span| (span.start_offset..span.end_offset, span.text.clone())).collect();
+                    config.store.write(linking_note, &trash::rewrite_deleted_links(contents, &links, action))?;
+                }
+                // reload the notes that are open in buffers
+                api::command("checktime")?;
+                let verb = if action == trash::DeletedNoteLinks::Unlink { "unlinked" } else { "struck through" };
+                message.push_str(&format!(", {verb} {link_count} link(s) to it in {} note(s)", linking.len()));
+            }
+        }
+    }
+    api::command(&format!(r#"echo "{message}""#))?;
+    Ok(())
+}
+
+/// The links to the note at `target` from the other notes in this wiki, with the note that they are in and its contents. Notes in other
+/// wikis are not included, since they are not rewritten when the note is deleted. With `with_definitions`, the link reference definitions
+/// that point at the note are included too, for rewriting their targets; they are left out when the links are rewritten as a whole.
+fn links_to_note(
+    config: &Config,
+    target: &Path,
+    with_definitions: bool,
+) -> Result<Vec<(PhysicalNote, String, Vec<markdown::LinkSpan>)>, ListAllPhysicalNotesError> {
+    let mut linking = Vec::new();
+    for other_note in list_all_physical_notes(config)? {
+        if other_note.path(config) == target {
+            continue;
+        }
+        let Ok(contents) = other_note.read_contents(config) else {
+            continue;
+        };
+        let Ok(md) = markdown::parse_markdown(&contents) else {
+            continue;
+        };
+        let other_note_as_note = Note::Physical(other_note.clone());
+        let definitions = if with_definitions { markdown::get_all_definition_urls(&md) } else { Vec::new() };
+        let definition_spans = definitions.into_iter().map(|(url, position)| markdown::LinkSpan {
+            target: url,
+            text: String::new(),
+            start_offset: position.start.offset,
+            end_offset: position.end.offset,
+        });
+        let spans: Vec<markdown::LinkSpan> = markdown::get_all_link_spans(&md)
+            .into_iter()
+            .chain(definition_spans)
+            .filter(|span| {
+                !links::is_external_link(config, &span.target)
+                    && links::resolve_link_path(config, &other_note_as_note, &span.target).is_ok_and(|link_to| link_to == target)
+            })
+            .collect();
+        if !spans.is_empty() {
+            linking.push((other_note, contents, spans));
+        }
+    }
+    Ok(linking)
+}
+
+error_union! {
+    pub enum IncomingLinkTitlesError {
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+    }
+}
+
+/// The titles (or ids, for notes without one) of the notes in any wiki that link to the note at `path`, for asking whether to delete it.
+pub fn incoming_link_titles(config: &Config, path: String) -> Result<Vec<String>, IncomingLinkTitlesError> {
+    let note = PhysicalNote::parse_from_filepath(config, Path::new(&path))?;
+    Ok(find_backlinks(config, &note.path(config))?.into_iter().map(|backlink| backlink.title.unwrap_or(backlink.note.id)).collect())
+}
+
+/// Puts a note from the trash back where it was. `path` is either the path of the note in the trash or the path that it had before it was
+/// deleted, and if it is not given, the note that was deleted last is restored.
+pub fn restore_note(config: &Config, path: Option<String>) -> Result<(), RestoreNoteError> {
+    let wanted = match path {
+        Some(path) => Some(note_at_missing_path(config, Path::new(&path)).ok_or(NothingToRestore)?),
+        None => None,
+    };
+
+    let trashed = config
+        .store
+        .list()?
+        .into_iter()
+        .filter(|trashed| match (trash::original_note(trashed), &wanted) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some((_, original)), Some(wanted)) => trashed == wanted || original == *wanted,
+        })
+        // the directory after the trash directory is when the note was deleted
+        .max_by_key(|trashed| trashed.directories[1].clone())
+        .ok_or(NothingToRestore)?;
+    let (_, original) = trash::original_note(&trashed).expect("only notes in the trash are kept");
+
+    if config.store.exists(&original) {
+        Err(RestoreTargetExists(original.path(config)))?;
+    }
+    config.store.rename(&trashed, &original)?;
+    // take away the directory that the note was put in when it was deleted if nothing else is in it
+    let _ = std::fs::remove_dir(config.home_path.join(trash::TRASH_DIRECTORY).join(&trashed.directories[1]));
+    api::command(&format!(r#"echo "restored {}""#, original.path(config).to_string_lossy()))?;
+    Ok(())
+}
+
+/// The note that `path` (absolute or relative to the home path) would be, for paths that do not exist, which
+/// [`PhysicalNote::parse_from_filepath`] cannot canonicalize.
+fn note_at_missing_path(config: &Config, path: &Path) -> Option<PhysicalNote> {
+    let relative = path.strip_prefix(&config.home_path).unwrap_or(path);
+    let mut directories: Vec<String> = relative.iter().map(|component| component.to_str().map(ToString::to_string)).collect::<Option<_>>()?;
+    let file_name = directories.pop()?;
+    Some(PhysicalNote { directories, id: file_name.strip_suffix(".md").unwrap_or(&file_name).to_string() })
+}
+
+/// Deletes everything in the trash for good. Like [`delete_note`], asking first happens on the lua side.
+pub fn empty_trash(config: &Config) -> Result<(), EmptyTrashError> {
+    let trashed: Vec<PhysicalNote> = config.store.list()?.into_iter().filter(|note| trash::original_note(note).is_some()).collect();
+    for note in &trashed {
+        config.store.delete(note)?;
+    }
+    // the directories that the notes were in, and anything in the trash that is not a note
+    let trash_path = config.home_path.join(trash::TRASH_DIRECTORY);
+    if trash_path.is_dir() {
+        std::fs::remove_dir_all(trash_path)?;
+    }
+    api::command(&format!(r#"echo "deleted {} note(s) in the trash for good""#, trashed.len()))?;
+    Ok(())
+}
+
+/// Whether the `confirm` config says to ask before doing the operation, for the prompts that are made on the lua side.
+pub fn should_confirm(config: &Config, operation: String) -> Result<bool, InvalidConfirmOperation> {
+    let operation = confirm::Operation::parse_from_str(&operation).ok_or(InvalidConfirmOperation(operation))?;
+    Ok(config.confirm.policy(operation) == confirm::ConfirmPolicy::Always)
+}
+
+#[derive(Debug)]
+pub struct NoInboxDirectory;
+impl std::error::Error for NoInboxDirectory {}
+impl std::fmt::Display for NoInboxDirectory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no inbox_directory configured")
+    }
+}
+#[derive(Debug)]
+pub struct MergeTargetDoesNotExist(PathBuf);
+impl std::error::Error for MergeTargetDoesNotExist {}
+impl std::fmt::Display for MergeTargetDoesNotExist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot merge into {} because it does not exist", self.0.display())
+    }
+}
+#[derive(Debug)]
+pub struct InvalidInboxAction(String);
+impl std::error::Error for InvalidInboxAction {}
+impl std::fmt::Display for InvalidInboxAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not something that can be done to an inbox note", self.0)
+    }
+}
+error_union! {
+    pub enum ProcessInboxError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        NonUtf8Path(NonUtf8Path),
+        IoError(std::io::Error),
+        NoInboxDirectory(NoInboxDirectory),
+        MergeTargetDoesNotExist(MergeTargetDoesNotExist),
+        InvalidInboxAction(InvalidInboxAction),
+    }
+}
+
+/// The notes in the inbox directory, for `process_inbox` on the lua side to step through one at a time.
+pub fn inbox_notes(config: &Config) -> Result<Vec<String>, ProcessInboxError> {
+    let inbox = config.inbox_directory.as_ref().ok_or(NoInboxDirectory)?;
+    let mut paths = Vec::new();
+    for note in list_all_physical_notes(config)?.into_iter().filter(|note| &note.directories == inbox) {
+        paths.push(note.path(config).to_str().ok_or(NonUtf8Path)?.to_string());
+    }
+    Ok(paths)
+}
+
+/// Does what was picked for an inbox note in `process_inbox` on the lua side, which has already opened the note and asked for
+/// `argument`: the directory to move it to for `"move"`, the space separated tags for `"tag"`, and the path inside the wiki of the note
+/// to merge it into for `"merge"`. `"delete"` ignores `argument` and is confirmed on the lua side.
+pub fn process_inbox_note(config: &Config, path: String, action: String, argument: String) -> Result<(), ProcessInboxError> {
+    let note = PhysicalNote::parse_from_filepath(config, Path::new(&path))?;
+    match action.as_str() {
+        "move" => {
+            let directories = argument.split('/').filter(|directory| !directory.is_empty()).map(ToString::to_string).collect();
+            // wiped first so that the buffer does not keep pointing at the old path
+            api::command("bwipeout")?;
+            move_note_file(config, &note, directories)?;
+        }
+        "tag" => {
+            let tags: Vec<String> = argument.split_whitespace().map(ToString::to_string).collect();
+            let mut current_buf = api::get_current_buf();
+            let lines = get_buffer_lines(&current_buf)?;
+            current_buf.set_lines(.., false, frontmatter::add_tags(&lines, &tags))?;
+            api::command("silent write")?;
+        }
+        "merge" => {
+            let target_path = config.home_path.join(argument).with_extension("md");
+            if !wiki_file_exists(config, &target_path) {
+                Err(MergeTargetDoesNotExist(target_path.clone()))?;
+            }
+            let lines = get_buffer_lines(&api::get_current_buf())?;
+            let body: Vec<&String> = frontmatter::strip_frontmatter(&lines).iter().skip_while(|line| line.trim().is_empty()).collect();
+
+            let mut target_contents = read_wiki_file(config, &target_path)?;
+            if !target_contents.ends_with('\n') {
+                target_contents.push('\n');
+            }
+            for line in body {
+                target_contents.push('\n');
+                target_contents.push_str(line);
+            }
+            target_contents.push('\n');
+            write_wiki_file(config, &target_path, &target_contents)?;
+
+            api::command("bwipeout")?;
+            config.store.delete(&note)?;
+        }
+        "delete" => {
+            api::command("bwipeout")?;
+            config.store.delete(&note)?;
+        }
+        _ => Err(InvalidInboxAction(action))?,
+    }
+    Ok(())
+}
+
+/// Moves a note's file into other directories, keeping its id. Links to it are not updated.
+fn move_note_file(config: &Config, note: &PhysicalNote, directories: Vec<String>) -> Result<PhysicalNote, std::io::Error> {
+    let moved = PhysicalNote { directories, id: note.id.clone() };
+    config.store.rename(note, &moved)?;
+    Ok(moved)
+}
+
+#[derive(Debug)]
+pub struct MoveTargetExists(PathBuf);
+impl std::error::Error for MoveTargetExists {}
+impl std::fmt::Display for MoveTargetExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot move the note to {} because something is already there", self.0.display())
+    }
+}
+#[derive(Debug)]
+pub struct InvalidDirectoryName(String);
+impl std::error::Error for InvalidDirectoryName {}
+impl std::fmt::Display for InvalidDirectoryName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not a directory that notes can be moved into", self.0)
+    }
+}
+error_union! {
+    pub enum MoveNoteError {
+        ApiError(api::Error),
+        NonUtf8Path(NonUtf8Path),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+        FormatLinkPathError(links::FormatLinkPathError),
+        IoError(std::io::Error),
+        MoveTargetExists(MoveTargetExists),
+        InvalidDirectoryName(InvalidDirectoryName),
+    }
+}
+
+/// Moves the current note into `directories` (relative to the home path), keeping its id, and reopens it from there. The links in the
+/// note and the links to it from other notes in the wiki are rewritten to keep pointing at the same places, in the same form that they
+/// were written in. Links that were already broken and links that find their target by id are left alone.
+pub fn move_note(config: &Config, directories: Vec<String>) -> Result<(), MoveNoteError> {
+    let note = match Note::get_current_note(config)? {
+        Note::Physical(note) => note,
+        Note::Scratch(_) => Err(NotAPhysicalNote)?,
+    };
+    // these would put the note outside of the wiki or somewhere that it cannot be found
+    let is_invalid = |directory: &&String| directory.is_empty() || *directory == "." || *directory == ".." || directory.contains('/');
+    if let Some(directory) = directories.iter().find(is_invalid) {
+        Err(InvalidDirectoryName(directory.clone()))?;
+    }
+    let old_path = note.path(config);
+    let new_note = PhysicalNote { directories: directories.clone(), id: note.id.clone() };
+    let new_path = new_note.path(config);
+    if new_path == old_path {
+        api::command(r#"echo "the note is already there""#)?;
+        return Ok(());
+    }
+    if config.store.exists(&new_note) {
+        Err(MoveTargetExists(new_path.clone()))?;
+    }
+
+    // links are resolved before anything moves, since links by id are resolved by looking for the note, and every buffer is written first
+    // so that the notes that are rewritten on disk are not left with unsaved changes that would overwrite the rewritten links
+    api::command("silent wall")?;
+    let contents = note.read_contents(config)?;
+    let old_note = Note::Physical(note.clone());
+    let outgoing: Vec<(String, std::ops::Range<usize>, PathBuf)> = link_targets_with_ranges(&contents)?
+        .into_iter()
+        .filter(|(url, _)| !links::is_external_link(config, url) && links::split_interwiki(config, url).is_none())
+        .filter(|(url, _)| !links::split_anchor(url).0.is_empty())
+        .filter_map(|(url, range)| {
+            let target = links::resolve_link_path(config, &old_note, &url).ok()?;
+            // links from the note to itself point at where it is going
+            let target = if target == old_path { new_path.clone() } else { target };
+            Some((url, range, target))
+        })
+        .collect();
+    let incoming = links_to_note(config, &old_path, true)?;
+
+    let (row, col) = api::get_current_win().get_cursor()?;
+    let old_buffer = api::get_current_buf();
+    let moved = move_note_file(config, &note, directories)?;
+
+    let moved_note = Note::Physical(moved.clone());
+    let mut replacements = Vec::new();
+    for (url, range, target) in outgoing {
+        if !wiki_file_exists(config, &target) || links::resolve_link_path(config, &moved_note, &url).is_ok_and(|resolved| resolved == target) {
+            continue;
+        }
+        let new_url = relinked_path(config, &moved_note, &url, &target)?;
+        replacements.push((range, url, new_url));
+    }
+    let (new_contents, moved_link_count) = replace_link_paths(&contents, replacements);
+    config.store.write(&moved, &new_contents)?;
+
+    let mut incoming_link_count = 0;
+    for (linking_note, linking_contents, spans) in incoming {
+        let linking = Note::Physical(linking_note.clone());
+        let mut replacements = Vec::new();
+        for span in spans {
+            if links::resolve_link_path(config, &linking, &span.target).is_ok_and(|resolved| resolved == new_path) {
+                continue;
+            }
+            let new_url = relinked_path(config, &linking, &span.target, &new_path)?;
+            replacements.push((span.start_offset..span.end_offset, span.target, new_url));
+        }
+        let (new_contents, replaced_count) = replace_link_paths(&linking_contents, replacements);
+        if replaced_count > 0 {
+            incoming_link_count += replaced_count;
+            config.store.write(&linking_note, &new_contents)?;
+        }
+    }
+
+    api::cmd(&CmdInfos::builder().cmd("edit").args([new_path.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+    api::command(&format!("bwipeout {}", old_buffer.handle()))?;
+    let line_count = api::get_current_buf().line_count()?;
+    api::get_current_win().set_cursor(row.min(line_count), col)?;
+    // reload the notes with rewritten links that are open in buffers
+    api::command("checktime")?;
+    api::command(&format!(
+        r#"echo "moved to {}, rewrote {moved_link_count} link(s) in it and {incoming_link_count} link(s) to it""#,
+        new_path.display()
+    ))?;
+    Ok(())
+}
+
+/// Every link, image, wikilink, and link reference definition in a note, with its target and the byte range of the whole thing.
+fn link_targets_with_ranges(contents: &str) -> Result<Vec<(String, std::ops::Range<usize>)>, markdown::MdParseError> {
+    let md = markdown::parse_markdown(contents)?;
+    let mut targets: Vec<_> = markdown::get_all_link_and_image_urls(&md)
+        .into_iter()
+        .chain(markdown::get_all_definition_urls(&md))
+        .map(|(url, position)| (url, position.start.offset..position.end.offset))
+        .collect();
+    let wikilinks = markdown::get_all_wikilinks(&md);
+    targets.extend(wikilinks.into_iter().map(|wikilink| (wikilink.target, wikilink.start_offset..wikilink.end_offset)));
+    targets.sort_by_key(|(_, range)| range.start);
+    Ok(targets)
+}
+
+/// The target for a link from `from` to `target` written the same way as `old_link`: absolute, relative to the home path, or relative to
+/// the note, with the same anchor.
+fn relinked_path(config: &Config, from: &Note, old_link: &str, target: &Path) -> Result<String, links::FormatLinkPathError> {
+    let (old_path, anchor) = links::split_anchor(old_link);
+    let mut style_config = config.clone();
+    style_config.link_style = if Path::new(old_path).starts_with(&config.home_path) {
+        links::LinkStyle::Absolute
+    } else if old_path.starts_with('/') {
+        links::LinkStyle::HomeRelative
+    } else {
+        links::LinkStyle::Relative
+    };
+    links::format_link_path(&style_config, from, target, anchor)
+}
+
+/// Replaces the target of each link, given as the range of the whole link with its old and new targets, keeping the rest of the link as it
+/// was written. Returns the new contents and how many of the links were replaced, since a target that is not written in the link as it was
+/// parsed (such as one with escapes in it) cannot be found to be replaced.
+fn replace_link_paths(contents: &str, mut replacements: Vec<(std::ops::Range<usize>, String, String)>) -> (String, usize) {
+    replacements.sort_by_key(|(range, _, _)| range.start);
+    let mut new_contents = contents.to_string();
+    let mut replaced_count = 0;
+    // replace back to front so that the offsets of the links that have not been replaced yet stay valid
+    for (range, old_target, new_target) in replacements.into_iter().rev() {
+        if let Some(target_offset) = markdown::link_target_offset(&contents[range.clone()], &old_target) {
+            let start = range.start + target_offset;
+            new_contents.replace_range(start..start + old_target.len(), &new_target);
+            replaced_count += 1;
+        }
+    }
+    (new_contents, replaced_count)
+}
+
+pub fn suggest_tags(config: &Config) -> Result<Vec<String>, SuggestTagsError> {
+    let current_note = Note::get_current_note(config)?;
+    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+    let existing_tags = markdown::parse_frontmatter(&current_md)
+        .ok()
+        .map(|frontmatter| frontmatter.tags)
+        .unwrap_or_default()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    let corpus = list_all_physical_notes(config)?
+        .into_iter()
+        .filter_map(|note| note.read_contents(config).ok())
+        .filter_map(|contents| markdown::parse_markdown(&contents).ok())
+        .map(|md| markdown::plain_text(&md))
+        .collect::<Vec<_>>();
+
+    Ok(tfidf::extract_keywords(&markdown::plain_text(&current_md), &corpus, 20)
+        .into_iter()
+        .filter(|keyword| !existing_tags.contains(keyword))
+        .collect())
+}
+
+pub fn add_tags_to_current_note(tags: Vec<String>) -> Result<(), api::Error> {
+    let mut current_buf = api::get_current_buf();
+    let lines = get_buffer_lines(&current_buf)?;
+
+    let existing_tags = markdown::parse_markdown(&lines.join("\n"))
+        .ok()
+        .and_then(|md| markdown::parse_frontmatter(&md).ok())
+        .map(|frontmatter| frontmatter.tags)
+        .unwrap_or_default()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let mut new_tags = Vec::new();
+    for tag in tags {
+        if !existing_tags.contains(&tag) && !new_tags.contains(&tag) {
+            new_tags.push(tag);
+        }
+    }
+
+    if !new_tags.is_empty() {
+        current_buf.set_lines(.., false, frontmatter::add_tags(&lines, &new_tags))?;
+    }
+
+    Ok(())
+}
+
+/// Gathers a note (the current note if `note_path` is not given) and every note linked from it within `depth` hops into one document
+/// with the frontmatter and autogenerated sections stripped out, for pasting into other tools. The result goes into `output_file` if it
+/// is given, otherwise into `register`, and the approximate number of tokens in it is reported.
+pub fn copy_context(
+    config: &Config,
+    note_path: Option<String>,
+    depth: usize,
+    register: String,
+    output_file: Option<String>,
+) -> Result<(), CopyContextError> {
+    let root = match note_path {
+        Some(note_path) => Note::Physical(PhysicalNote::parse_from_filepath(config, Path::new(&note_path))?),
+        None => Note::get_current_note(config)?,
+    };
+
+    let mut seen: BTreeSet<PathBuf> = root.path(config).into_iter().collect();
+    let mut queue = VecDeque::from([(root, 0)]);
+    let mut parts = Vec::new();
+    while let Some((note, hops)) = queue.pop_front() {
+        let Some((contents, md)) =
+            note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok().map(|md| (contents, md)))
+        else {
+            continue;
+        };
+
+        let title = markdown::parse_frontmatter(&md).ok().and_then(|frontmatter| frontmatter.title).unwrap_or_default();
+        let path = note.path(config).map(|path| path.display().to_string()).unwrap_or_default();
+        parts.push((title, path, context::strip_for_context(&contents)));
+
+        if hops < depth {
+            for linked_path in note_index::resolved_links(config, &note, &md) {
+                if linked_path.extension().is_some_and(|extension| extension == "md") && !seen.contains(&linked_path) {
+                    if let Ok(linked) = PhysicalNote::parse_from_filepath(config, &linked_path) {
+                        seen.insert(linked_path);
+                        queue.push_back((Note::Physical(linked), hops + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    let text = context::join_context(&parts);
+    let destination = match output_file {
+        Some(output_file) => {
+            std::fs::write(&output_file, &text)?;
+            output_file
+        }
+        None => {
+            api::call_function::<_, i64>("setreg", Array::from_iter([Object::from(register.as_str()), Object::from(text.as_str())]))?;
+            format!("register {register}")
+        }
+    };
+    api::command(&format!(r#"echo "copied {} note(s), about {} tokens, to {destination}""#, parts.len(), context::approximate_tokens(&text)))?;
+
+    Ok(())
+}
+
+pub fn add_tag(tag: String) -> Result<(), api::Error> {
+    add_tags_to_current_note(vec![tag])
+}
+
+/// Moves the current note to the next of the configured `statuses`, setting its `status` field.
+pub fn advance_status(config: &Config) -> Result<(), api::Error> {
+    let mut current_buf = api::get_current_buf();
+    let lines = get_buffer_lines(&current_buf)?;
+    let current = markdown::parse_markdown(&lines.join("\n")).ok().and_then(|md| markdown::parse_frontmatter(&md).ok()).unwrap_or_default();
+    match frontmatter::next_status(&config.statuses, current.status()) {
+        Some(next) => {
+            current_buf.set_lines(.., false, frontmatter::set_field(&lines, "status", next))?;
+            api::command(&format!(r#"echo "status: {next}""#))?;
+        }
+        None => api::command(r#"echo "no statuses configured""#)?,
+    }
+    Ok(())
+}
+
+pub fn remove_tag(tag: String) -> Result<(), api::Error> {
+    let mut current_buf = api::get_current_buf();
+    let lines = get_buffer_lines(&current_buf)?;
+    match frontmatter::remove_tag(&lines, &tag) {
+        Some(new_lines) => current_buf.set_lines(.., false, new_lines)?,
+        None => api::command(&format!(r#"echo "note does not have tag {tag}""#))?,
+    }
+    Ok(())
+}
+
+/// Renames a tag (and every tag nested inside it) in the frontmatter of every note that has it.
+pub fn rename_tag(config: &Config, old: String, new: String) -> Result<(), RenameTagError> {
+    let mut changed = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        if let Some(new_lines) = frontmatter::rename_tag(&lines, &old, &new) {
+            let mut new_contents = new_lines.join("\n");
+            if contents.ends_with('\n') {
+                new_contents.push('\n');
+            }
+            changed.push((note, new_contents));
+        }
+    }
+
+    let changed_note_count = changed.len();
+    let question = format!("rename tag {old} to {new} in {changed_note_count} note(s)?");
+    if changed_note_count > 0 && !confirm::confirm(&config.confirm, confirm::Operation::BulkReplace, &question)? {
+        api::command(r#"echo "\nnot renaming""#)?;
+        return Ok(());
+    }
+    for (note, new_contents) in changed {
+        config.store.write(&note, &new_contents)?;
+    }
+
+    // reload any notes that are open in buffers
+    api::command("checktime")?;
+    api::command(&format!(r#"echo "renamed tag {old} to {new} in {changed_note_count} note(s)""#))?;
+
+    Ok(())
+}
+
+/// The notes with conflict markers in them, ordered by path, with their contents.
+fn conflicted_notes(config: &Config) -> Result<Vec<(PathBuf, String)>, ResolveConflictsError> {
+    let mut conflicted = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        if conflicts::has_conflicts(&contents) {
+            conflicted.push((note.path(config), contents));
+        }
+    }
+    conflicted.sort();
+    Ok(conflicted)
+}
+
+/// Puts every conflicted note in the quickfix list and opens the first one in a diff. [`next_conflict`] moves on to the next one.
+pub fn resolve_conflicts(config: &Config) -> Result<(), ResolveConflictsError> {
+    let conflicted = conflicted_notes(config)?;
+    let Some((first_path, first_contents)) = conflicted.first() else {
+        api::command(r#"echo "no conflicted notes""#)?;
+        return Ok(());
+    };
+
+    let mut quickfix_items = Vec::new();
+    for (path, contents) in &conflicted {
+        let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        let hunks = conflicts::find_conflict_hunks(&lines);
+        quickfix_items.push(Object::from(Dictionary::from_iter([
+            ("filename", Object::from(path.to_str().ok_or(NonUtf8Path)?)),
+            ("lnum", Object::from(hunks.first().map(|hunk| hunk.start_line + 1).unwrap_or(1) as i64)),
+            ("text", Object::from(format!("{} conflict(s)", hunks.len()))),
+        ])));
+    }
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+
+    open_conflict_diff(first_path, first_contents)?;
+    api::command(&format!(r#"echo "{} conflicted note(s)""#, conflicted.len()))?;
+
+    Ok(())
+}
+
+/// Saves the note that is being resolved and opens the next conflicted note after it in a diff, going back to the first one after the
+/// last. The note that was being resolved is only opened again if it still has conflicts and it is the only one left.
+pub fn next_conflict(config: &Config) -> Result<(), ResolveConflictsError> {
+    // the side buffers are not files, so this only writes the note in the middle
+    api::command("silent wall")?;
+    let current_path = Note::get_current_note(config).ok().and_then(|note| note.path(config));
+    let conflicted = conflicted_notes(config)?;
+    let next = conflicted
+        .iter()
+        .find(|(path, _)| current_path.as_ref().is_none_or(|current_path| path > current_path))
+        .or_else(|| conflicted.first());
+    let Some((path, contents)) = next else {
+        api::command("diffoff! | only")?;
+        api::command(r#"echo "no conflicted notes left""#)?;
+        return Ok(());
+    };
+
+    open_conflict_diff(path, contents)?;
+    api::command(&format!(r#"echo "{} conflicted note(s) left""#, conflicted.len()))?;
+
+    Ok(())
+}
+
+/// A 3 way diff: "ours" on the left, the conflicted note in the middle, and "theirs" on the right.
+fn open_conflict_diff(path: &Path, contents: &str) -> Result<(), ResolveConflictsError> {
+    let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+    let hunks = conflicts::find_conflict_hunks(&lines);
+
+    api::command("diffoff! | only")?;
+    api::cmd(&CmdInfos::builder().cmd("edit").args([path.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+    api::command("diffthis")?;
+    for (split_command, resolution) in [("leftabove vnew", Resolution::Ours), ("rightbelow vnew", Resolution::Theirs)] {
+        api::command(split_command)?;
+        let mut side_buffer = api::get_current_buf();
+        side_buffer.set_lines(.., false, conflicts::resolve_all(&lines, &hunks, resolution))?;
+        for (option, value) in [("buftype", "nofile"), ("bufhidden", "wipe"), ("filetype", "markdown")] {
+            api::set_option_value(option, value, &OptionOpts::builder().buffer(side_buffer.clone()).build())?;
+        }
+        api::command("diffthis")?;
+        api::command("wincmd p")?;
+    }
+
+    Ok(())
+}
+
+pub fn resolve_conflict_hunk(resolution: String) -> Result<(), ResolveConflictHunkError> {
+    let resolution = Resolution::parse_from_str(&resolution).ok_or(InvalidResolution(resolution))?;
+
+    let mut current_buf = api::get_current_buf();
+    let lines = get_buffer_lines(&current_buf)?;
+    let cursor_line = api::eval::<usize>(r#"line(".")"#)? - 1;
+
+    let hunk = conflicts::find_conflict_hunks(&lines).into_iter().find(|hunk| hunk.contains_line(cursor_line)).ok_or(NotInConflictHunk)?;
+    current_buf.set_lines(hunk.start_line..hunk.end_line + 1, false, hunk.resolved(resolution))?;
+    api::command("diffupdate")?;
+
+    Ok(())
+}
+
+/// Notifies about every note whose `remind` time has passed since the last time reminders were checked, and puts them in the quickfix
+/// list so that they can be jumped to.
+pub fn check_reminders(config: &Config) -> Result<(), CheckRemindersError> {
+    let now = chrono::Local::now().naive_local();
+    // the lock is held until the new time is written so that instances that share the wiki do not both notify about the same reminders
+    let lock = match state::lock_state(config) {
+        Ok(lock) => lock,
+        // another instance is checking right now, and whatever is due will be shown there
+        Err(state::StateFileError::StateLocked(_)) => return Ok(()),
+        Err(e) => Err(e)?,
+    };
+    let last_checked = lock.read(reminders::LAST_CHECKED_STATE_FILE)?.and_then(|contents| reminders::parse_last_checked(&contents));
+
+    let mut all_reminders = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let Some(frontmatter) = note
+            .read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+        else {
+            continue;
+        };
+        if let Ok(at) = frontmatter.remind(config) {
+            all_reminders.push(reminders::Reminder { note, title: frontmatter.title, at });
+        }
+    }
+
+    let due = reminders::due_reminders(all_reminders, last_checked, now);
+    if !due.is_empty() {
+        let mut quickfix_items = Vec::new();
+        for reminder in &due {
+            let path = reminder.note.path(config);
+            let path = path.to_str().ok_or(NonUtf8Path)?;
+            let title = reminder.title.clone().unwrap_or_else(|| reminder.note.id.clone());
+
+            api::notify(&format!("reminder: {title}\n{path}"), api::types::LogLevel::Info, &Dictionary::new())?;
+            if let Some(command) = &config.reminder_command {
+                std::process::Command::new(command).arg(&title).arg(path).spawn()?;
+            }
+
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(path)),
+                ("lnum", Object::from(1i64)),
+                ("text", Object::from(format!("reminder: {title}"))),
+            ])));
+        }
+        api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    }
+
+    lock.write(reminders::LAST_CHECKED_STATE_FILE, reminders::format_last_checked(now).as_bytes())?;
+
+    Ok(())
+}
+
+/// Regenerates every autogenerated section of the current note. This is run by autocommands, which can go off while Neovim does not allow
+/// edits, so it is put off until it does if it has to be.
+pub fn regenerate_autogenerated_sections(config: &Config) -> Result<(), AutogenerateError> {
+    let config = config.clone();
+    let buffer = api::get_current_buf();
+    deferred::run_or_defer(move || {
+        // by the time that it is safe, another buffer might be current, and this one gets regenerated again when it is opened anyway
+        if api::get_current_buf() != buffer {
+            return Ok(());
+        }
+        regenerate_sections(&config, &RegenerateOnSave::All)
+    })
+}
+
+/// Registers the `BufWritePre` autocommand that regenerates the autogenerated sections of notes as they are saved, if
+/// `regenerate_on_save` is set. A note can opt out with `regenerate_on_save: false` in its frontmatter.
+pub fn register_save_autocmd(config: &Config) -> Result<(), api::Error> {
+    // clearing the group means that calling setup again does not register the autocommands twice
+    let group = api::create_augroup("wikiplugin_regenerate_on_save", &CreateAugroupOpts::builder().clear(true).build())?;
+    for (_, wiki) in all_wikis(config) {
+        if wiki.regenerate_on_save == RegenerateOnSave::Never {
+            continue;
+        }
+        let pattern = format!("{}/*", wiki.home_path.to_string_lossy().trim_end_matches('/'));
+        api::create_autocmd(
+            ["BufWritePre"],
+            &CreateAutocmdOpts::builder()
+                .group(group)
+                .patterns([pattern.as_str()])
+                .callback(move |_: AutocmdCallbackArgs| {
+                    let wiki = wiki.clone();
+                    let buffer = api::get_current_buf();
+                    // if this has to be put off, the note is saved without the regenerated sections, but they are still regenerated
+                    let result = deferred::run_or_defer(move || if api::get_current_buf() == buffer { regenerate_on_save(&wiki) } else { Ok(()) });
+                    if let Err(e) = result {
+                        crate::error::print_error(&e as &dyn std::error::Error);
+                    }
+                    // returning true would delete the autocommand
+                    Ok::<_, api::Error>(false)
+                })
+                .build(),
+        )?;
+    }
+    Ok(())
+}
+
+fn regenerate_on_save(config: &Config) -> Result<(), AutogenerateError> {
+    let frontmatter = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))
+        .ok()
+        .and_then(|md| markdown::parse_frontmatter(&md).ok());
+    if frontmatter.is_some_and(|frontmatter| frontmatter.regenerate_on_save() == Some(false)) {
+        return Ok(());
+    }
+    regenerate_sections(config, &config.regenerate_on_save)
+}
+
+/// Regenerates every autogenerated section in the current buffer whose command is in `commands`.
+fn regenerate_sections(config: &Config, commands: &RegenerateOnSave) -> Result<(), AutogenerateError> {
+    if config.virtual_sections {
+        return render_virtual_sections(config, commands);
+    }
+    let current_note = Note::get_current_note(config)?;
+    let mut current_buf = api::get_current_buf();
+
+    // lines are added and removed as sections are regenerated, which moves every later section by the same number of lines
+    let mut shift: isize = 0;
+    for section in autogenerate::find_marked_sections(&get_buffer_lines(&current_buf)?) {
+        let start_line_index = (section.start_line as isize + shift) as usize;
+        let end_line_index = match section.end_line {
+            Some(end_line) => (end_line as isize + shift) as usize,
+            None => {
+                // the end marker goes immediately after the start marker, so that the generated lines go between them
+                current_buf.set_lines(start_line_index + 1..start_line_index + 1, false, vec![config.autogenerate_marker_style.end_marker()])?;
+                shift += 1;
+                start_line_index + 1
+            }
+        };
+
+        let autogenerate_command = section.command.as_str();
+        if !commands.includes(autogenerate_command) {
+            continue;
+        }
+        let replacement = generate_section(config, &current_note, &current_buf, &section, start_line_index)?;
+
+        if let Some(replacement) = replacement {
+            // setting the lines marks the buffer as modified and moves marks and folds even if they are the same, so only do it if the
+            // section actually changed
+            let existing: Vec<String> =
+                current_buf.get_lines((start_line_index + 1)..end_line_index, false)?.map(|s| s.to_string_lossy().to_string()).collect();
+            if existing != replacement {
+                shift += replacement.len() as isize - existing.len() as isize;
+                current_buf.set_lines((start_line_index + 1)..end_line_index, false, replacement)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const VIRTUAL_SECTIONS_NAMESPACE: &str = "wikiplugin_virtual_sections";
+
+/// Shows what each autogenerated section would hold as virtual lines under its start marker, without changing the buffer, so that
+/// generated content never ends up in the note on disk. Anything already between the markers is left alone.
+fn render_virtual_sections(config: &Config, commands: &RegenerateOnSave) -> Result<(), AutogenerateError> {
+    let current_note = Note::get_current_note(config)?;
+    let mut current_buf = api::get_current_buf();
+    let namespace = api::create_namespace(VIRTUAL_SECTIONS_NAMESPACE);
+
+    for section in autogenerate::find_marked_sections(&get_buffer_lines(&current_buf)?) {
+        if !commands.includes(&section.command) {
+            continue;
+        }
+        current_buf.clear_namespace(namespace, section.start_line..section.start_line + 1)?;
+        let Some(lines) = generate_section(config, &current_note, &current_buf, &section, section.start_line)? else {
+            continue;
+        };
+        // each virtual line is a list of chunks of text and their highlight groups, and each of these lines is only one chunk
+        let virt_lines = Array::from_iter(lines.into_iter().map(|line| {
+            let chunk = Array::from_iter([Object::from(line), Object::from("Comment")]);
+            Object::from(Array::from_iter([Object::from(chunk)]))
+        }));
+        let opts = Dictionary::from_iter([("virt_lines", Object::from(virt_lines))]);
+        api::call_function::<_, i64>(
+            "nvim_buf_set_extmark",
+            Array::from_iter([
+                Object::from(0i64),
+                Object::from(i64::from(namespace)),
+                Object::from(section.start_line as i64),
+                Object::from(0i64),
+                Object::from(opts),
+            ]),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The lines that an `embed` section with these arguments holds, or `None` if the note or heading to embed cannot be found.
+fn embed_lines(config: &Config, current_note: &Note, arguments: &[&str]) -> Option<Vec<String>> {
+    let target = arguments.first().copied().unwrap_or("");
+    let (target, anchor) = links::split_anchor(target);
+    // the heading can be given as an anchor on the path or as its own argument
+    let heading = arguments.get(1).copied().filter(|heading| !heading.is_empty()).or(anchor);
+
+    let path = links::resolve_link_path(config, current_note, target).ok()?;
+    let contents = PhysicalNote::parse_from_filepath(config, &path).ok()?.read_contents(config).ok()?;
+    let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+    autogenerate::embedded_lines(frontmatter::strip_frontmatter(&lines), heading)
+}
+
+/// The lines that belong in `section`, which starts at `start_line_index` in `current_buf`, or `None` if the marker is invalid (which has
+/// already been reported).
+fn generate_section(
+    config: &Config,
+    current_note: &Note,
+    current_buf: &api::Buffer,
+    section: &autogenerate::MarkedSection,
+    start_line_index: usize,
+) -> Result<Option<Vec<String>>, AutogenerateError> {
+    let autogenerate_command = section.command.as_str();
+    let all_arguments = section.arguments.split(';').map(str::trim).collect::<Vec<_>>();
+    let (filters, autogenerate_arguments) = filter::split_filters(&all_arguments);
+    let passes_filters = |frontmatter: Option<&markdown::Frontmatter>| {
+        filters.is_empty() || frontmatter.is_some_and(|frontmatter| filters.iter().all(|filter| filter.matches(frontmatter)))
+    };
+
+    Ok(match autogenerate_command {
+        "index" => {
+            let directories: Vec<String> =
+                autogenerate_arguments.first().copied().unwrap_or("").split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect();
+            let sort_by = autogenerate_arguments.get(1).copied().unwrap_or("title");
+            // everything after the sort key is a flag, in any order
+            let flags = autogenerate_arguments.get(2..).unwrap_or_default();
+            let show_author = flags.contains(&"author");
+            let grouped = flags.contains(&"grouped");
+            let recursive = grouped || flags.contains(&"recursive");
+
+            let sort_key = match autogenerate::IndexSortKey::parse_from_str(sort_by) {
+                Some(autogenerate::IndexSortKey::Title) => query::SortKey::Title,
+                Some(autogenerate::IndexSortKey::Date) => query::SortKey::Created,
+                Some(autogenerate::IndexSortKey::Id) => query::SortKey::Id,
+                None => {
+                    api::err_writeln(&format!("error: invalid comparison '{sort_by}'"));
+                    query::SortKey::Id
+                }
+            };
+
+            let mut index_query = query::Query::new(query::Source::Directory { directories: directories.clone(), recursive });
+            index_query.filters = filters.clone();
+            index_query.sort = Some(query::Sort { key: sort_key, descending: false });
+            let format = if show_author { format!("{} | {{author}}", query::DEFAULT_FORMAT) } else { query::DEFAULT_FORMAT.to_string() };
+            let rows = run_query(config, current_note, &index_query)?;
+
+            let groups =
+                if grouped { autogenerate::group_by_subdirectory(rows, &directories, |row| &row.note) } else { vec![(Vec::new(), rows)] };
+            // subdirectory headings go one level below the heading that the section is under
+            let heading_level = if grouped {
+                let current_md = markdown::parse_markdown(&get_buffer_lines(current_buf)?.join("\n"))?;
+                let enclosing = markdown::get_headings(&current_md)
+                    .into_iter()
+                    .filter(|(_, _, position)| position.start.line <= start_line_index)
+                    .last()
+                    .map_or(1, |(depth, _, _)| depth);
+                usize::from(enclosing + 1).min(6)
+            } else {
+                0
+            };
+
+            Some(autogenerate::grouped_lines(groups, heading_level, |rows| format_query_rows(config, current_note, rows, &format))?)
+        }
+
+        "query" => {
+            let raw_query = section.arguments.trim();
+            match query::parse_query(raw_query) {
+                Ok(query) => {
+                    let rows = run_query(config, current_note, &query)?;
+                    Some(format_query_rows(config, current_note, rows, query.format.as_deref().unwrap_or(query::DEFAULT_FORMAT))?)
+                }
+                Err(err) => {
+                    api::err_writeln(&format!("error: {err}"));
+                    None
+                }
+            }
+        }
+
+        "recent" => {
+            let count = autogenerate_arguments.first().and_then(|count| count.parse().ok()).unwrap_or(10);
+            let recent_by = autogenerate_arguments.get(1).and_then(|by| autogenerate::RecentBy::parse_from_str(by));
+            let recent_by = recent_by.unwrap_or(autogenerate::RecentBy::Created);
+            // the directory filter includes subdirectories
+            let directory: Vec<String> = autogenerate_arguments
+                .get(2)
+                .map(|directory| directory.split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect())
+                .unwrap_or_default();
+            // everything after the directory is a flag, like in an index
+            let show_author = autogenerate_arguments.get(3..).unwrap_or_default().contains(&"author");
+
+            let mut entries = Vec::new();
+            for file in list_all_physical_notes(config)? {
+                if !file.directories.starts_with(&directory) {
+                    continue;
+                }
+                let frontmatter = file
+                    .read_contents(config)
+                    .ok()
+                    .and_then(|contents| markdown::parse_markdown(&contents).ok())
+                    .and_then(|md| markdown::parse_frontmatter(&md).ok());
+                if !passes_filters(frontmatter.as_ref()) {
+                    continue;
+                }
+                let title = frontmatter.as_ref().and_then(|f| f.title.clone());
+                let modified = config
+                    .store
+                    .modified(&file)
+                    .ok()
+                    .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).naive_local());
+                let created = frontmatter.as_ref().and_then(|f| f.timestamp(config).ok());
+                // the file's modification time is the best guess at when a note without a date was created
+                let timestamp = match recent_by {
+                    autogenerate::RecentBy::Created => created.or(modified),
+                    autogenerate::RecentBy::Modified => modified.or(created),
+                };
+                let author = frontmatter.and_then(|f| f.author);
+                entries.push(autogenerate::IndexEntry { note: file, title, timestamp, author });
+            }
+
+            let mut result = Vec::new();
+            for entry in autogenerate::most_recent_entries(entries, count) {
+                result.push(format!(
+                    "- {}{}{}",
+                    note_icon_prefix(config, &entry.note),
+                    links::format_link(config, current_note, &entry.note.path(config), &entry.title.unwrap_or_default(), None)?,
+                    if show_author { autogenerate::author_column(entry.author.as_deref()) } else { String::new() }
+                ));
+            }
+
+            Some(result)
+        }
+
+        "tasks" => {
+            // the directory filter includes subdirectories
+            let directory: Vec<String> = autogenerate_arguments
+                .first()
+                .map(|directory| directory.split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect())
+                .unwrap_or_default();
+
+            let mut result = Vec::new();
+            for file in list_all_physical_notes(config)? {
+                if !file.directories.starts_with(&directory) {
+                    continue;
+                }
+                let Ok(contents) = file.read_contents(config) else {
+                    continue;
+                };
+                let tasks = autogenerate::unchecked_tasks(&contents);
+                if tasks.is_empty() {
+                    continue;
+                }
+                let frontmatter = markdown::parse_markdown(&contents).ok().and_then(|md| markdown::parse_frontmatter(&md).ok());
+                if !passes_filters(frontmatter.as_ref()) {
+                    continue;
+                }
+
+                let title = frontmatter.and_then(|f| f.title).unwrap_or_default();
+                let link = links::format_link(config, current_note, &file.path(config), &title, None)?;
+                for (line, task) in tasks {
+                    result.push(format!("- {task} ({link}, line {})", line + 1));
+                }
+            }
+
+            Some(result)
+        }
+
+        "embed" => {
+            let embedded = embed_lines(config, current_note, &autogenerate_arguments);
+            if embedded.is_none() {
+                api::err_writeln(&format!("error: cannot embed '{}'", autogenerate_arguments.join("; ")));
+            }
+            embedded
+        }
+
+        "directories" => {
+            let notes = list_all_physical_notes(config)?;
+            Some(
+                autogenerate::directory_counts(&notes)
+                    .into_iter()
+                    .map(|(directories, count)| format!("- {} ({count} note(s))", directories.join("/")))
+                    .collect(),
+            )
+        }
+
+        "tags" => {
+            let index = note_index::NoteIndex::build(config)?;
+            Some(
+                autogenerate::tag_counts(index.notes.iter().map(|indexed| indexed.tags.as_slice()))
+                    .into_iter()
+                    .map(|(tag, count)| format!("- {tag} ({count})"))
+                    .collect(),
+            )
+        }
+
+        "tag_index" => {
+            let filter = autogenerate_arguments.first().map(|tag| Tag::parse_from_str(tag));
+            let entries = tag_index_entries(config, filter.as_ref())?;
+            Some(tag_index::render_tag_index(&entries, config.tag_index_style, |entry| {
+                Ok::<_, links::FormatLinkPathError>(vec![format!(
+                    "- {}{}",
+                    note_icon_prefix(config, &entry.note),
+                    links::format_link(config, current_note, &entry.note.path(config), &entry.title, None)?
+                )])
+            })?)
+        }
+
+        "toc" => {
+            let min_depth = autogenerate_arguments.first().and_then(|depth| depth.parse().ok()).unwrap_or(1);
+            let max_depth = autogenerate_arguments.get(1).and_then(|depth| depth.parse().ok()).unwrap_or(6);
+            // the buffer is used instead of the file so that headings that have not been saved yet are included
+            let current_md = markdown::parse_markdown(&get_buffer_lines(current_buf)?.join("\n"))?;
+            let headings: Vec<_> = markdown::get_headings(&current_md).into_iter().map(|(depth, text, _)| (depth, text)).collect();
+            Some(autogenerate::table_of_contents(&headings, min_depth, max_depth))
+        }
+
+        "backlinks" => {
+            // like the backlinks query source, but each note is followed by the sections that its links are in
+            let backlinks: Vec<Backlink> = match current_note.path(config) {
+                Some(current_path) => {
+                    find_backlinks(config, &current_path)?.into_iter().filter(|backlink| backlink.path.starts_with(&config.home_path)).collect()
+                }
+                None => Vec::new(),
+            };
+            let sections: BTreeMap<PhysicalNote, Vec<String>> =
+                backlinks.iter().map(|backlink| (backlink.note.clone(), backlink.sections())).collect();
+            let notes = backlinks.into_iter().map(|backlink| backlink.note).collect();
+            let rows = query_rows(config, notes, &query::Query::new(query::Source::Backlinks));
+
+            let mut result = Vec::new();
+            for row in rows {
+                let row_sections = sections.get(&row.note).cloned().unwrap_or_default();
+                for line in format_query_rows(config, current_note, vec![row], query::DEFAULT_FORMAT)? {
+                    if row_sections.is_empty() {
+                        result.push(line);
+                    } else {
+                        result.push(format!("{line} (in {})", row_sections.join(", ")));
+                    }
+                }
+            }
+            Some(result)
+        }
+
+        "seealso" => {
+            let count = autogenerate_arguments.first().and_then(|count| count.parse().ok()).unwrap_or(10);
+
+            let index = note_index::NoteIndex::build(config)?;
+            let current_path = current_note.path(config);
+            let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+            let current_tags = markdown::parse_frontmatter(&current_md)
+                .ok()
+                .map(|frontmatter| frontmatter.tags)
+                .unwrap_or_default();
+            let current_links = note_index::resolved_links(config, current_note, &current_md);
+
+            let corpus = index.notes.iter().map(|indexed| markdown::plain_text(&indexed.md)).collect::<Vec<_>>();
+            let document_frequencies = tfidf::document_frequencies(&corpus);
+            let current_vector = tfidf::tfidf_vector(&markdown::plain_text(&current_md), &document_frequencies, corpus.len());
+
+            let candidates = index
+                .notes
+                .iter()
+                .zip(&corpus)
+                .filter(|(indexed, _)| Some(&indexed.path) != current_path.as_ref() && !current_links.contains(&indexed.path))
+                .map(|(indexed, text)| autogenerate::SeeAlsoCandidate {
+                    note: indexed.note.clone(),
+                    similarity: tfidf::cosine_similarity(&current_vector, &tfidf::tfidf_vector(text, &document_frequencies, corpus.len())),
+                    shared_tags: indexed.tags.iter().filter(|tag| current_tags.contains(tag)).cloned().collect(),
+                    cocitations: current_path.as_ref().map(|current_path| index.cocitations(current_path, &indexed.path)).unwrap_or(0),
+                })
+                .collect();
+
+            let mut result = Vec::new();
+            for candidate in autogenerate::rank_see_also(candidates, count) {
+                let title = index.notes.iter().find(|indexed| indexed.note == candidate.note).and_then(|indexed| indexed.title.clone());
+                let link = links::format_link(config, current_note, &candidate.note.path(config), &title.unwrap_or_default(), None)?;
+                result.push(format!("- suggestion: {}{} ({})", note_icon_prefix(config, &candidate.note), link, candidate.reasons()));
+            }
+
+            Some(result)
+        }
+
+        // every relation, or only the one given, as a list of relations with the related notes under each
+        "relations" => {
+            let wanted = autogenerate_arguments.first().copied().filter(|relation| !relation.is_empty());
+            let mut by_relation: BTreeMap<String, Vec<PhysicalNote>> = BTreeMap::new();
+            for (relation, note) in find_related(config, current_note)? {
+                if wanted.is_none_or(|wanted| wanted == relation) {
+                    by_relation.entry(relation).or_default().push(note);
+                }
+            }
+
+            let relation_query = query::Query { filters: filters.clone(), ..query::Query::new(query::Source::All) };
+            let mut result = Vec::new();
+            for (relation, notes) in by_relation {
+                let rows = query_rows(config, notes, &relation_query);
+                if wanted.is_some() {
+                    result.extend(format_query_rows(config, current_note, rows, query::DEFAULT_FORMAT)?);
+                } else if !rows.is_empty() {
+                    result.push(format!("- {relation}"));
+                    result.extend(format_query_rows(config, current_note, rows, &format!("  {}", query::DEFAULT_FORMAT))?);
+                }
+            }
+            Some(result)
+        }
+
+        // the notes that are linked from (or to, or both) the current note, grouped by how many links away they are
+        "explore" => {
+            let max_depth = autogenerate_arguments.first().and_then(|depth| depth.parse().ok());
+            let direction = autogenerate_arguments.get(1).and_then(|direction| query::ExploreDirection::parse_from_str(direction));
+            let mut by_distance: BTreeMap<usize, Vec<PhysicalNote>> = BTreeMap::new();
+            for (note, distance) in explore_links(config, current_note, max_depth, direction.unwrap_or(query::ExploreDirection::Out))? {
+                by_distance.entry(distance).or_default().push(note);
+            }
+
+            let distance_query = query::Query { filters: filters.clone(), ..query::Query::new(query::Source::All) };
+            let mut result = Vec::new();
+            for (distance, notes) in by_distance {
+                let rows = query_rows(config, notes, &distance_query);
+                if !rows.is_empty() {
+                    result.push(format!("- {distance} {} away", if distance == 1 { "link" } else { "links" }));
+                    result.extend(format_query_rows(config, current_note, rows, &format!("  {}", query::DEFAULT_FORMAT))?);
+                }
+            }
+            Some(result)
+        }
+
+        // commands that are not built in might have been registered from lua
+        _ => match run_custom_autogenerate(config, current_note, autogenerate_command, &all_arguments)? {
+            Some(lines) => Some(lines),
+            None => {
+                api::err_writeln(&format!("error: invalid autogenerate function '{autogenerate_command}'"));
+                None
+            }
+        },
+    })
+}
+
+/// Gives every note in `directory` (or the whole wiki if it is empty) whose name is not an id an id, for notes imported from tools that name
+/// them by title. Ids are picked with `id_strategy`, and timestamp ids come from the note's frontmatter date or else when the file was last
+/// modified. Notes without a title are titled after their old name. The old paths are recorded so that links to them keep resolving if
+/// `resolve_links_by_remap` is set.
+pub fn remap_note_ids(config: &Config, directory: String) -> Result<(), RemapNoteIdsError> {
+    let directories: Vec<String> = directory.split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect();
+    let is_special = |note: &PhysicalNote| {
+        let relative = note.path(config).strip_prefix(&config.home_path).map(Path::to_path_buf).unwrap_or_default();
+        let is_tag_index = config.tag_index_note.as_ref().is_some_and(|tag_index_note| relative == Path::new(tag_index_note));
+        relative.file_name() == Some(std::ffi::OsStr::new(&config.index_filename)) || is_tag_index
+    };
+    let to_remap: Vec<PhysicalNote> = list_all_physical_notes(config)?
+        .into_iter()
+        .filter(|note| note.directories.starts_with(&directories) && !is_special(note))
+        .filter(|note| !note_id::is_id(&note.id, config.id_strategy, &config.note_id_timestamp_format))
+        .collect();
+    if to_remap.is_empty() {
+        api::command(r#"echo "every note already has an id""#)?;
+        return Ok(());
+    }
+    if !confirm::confirm(&config.confirm, confirm::Operation::BulkReplace, &format!("give {} note(s) ids?", to_remap.len()))? {
+        api::command(r#"echo "\nnot remapping""#)?;
+        return Ok(());
+    }
+
+    // held until the table is written so that remaps made by another instance in the meantime are not lost
+    let lock = state::lock_state(config)?;
+    let mut table = match lock.read(remap::REMAP_STATE_FILE)? {
+        Some(contents) => remap::RemapTable::parse(&String::from_utf8_lossy(&contents)),
+        None => remap::RemapTable::default(),
+    };
+    let relative_path = |path: &Path| path.strip_prefix(&config.home_path).unwrap_or(path).to_string_lossy().replace('\\', "/");
+    for note in &to_remap {
+        let old_path = note.path(config);
+        let contents = note.read_contents(config)?;
+        let frontmatter = markdown::parse_markdown(&contents).ok().and_then(|md| markdown::parse_frontmatter(&md).ok()).unwrap_or_default();
+        let created = match frontmatter.timestamp(config) {
+            Ok(timestamp) => timestamp,
+            Err(_) => chrono::DateTime::<chrono::Local>::from(config.store.modified(note)?).naive_local(),
+        };
+        let new_note = new_note_id(config, &note.directories, frontmatter.title.as_deref().unwrap_or(&note.id), &created)?;
+
+        let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        let lines = if frontmatter.title.is_none() { frontmatter::set_field(&lines, "title", &frontmatter::yaml_scalar(&note.id)) } else { lines };
+        config.store.write(&new_note, &(lines.join("\n") + "\n"))?;
+        config.store.delete(note)?;
+        table.insert(relative_path(&old_path), relative_path(&new_note.path(config)));
+    }
+    lock.write(remap::REMAP_STATE_FILE, table.format().as_bytes())?;
+
+    api::command(&format!(r#"echo "gave {} note(s) ids""#, to_remap.len()))?;
+    Ok(())
+}
+
+/// Regenerates the autogenerated sections of every note that has any and saves the ones that changed, then lists them in the quickfix list.
+/// Each note is loaded into a hidden buffer so that regenerating it works the same way as regenerating the current buffer. Notes that are
+/// open with unsaved changes are skipped, since saving the regenerated sections would save those changes too.
+pub fn regenerate_all(config: &Config) -> Result<(), RegenerateAllError> {
+    let mut changed = Vec::new();
+    let mut skipped_count = 0;
+    for note in list_all_physical_notes(config)? {
+        let Ok(contents) = note.read_contents(config) else {
+            continue;
+        };
+        if !contents.contains("wikiplugin_autogenerate") {
+            continue;
+        }
+
+        let path = note.path(config).to_string_lossy().into_owned();
+        let existed: bool = api::call_function("bufexists", Array::from_iter([Object::from(path.clone())]))?;
+        let buffer = api::Buffer::from(api::call_function::<_, i32>("bufadd", Array::from_iter([Object::from(path.clone())]))?);
+        if buffer.is_loaded() && api::get_option_value::<bool>("modified", &OptionOpts::builder().buffer(buffer.clone()).build())? {
+            skipped_count += 1;
+            continue;
+        }
+        // loading without autocommands keeps the BufRead autocommand from regenerating the sections before they can be compared
+        api::command(&format!("noautocmd call bufload({})", buffer.handle()))?;
+
+        let before = get_buffer_lines(&buffer)?;
+        let note_config = config.clone();
+        match buffer.call(move |_| regenerate_autogenerated_sections(&note_config)) {
+            Ok(()) if get_buffer_lines(&buffer)? != before => {
+                buffer.call(|_| api::command("silent noautocmd write"))?;
+                changed.push(note);
+            }
+            Ok(()) => {}
+            Err(e) => api::err_writeln(&format!("error regenerating {path}: {e}")),
+        }
+        if !existed {
+            api::command(&format!("noautocmd bwipeout {}", buffer.handle()))?;
+        }
+    }
+
+    let quickfix_items: Vec<Object> = changed
+        .iter()
+        .map(|note| {
+            Object::from(Dictionary::from_iter([
+                ("filename", Object::from(note.path(config).to_string_lossy().into_owned())),
+                ("lnum", Object::from(1)),
+                ("text", Object::from("regenerated")),
+            ]))
+        })
+        .collect();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+
+    let skipped = if skipped_count > 0 { format!(", skipped {skipped_count} with unsaved changes") } else { String::new() };
+    api::command(&format!(r#"echo "regenerated {} note(s){skipped}""#, changed.len()))?;
+    Ok(())
+}
+
+/// Lists every autogenerated section in the wiki in the quickfix list, with its command, its arguments, and whether regenerating it would
+/// change it. Nothing is regenerated; notes that are open with unsaved changes are listed without being checked.
+pub fn list_autogenerated_sections(config: &Config) -> Result<(), ListAutogeneratedSectionsError> {
+    let mut quickfix_items = Vec::new();
+    let mut note_count = 0;
+    let mut stale_count = 0;
+    for note in list_all_physical_notes(config)? {
+        let Ok(contents) = note.read_contents(config) else {
+            continue;
+        };
+        if !contents.contains("wikiplugin_autogenerate") {
+            continue;
+        }
+
+        let path = note.path(config).to_string_lossy().into_owned();
+        let existed: bool = api::call_function("bufexists", Array::from_iter([Object::from(path.clone())]))?;
+        let buffer = api::Buffer::from(api::call_function::<_, i32>("bufadd", Array::from_iter([Object::from(path.clone())]))?);
+        let unsaved = buffer.is_loaded() && api::get_option_value::<bool>("modified", &OptionOpts::builder().buffer(buffer.clone()).build())?;
+        // loading without autocommands keeps the BufRead autocommand from regenerating the sections before they can be checked
+        api::command(&format!("noautocmd call bufload({})", buffer.handle()))?;
+
+        let lines = get_buffer_lines(&buffer)?;
+        let sections = autogenerate::find_marked_sections(&lines);
+        if !sections.is_empty() {
+            note_count += 1;
+        }
+        let current_note = Note::Physical(note);
+        for section in sections {
+            let status = match section.end_line {
+                _ if unsaved => "unsaved changes, not checked",
+                None => "stale, missing its end marker",
+                Some(end_line) => match generate_section(config, &current_note, &buffer, &section, section.start_line) {
+                    Ok(Some(replacement)) if replacement[..] == lines[section.start_line + 1..end_line] => "up to date",
+                    Ok(Some(_)) => "stale",
+                    Ok(None) => "invalid command",
+                    Err(e) => {
+                        api::err_writeln(&format!("error generating section on line {} of {path}: {e}", section.start_line + 1));
+                        "error"
+                    }
+                },
+            };
+            if status.starts_with("stale") {
+                stale_count += 1;
+            }
+            let command = if section.arguments.trim().is_empty() {
+                section.command.clone()
+            } else {
+                format!("{} {}", section.command, section.arguments.trim())
+            };
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(path.clone())),
+                ("lnum", Object::from(section.start_line as i64 + 1)),
+                ("text", Object::from(format!("{command}: {status}"))),
+            ])));
+        }
+        if !existed {
+            api::command(&format!("noautocmd bwipeout {}", buffer.handle()))?;
+        }
+    }
+
+    let section_count = quickfix_items.len();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    api::command(&format!(r#"echo "{section_count} autogenerated section(s) in {note_count} note(s), {stale_count} stale""#))?;
+    Ok(())
+}
+
+/// Returns the lines of a summary of the current note, for the floating window that the lua side opens.
+pub fn note_info(config: &Config) -> Result<Vec<String>, NoteInfoError> {
+    let note = match Note::get_current_note(config)? {
+        Note::Physical(note) => note,
+        Note::Scratch(_) => Err(NotAPhysicalNote)?,
+    };
+    let path = note.path(config);
+    // the buffer is used instead of the file so that unsaved changes are counted
+    let md = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))?;
+    let frontmatter = markdown::parse_frontmatter(&md).unwrap_or_default();
+
+    let datetime_format = format!("{} {}", config.date_format, config.time_format);
+    let created = frontmatter.timestamp(config).map_or("unknown".to_string(), |timestamp| timestamp.format(&datetime_format).to_string());
+    let modified = config
+        .store
+        .modified(&note)
+        .map_or("never saved".to_string(), |modified| config.format_datetime(&modified.into(), &datetime_format));
+    let directories = if note.directories.is_empty() { "(top level)".to_string() } else { note.directories.join("/") };
+    let tags = frontmatter.tags.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+
+    let mut lines = vec![
+        format!("# {}", frontmatter.title.as_deref().unwrap_or("(untitled)")),
+        format!("id: {}", note.id),
+        format!("directories: {directories}"),
+        format!("created: {created}"),
+        format!("modified: {modified}"),
+        format!("tags: {}", if tags.is_empty() { "(none)" } else { &tags }),
+    ];
+    if !frontmatter.aliases.is_empty() {
+        lines.push(format!("aliases: {}", frontmatter.aliases.join(", ")));
+    }
+    lines.push(format!("words: {}", markdown::plain_text(&md).split_whitespace().count()));
+    lines.push(format!("backlinks: {}", find_backlinks(config, &path)?.len()));
+
+    Ok(lines)
+}
+
+fn current_completion_context() -> Result<Option<completion::CompletionContext>, api::Error> {
+    let (row, col) = api::get_current_win().get_cursor()?;
+    Ok(completion::completion_context(&get_buffer_lines(&api::get_current_buf())?, row - 1, col))
+}
+
+/// The first call of the omnifunc: returns the column where the completed text starts, or -3 (which cancels completion without an error
+/// message) if the cursor is not in a link or the tags field.
+pub fn omnifunc_start() -> Result<i64, api::Error> {
+    Ok(current_completion_context()?.map_or(-3, |context| context.start() as i64))
+}
+
+/// The second call of the omnifunc: returns the notes (or tags) that match what has been typed so far. Notes match on their id, title, and
+/// aliases.
+pub(crate) fn omnifunc_complete(config: &Config, base: String) -> Result<Vec<[(&'static str, String); 3]>, OmnifuncError> {
+    let Some(context) = current_completion_context()? else {
+        return Ok(Vec::new());
+    };
+    let current_note = Note::get_current_note(config)?;
+
+    let mut matches = Vec::new();
+    let mut tags = BTreeSet::new();
+    for note in list_all_physical_notes(config)? {
+        let frontmatter = note
+            .read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+            .unwrap_or_default();
+
+        if let completion::CompletionContext::Tag { .. } = context {
+            tags.extend(frontmatter.tags.iter().map(ToString::to_string));
+            continue;
+        }
+
+        let title = frontmatter.title.clone().unwrap_or_default();
+        let names: Vec<&str> = [note.id.as_str(), title.as_str()].into_iter().chain(frontmatter.aliases.iter().map(String::as_str)).collect();
+        if !completion::matches_base(&base, &names) {
+            continue;
+        }
+        let word = match context {
+            completion::CompletionContext::Path { .. } if !config.link_style.links_by_id() => {
+                links::format_link_path(config, &current_note, &note.path(config), None)?
+            }
+            _ => note.id.clone(),
+        };
+        let abbr = if title.is_empty() { word.clone() } else { title };
+        matches.push([("word", word), ("abbr", format!("{}{abbr}", note_icon_prefix(config, &note))), ("menu", note.id.clone())]);
+    }
+
+    matches.extend(
+        tags.into_iter()
+            .filter(|tag| completion::matches_base(&base, &[tag.as_str()]))
+            .map(|tag| [("word", tag.clone()), ("abbr", tag), ("menu", "tag".to_string())]),
+    );
+    Ok(matches)
+}
 
-    if focus {
-        api::cmd(&CmdInfos::builder().cmd("edit").args([note_path.to_str().ok_or(NonUtf8Path)?]).build(), &CmdOpts::builder().build())?;
+/// Calls the handler that was registered for `command` with `register_autogenerate` on the lua side, passing it the arguments (filters
+/// included) and a table describing the current note. Returns `None` if no handler is registered.
+fn run_custom_autogenerate(config: &Config, current_note: &Note, command: &str, arguments: &[&str]) -> Result<Option<Vec<String>>, api::Error> {
+    let mut context = vec![("home_path", Object::from(config.home_path.to_string_lossy().into_owned()))];
+    if let Note::Physical(note) = current_note {
+        context.push(("note_path", Object::from(note.path(config).to_string_lossy().into_owned())));
+        context.push(("note_id", Object::from(note.id.clone())));
+        context.push(("directories", Object::from(Array::from_iter(note.directories.iter().map(|directory| Object::from(directory.clone()))))));
     }
+    api::call_function(
+        "luaeval",
+        Array::from_iter([
+            Object::from("require('wikiplugin').run_custom_autogenerate(_A[1], _A[2], _A[3])"),
+            Object::from(Array::from_iter([
+                Object::from(command),
+                Object::from(Array::from_iter(arguments.iter().map(|argument| Object::from(*argument)))),
+                Object::from(Dictionary::from_iter(context)),
+            ])),
+        ]),
+    )
+}
 
-    Ok(Note::new_physical(directories, note_id))
+/// Runs a query from an autogenerated section, returning the notes that it selects in the order that it asks for. Notes that cannot be
+/// read or parsed never pass any filters.
+fn run_query(config: &Config, current_note: &Note, query: &query::Query) -> Result<Vec<query::QueryRow>, AutogenerateError> {
+    let notes: Vec<PhysicalNote> = match &query.source {
+        query::Source::All => list_all_physical_notes(config)?,
+        query::Source::Directory { directories, recursive } => list_all_physical_notes(config)?
+            .into_iter()
+            .filter(|note| note.directories == *directories || (*recursive && note.directories.starts_with(directories)))
+            .collect(),
+        query::Source::Backlinks => match current_note.path(config) {
+            // query rows are notes in this wiki, so backlinks from other wikis are left out
+            Some(current_path) => find_backlinks(config, &current_path)?
+                .into_iter()
+                .filter(|backlink| backlink.path.starts_with(&config.home_path))
+                .map(|backlink| backlink.note)
+                .collect(),
+            None => Vec::new(),
+        },
+        query::Source::Explore { max_depth, direction } => explore_links(config, current_note, *max_depth, *direction)?.into_keys().collect(),
+        query::Source::Related(relation) => {
+            find_related(config, current_note)?.into_iter().filter(|(other, _)| other == relation).map(|(_, note)| note).collect()
+        }
+    };
+    Ok(query_rows(config, notes, query))
 }
 
-pub fn open_index(config: &Config) -> Result<(), ApiErrorOrNonUtf8Path> {
-    let index_path = config.home_path.join("index.md");
-    let index_path: &str = index_path.to_str().ok_or(NonUtf8Path)?;
-    api::cmd(&api::types::CmdInfos::builder().cmd("edit").args([index_path]).build(), &api::opts::CmdOpts::default())?;
+/// Filters, sorts, and limits `notes` as `query` says, ignoring its source.
+fn query_rows(config: &Config, notes: Vec<PhysicalNote>, query: &query::Query) -> Vec<query::QueryRow> {
+    let mut rows = Vec::new();
+    for note in notes {
+        let frontmatter = note
+            .read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok());
+        if !query.filters.is_empty() && !frontmatter.as_ref().is_some_and(|f| query.filters.iter().all(|filter| filter.matches(f))) {
+            continue;
+        }
+        let created = frontmatter.as_ref().and_then(|f| f.timestamp(config).ok());
+        let modified = config
+            .store
+            .modified(&note)
+            .ok()
+            .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).naive_local());
+        rows.push(query::QueryRow { note, frontmatter, created, modified });
+    }
 
-    Ok(())
+    if let Some(sort) = query.sort {
+        query::sort_rows(&mut rows, sort);
+    }
+    if let Some(limit) = query.limit {
+        rows.truncate(limit);
+    }
+    rows
 }
 
-pub fn new_note_and_insert_link(config: &Config, template: Option<String>, directories: Vec<String>) -> Result<(), InsertLinkError> {
-    let new_note = new_note(config, template, directories, false)?;
-    insert_link_at_cursor(config, &new_note, None)?;
-    Ok(())
+/// Formats each row as a list item. `format_index_item` from the config takes over from the default format, but not from one that the query
+/// gave.
+fn format_query_rows(config: &Config, current_note: &Note, rows: Vec<query::QueryRow>, format: &str) -> Result<Vec<String>, AutogenerateError> {
+    let indent = &format[..format.len() - format.trim_start().len()];
+    let item_callback = config.format_index_item.as_ref().filter(|_| format.trim_start() == query::DEFAULT_FORMAT);
+    let mut result = Vec::new();
+    for row in rows {
+        if let Some(item) = run_format_callback(item_callback, || note_meta(config, &row.note, row.frontmatter.as_ref())) {
+            result.push(format!("{indent}{item}"));
+            continue;
+        }
+
+        let path = row.note.path(config);
+        let link_text = run_format_callback(config.format_link_text.as_ref(), || note_meta(config, &row.note, row.frontmatter.as_ref()));
+        let link = links::format_link(config, current_note, &path, link_text.as_deref().or(row.title()).unwrap_or_default(), None)?;
+        let link_path = links::format_link_path(config, current_note, &path, None)?;
+        result.push(query::fill_format(format, |name| match name {
+            "icon" => Some(note_icon_prefix(config, &row.note)),
+            "link" => Some(link.clone()),
+            "path" => Some(link_path.clone()),
+            "badges" => Some(row.badges(&config.autogenerate_badges, &config.date_format)),
+            _ => row.value(name, &config.date_format),
+        }));
+    }
+    Ok(result)
 }
 
-pub fn insert_link_to_path_at_cursor_or_create(config: &Config, link_to: Option<String>, link_text: Option<String>) -> Result<(), InsertLinkError> {
-    let n;
-    let note = match link_to {
-        Some(link_to_path) => {
-            let path = Path::new(&link_to_path);
-            n = Note::Physical(PhysicalNote::parse_from_filepath(config, path)?);
-            Some(&n)
+/// Every note that can be reached by following links from `root` in `direction`, up to `max_depth` links away, with how many links away it
+/// is. `root` itself is not included, and neither are links that do not point to an existing note.
+fn explore_links(
+    config: &Config,
+    root: &Note,
+    max_depth: Option<usize>,
+    direction: query::ExploreDirection,
+) -> Result<BTreeMap<PhysicalNote, usize>, AutogenerateError> {
+    let incoming = if direction == query::ExploreDirection::Out { BTreeMap::new() } else { incoming_links(config)? };
+
+    let mut distances = BTreeMap::new();
+    // breadth first so that every note is found at its shortest distance
+    let mut frontier = VecDeque::from([(root.clone(), 0)]);
+    while let Some((current, distance)) = frontier.pop_front() {
+        if max_depth.is_some_and(|max_depth| distance >= max_depth) {
+            continue;
         }
-        None => None,
-    };
 
-    insert_link_at_cursor_or_create(config, note, link_text)?;
+        let mut neighbours = BTreeSet::new();
+        if direction != query::ExploreDirection::In {
+            neighbours.extend(outgoing_links(config, &current));
+        }
+        if let Note::Physical(current) = &current {
+            neighbours.extend(incoming.get(current).into_iter().flatten().cloned());
+        }
+        for neighbour in neighbours {
+            let neighbour_as_note = Note::Physical(neighbour.clone());
+            if neighbour_as_note != *root && !distances.contains_key(&neighbour) {
+                distances.insert(neighbour, distance + 1);
+                frontier.push_back((neighbour_as_note, distance + 1));
+            }
+        }
+    }
+    Ok(distances)
+}
+
+/// The existing notes that a note links to. A note that cannot be read or parsed links to nothing.
+fn outgoing_links(config: &Config, note: &Note) -> BTreeSet<PhysicalNote> {
+    let Some(md) = note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok()) else {
+        return BTreeSet::new();
+    };
+    markdown::get_all_link_targets(&md)
+        .into_iter()
+        .filter(|link| !links::is_external_link(config, link))
+        .filter_map(|link| links::resolve_link_path(config, note, &link).ok())
+        .filter(|path| wiki_file_exists(config, path))
+        .filter_map(|path| PhysicalNote::parse_from_filepath(config, &path).ok())
+        .collect()
+}
 
-    Ok(())
+/// Every note's backlinks, by the note that they link to.
+fn incoming_links(config: &Config) -> Result<BTreeMap<PhysicalNote, BTreeSet<PhysicalNote>>, ListAllPhysicalNotesError> {
+    let mut incoming: BTreeMap<PhysicalNote, BTreeSet<PhysicalNote>> = BTreeMap::new();
+    for note in list_all_physical_notes(config)? {
+        for linked in outgoing_links(config, &Note::Physical(note.clone())) {
+            if linked != note {
+                incoming.entry(linked).or_default().insert(note.clone());
+            }
+        }
+    }
+    Ok(incoming)
 }
 
-pub fn insert_link_at_cursor_or_create(config: &Config, link_to: Option<&Note>, link_text: Option<String>) -> Result<(), InsertLinkError> {
-    let note = match link_to {
-        Some(link_to) => link_to,
-        None => &new_note(config, None, Vec::new(), false)?, // TODO: figure out a cleaner way to pass these arguments instead of assuming a default
+/// Every note that is related to `current_note` by a typed link, with the relation from the point of view of `current_note`: the notes that
+/// it links to with a relation, and the notes that link to it with a relation that has an inverse. Links that do not resolve are skipped.
+fn find_related(config: &Config, current_note: &Note) -> Result<BTreeSet<(String, PhysicalNote)>, AutogenerateError> {
+    let mut related = BTreeSet::new();
+    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+    for link in markdown::get_all_typed_links(&current_md) {
+        let Ok(path) = links::resolve_link_path(config, current_note, &link.target) else {
+            continue;
+        };
+        if let Ok(note) = PhysicalNote::parse_from_filepath(config, &path) {
+            related.insert((link.relation, note));
+        }
+    }
+
+    let Some(current_path) = current_note.path(config) else {
+        return Ok(related);
     };
-    insert_link_at_cursor(config, note, link_text)?;
-    Ok(())
+    for other_note in list_all_physical_notes(config)? {
+        let Some(md) = other_note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok()) else {
+            continue;
+        };
+        let other_note_as_note = Note::Physical(other_note.clone());
+        for link in markdown::get_all_typed_links(&md) {
+            let Some(inverse) = relations::inverse(&link.relation) else {
+                continue;
+            };
+            if links::resolve_link_path(config, &other_note_as_note, &link.target).is_ok_and(|path| path == current_path) {
+                related.insert((inverse.to_string(), other_note.clone()));
+            }
+        }
+    }
+    Ok(related)
 }
 
-pub fn insert_link_at_cursor(config: &Config, link_to: &Note, link_text: Option<String>) -> Result<(), InsertLinkError> {
-    match link_to {
-        Note::Physical(link_to) => {
-            let link_text = match link_text {
-                Some(lt) => lt,
-                None => link_to
-                    .read_contents(config)
-                    .ok()
-                    .and_then(|contents| markdown::parse_markdown(&contents).ok())
-                    .and_then(|markdown| markdown::parse_frontmatter(&markdown).ok())
-                    .and_then(|frontmatter| markdown::get_title(&frontmatter).ok())
-                    .unwrap_or_default(),
+struct Backlink {
+    /// relative to the wiki that it is in, which is not always this one
+    note: PhysicalNote,
+    path: PathBuf,
+    title: Option<String>,
+    /// the lines that the links are on, each with the headings that it is under, outermost first
+    context: Vec<(Vec<String>, String)>,
+}
+
+impl Backlink {
+    /// The sections of the linking note that the links are in, as breadcrumbs like `Projects / Garden`, without repeats. Links above every
+    /// heading are not in any section.
+    fn sections(&self) -> Vec<String> {
+        let mut sections: Vec<String> = Vec::new();
+        for (headings, _) in &self.context {
+            let breadcrumbs = headings.join(" / ");
+            if !breadcrumbs.is_empty() && !sections.contains(&breadcrumbs) {
+                sections.push(breadcrumbs);
+            }
+        }
+        sections
+    }
+}
+
+/// Finds every note in any configured wiki that links to the target path. Notes that cannot be read or parsed are skipped.
+fn find_backlinks(config: &Config, target: &Path) -> Result<Vec<Backlink>, ListAllPhysicalNotesError> {
+    // TODO: this is extremely slow
+    let mut backlinks = Vec::new();
+    // a wiki inside another wiki has its notes listed by both of them
+    let mut seen_paths = BTreeSet::new();
+    for (_, wiki) in all_wikis(config) {
+        for other_note in list_all_physical_notes(&wiki)? {
+            let other_note_path = other_note.path(&wiki);
+            if other_note_path == target || !seen_paths.insert(other_note_path.clone()) {
+                continue;
+            }
+
+            let Ok(contents) = other_note.read_contents(&wiki) else {
+                continue;
+            };
+            let Ok(md) = markdown::parse_markdown(&contents) else {
+                continue;
             };
 
-            let current_note = Note::get_current_note(config)?;
-            let link_path_text = links::format_link_path(config, &current_note, &link_to.path(config))?;
-            // TODO: this is a workaround because calling api::put directly causes nvim to crash and i cannot figure out why
-            api::command(&format!(r##"lua vim.api.nvim_put({{ "[{link_text}]({link_path_text})" }}, 'c', false, true)"##))?;
-            // api::put([format!("[{link_text}]({link_path_text})")].into_iter(), api::types::RegisterType::Charwise, false, true)?;
+            // links are resolved with the settings of the wiki that they are in, which is where interwiki links come in
+            let other_note_as_note = Note::Physical(other_note.clone());
+            let mut context_line_indexes = BTreeSet::new();
+            for (link, offset) in markdown::get_all_link_targets_with_offsets(&md) {
+                if !links::is_external_link(&wiki, &link)
+                    && links::resolve_link_path(&wiki, &other_note_as_note, &link).is_ok_and(|link_to| link_to == target)
+                {
+                    context_line_indexes.insert(contents[..offset].matches('\n').count());
+                }
+            }
 
-            Ok(())
+            if !context_line_indexes.is_empty() {
+                let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+                backlinks.push(Backlink {
+                    title: markdown::parse_frontmatter(&md).ok().and_then(|frontmatter| frontmatter.title),
+                    context: context_line_indexes
+                        .into_iter()
+                        .filter_map(|i| {
+                            let headings = splits::heading_path(&lines, i).into_iter().map(ToString::to_string).collect();
+                            Some((headings, lines.get(i)?.trim().to_string()))
+                        })
+                        .collect(),
+                    note: other_note,
+                    path: other_note_path,
+                });
+            }
         }
-        Note::Scratch(_) => Err(CannotLinkToScratchNote)?,
     }
+    Ok(backlinks)
 }
 
-pub fn open_tag_index(config: &Config) -> Result<(), TagIndexError> {
-    // TODO: figure out how to get appropriate keymappings on this file
-    let notes = list_all_physical_notes(config)?;
-    let mut tag_table: BTreeMap<Tag, Vec<(&PhysicalNote, String, PathBuf)>> = BTreeMap::new(); // TODO: eventually this should become &(Note, String, PathBuf)
-    let mut tag_list = BTreeSet::new();
-
-    for note in &notes {
-        let frontmatter = markdown::parse_frontmatter(&markdown::parse_markdown(&note.read_contents(config)?)?)?; // TODO: do not error out on these and just don't list these files?
-        let title = markdown::get_title(&frontmatter).unwrap_or_default();
-        let tags = markdown::get_tags(&frontmatter).unwrap_or_default();
-        let path = note.path(config);
+// the buffer variable that marks a buffer as a backlinks panel, holding the path of the note that the panel is for
+const BACKLINKS_PANEL_VAR: &str = "wikiplugin_backlinks_panel_target";
 
-        for tag in tags {
-            tag_table.entry(tag.clone()).or_default().push((note, title.clone(), path.clone()));
-            tag_list.insert(tag);
+/// How many split points are suggested for each note that is too big.
+const MAX_SPLIT_POINTS_PER_NOTE: usize = 3;
+
+/// Puts the best places to split every note with more than `split_word_threshold` words or `split_heading_threshold` headings in the
+/// quickfix list. Sections that other notes link to are suggested first, since they are already being used as notes of their own.
+pub fn suggest_splits(config: &Config) -> Result<(), SuggestSplitsError> {
+    // the anchors of the links between notes, by the path of the note that they point into
+    let mut anchors: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let mut notes = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let Some((contents, md)) =
+            note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok().map(|md| (contents, md)))
+        else {
+            continue;
+        };
+        let as_note = Note::Physical(note.clone());
+        for link in markdown::get_all_link_targets(&md) {
+            let Some(anchor) = links::split_anchor(&link).1.filter(|_| !links::is_external_link(config, &link)) else {
+                continue;
+            };
+            if let Ok(target) = links::resolve_link_path(config, &as_note, &link) {
+                anchors.entry(target).or_default().push(anchor.to_string());
+            }
         }
+        let word_count = markdown::plain_text(&md).split_whitespace().count();
+        notes.push((note, contents, word_count));
     }
 
-    let mut buffer = api::create_buf(true, true)?;
-    api::set_option_value("filetype", "markdown", &OptionOpts::builder().scope(OptionScope::Local).buffer(buffer.clone()).build())?;
+    let mut quickfix_items = Vec::new();
+    for (note, contents, word_count) in notes {
+        let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        let heading_count = splits::headings(&lines).len();
+        if word_count <= config.split_word_threshold && heading_count <= config.split_heading_threshold {
+            continue;
+        }
 
-    let mut lines = Vec::new();
-    for tag in tag_list {
-        lines.extend([format!("# {tag}"), "".to_string()]);
-        for (_, note_title, note_path) in &tag_table[&tag] {
-            lines.extend([format!("- [{}]({})", note_title, note_path.to_str().ok_or(NonUtf8Path)?)]);
+        let path = note.path(config);
+        let note_anchors: Vec<&str> = anchors.get(&path).into_iter().flatten().map(String::as_str).collect();
+        for point in splits::split_points(&lines, &note_anchors).into_iter().take(MAX_SPLIT_POINTS_PER_NOTE) {
+            quickfix_items.push(Object::from(Dictionary::from_iter([
+                ("filename", Object::from(path.to_str().ok_or(NonUtf8Path)?)),
+                ("lnum", Object::from(point.line as i64 + 1)),
+                (
+                    "text",
+                    Object::from(format!(
+                        "{word_count} words, {heading_count} headings: split off '{}' ({} inbound link(s), {} words)",
+                        point.heading, point.inbound_links, point.words
+                    )),
+                ),
+            ])));
         }
-        lines.extend(["".to_string()]);
     }
 
-    buffer.set_lines(0..0, false, lines)?;
-    api::set_current_buf(&buffer)?;
+    let suggestion_count = quickfix_items.len();
+    api::call_function::<_, i64>("setqflist", Array::from_iter([Object::from(Array::from_iter(quickfix_items)), Object::from("r")]))?;
+    api::command(&format!(r#"echo "{suggestion_count} split suggestion(s)""#))?;
 
     Ok(())
 }
 
-pub fn follow_link(config: &Config) -> Result<(), FollowLinkError> {
+/// Numbers the items of the numbered list that the cursor is in from 1, for structure notes that lay out a sequence of notes by hand. With
+/// `sync_frontmatter`, the note that each item links to (the first link in the item) gets its position as its `sequence` field.
+pub fn renumber_sequence_section(config: &Config, sync_frontmatter: bool) -> Result<(), RenumberSequenceError> {
     let current_note = Note::get_current_note(config)?;
-    let current_md = markdown::parse_markdown(&current_note.read_contents(config)?)?;
+    let mut buffer = api::get_current_buf();
+    let lines = get_buffer_lines(&buffer)?;
+    let (row, _) = api::get_current_win().get_cursor()?;
+    let range = sequence::sequence_at(&lines, row - 1).ok_or(NotInASequence)?;
+    let (new_lines, item_lines) = sequence::renumber(&lines[range.clone()]);
+    buffer.set_lines(range, false, new_lines.clone())?;
+    if !sync_frontmatter {
+        api::command(&format!(r#"echo "renumbered {} item(s)""#, item_lines.len()))?;
+        return Ok(());
+    }
 
-    let cursor_byte_index: usize = nvim_oxi::api::eval(r#"line2byte(line(".")) + col(".") - 1 - 1"#)?;
-    let (_, link_path) = markdown::rec_find_preorder(&current_md, &mut |node| match node {
-        ::markdown::mdast::Node::Link(::markdown::mdast::Link { children: _, position: Some(position), url, title: _ }) => {
-            if markdown::point_in_position(position, cursor_byte_index) {
-                Some(url.to_string())
-            } else {
-                None
+    let mut changed = Vec::new();
+    for (position, item_line) in item_lines.iter().enumerate() {
+        let Some(target) = markdown::parse_markdown(&new_lines[*item_line])
+            .ok()
+            .and_then(|md| markdown::get_all_link_spans(&md).into_iter().next())
+            .map(|span| span.target)
+        else {
+            continue;
+        };
+        if links::is_external_link(config, &target) {
+            continue;
+        }
+        let Some(note) =
+            links::resolve_link_path(config, &current_note, &target).ok().and_then(|path| PhysicalNote::parse_from_filepath(config, &path).ok())
+        else {
+            continue;
+        };
+        let Ok(contents) = note.read_contents(config) else { continue };
+        let note_lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        let updated = frontmatter::set_field(&note_lines, "sequence", &(position + 1).to_string());
+        if updated != note_lines {
+            let mut new_contents = updated.join("\n");
+            if contents.ends_with('\n') {
+                new_contents.push('\n');
             }
+            changed.push((note, new_contents));
         }
-        _ => None,
-    })
-    .ok_or(NotOnALink)?;
+    }
+    let changed_note_count = changed.len();
+    for (note, new_contents) in changed {
+        config.store.write(&note, &new_contents)?;
+    }
 
-    let new_note_path = links::resolve_link_path(config, &current_note, &link_path)?;
+    // reload any notes that are open in buffers
+    api::command("checktime")?;
+    api::command(&format!(r#"echo "renumbered {} item(s) and updated the sequence of {changed_note_count} note(s)""#, item_lines.len()))?;
+    Ok(())
+}
 
-    api::cmd(
-        &api::types::CmdInfos::builder().cmd("edit").args([new_note_path.to_str().ok_or(NonUtf8Path)?]).build(),
-        &api::opts::CmdOpts::default(),
-    )?;
+/// Moves the section that the cursor is in into a new note in the same directory, titled with the section's heading. The heading stays
+/// where it was, with a link to the new note under it, so that links to the heading still lead somewhere.
+pub fn extract_section(config: &Config) -> Result<(), ExtractSectionError> {
+    let current_note = match Note::get_current_note(config)? {
+        Note::Physical(note) => note,
+        Note::Scratch(_) => Err(NotAPhysicalNote)?,
+    };
+    let mut buffer = api::get_current_buf();
+    let lines = get_buffer_lines(&buffer)?;
+    let (row, _) = api::get_current_win().get_cursor()?;
+    let (heading_line, end) = splits::section_at(&lines, row - 1).ok_or(NotInASection)?;
+    let (_, heading) = markdown::atx_heading(&lines[heading_line]).expect("section_at always starts sections at a heading");
+
+    let new_note = create_note_from_lines(config, &current_note.directories, heading, &lines[heading_line + 1..end])?;
+    let link = links::format_link(config, &Note::Physical(current_note), &new_note.path(config), heading, None)?;
+    buffer.set_lines(heading_line + 1..end, false, [String::new(), link, String::new()])?;
 
     Ok(())
 }
 
-pub fn delete_note() -> Result<(), DeleteNoteError> {
-    let current_buf_path_str: String = nvim_oxi::api::eval(r#"expand("%:p")"#)?;
-    let current_buf_path = Path::new(&current_buf_path_str);
+/// Does what [`extract_section`] does to every section under a heading at `level` in the current note, or under the headings that
+/// `suggest_splits` would split at if it is not given. Sections with nothing under their heading are left alone.
+pub fn split_note_by_headings(config: &Config, level: Option<usize>) -> Result<(), SplitNoteByHeadingsError> {
+    let current_note = match Note::get_current_note(config)? {
+        Note::Physical(note) => note,
+        Note::Scratch(_) => Err(NotAPhysicalNote)?,
+    };
+    let mut buffer = api::get_current_buf();
+    let lines = get_buffer_lines(&buffer)?;
+    let sections: Vec<(usize, usize)> = splits::sections_at_level(&lines, level)
+        .into_iter()
+        .filter(|(heading_line, end)| lines[heading_line + 1..*end].iter().any(|line| !line.trim().is_empty()))
+        .collect();
+    if sections.is_empty() {
+        Err(NoSectionsToSplit)?;
+    }
 
-    let choice: String =
-        nvim_oxi::api::eval(r#"input("are you sure you want to delete this note?\noptions: 'yes' for yes, anything else for no\ninput: ")"#)?;
-    if choice == "yes" {
-        std::fs::remove_file(current_buf_path)?;
-        api::command(&format!(r#"echo "\n{} deleted""#, current_buf_path.to_string_lossy()))?;
-    } else {
-        api::command(r#"echo "\nnot deleting""#)?;
+    // the notes are made in order so that timestamp ids follow the order of the sections
+    let mut new_links = Vec::new();
+    for (heading_line, end) in &sections {
+        let (_, heading) = markdown::atx_heading(&lines[*heading_line]).expect("sections always start at a heading");
+        let new_note = create_note_from_lines(config, &current_note.directories, heading, &lines[heading_line + 1..*end])?;
+        new_links.push(links::format_link(config, &Note::Physical(current_note.clone()), &new_note.path(config), heading, None)?);
+    }
+    // replace back to front so that the lines of the sections that have not been replaced yet stay where they were
+    for ((heading_line, end), link) in sections.iter().zip(new_links).rev() {
+        buffer.set_lines(heading_line + 1..*end, false, [String::new(), link, String::new()])?;
     }
+
+    api::command(&format!(r#"echo "split {} section(s) into notes""#, sections.len()))?;
     Ok(())
 }
 
-pub fn regenerate_autogenerated_sections(config: &Config) -> Result<(), AutogenerateError> {
+/// Picks an id for a new note in `directories` with the configured strategy, putting a suffix on it if it is taken. Timestamp ids are
+/// `timestamp` in `note_id_timestamp_format`.
+fn new_note_id(
+    config: &Config,
+    directories: &[String],
+    title: &str,
+    timestamp: &chrono::NaiveDateTime,
+) -> Result<PhysicalNote, ListAllPhysicalNotesError> {
+    let in_directories = |id: String| PhysicalNote { directories: directories.to_vec(), id };
+    let id = match config.id_strategy {
+        note_id::IdStrategy::Timestamp => timestamp.format(&config.note_id_timestamp_format).to_string(),
+        note_id::IdStrategy::Slug => note_id::slug_id(title),
+        note_id::IdStrategy::Uuid => {
+            use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+            let mut bytes = [0; 16];
+            OsRng.fill_bytes(&mut bytes);
+            note_id::uuid_id(bytes)
+        }
+        note_id::IdStrategy::Sequential => {
+            let notes = config.store.list()?;
+            note_id::sequential_id(notes.iter().filter(|note| note.directories == directories).map(|note| note.id.as_str()))
+        }
+    };
+    Ok(in_directories(note_id::unique_id(id, |id| config.store.exists(&in_directories(id.to_string())))))
+}
+
+/// Writes a new note with the given title and body, without going through a template.
+fn create_note_from_lines(config: &Config, directories: &[String], title: &str, body: &[String]) -> Result<PhysicalNote, NewNoteError> {
+    let now = chrono::Local::now();
+    let note = new_note_id(config, directories, title, &now.naive_local())?;
+
+    let frontmatter: Vec<String> = render_template(config, None, title.to_string(), &now)?.lines().map(ToString::to_string).collect();
+    let mut lines = frontmatter::set_field(&frontmatter, "title", &frontmatter::yaml_scalar(title));
+    lines.push(String::new());
+    lines.extend(body.iter().skip_while(|line| line.trim().is_empty()).cloned());
+
+    config.store.write(&note, &(lines.join("\n") + "\n"))?;
+    Ok(note)
+}
+
+const HEATMAP_NAMESPACE: &str = "wikiplugin_link_heatmap";
+
+/// Shows next to every heading of the current note what share of the links into the note point at that heading (or at a block under
+/// it), as virtual text. Showing it again refreshes it.
+pub fn show_link_heatmap(config: &Config) -> Result<(), LinkHeatmapError> {
     let current_note = Note::get_current_note(config)?;
-    let mut current_buf = api::get_current_buf();
+    let current_path = current_note.path(config).ok_or(NotAPhysicalNote)?;
+
+    let mut anchors = Vec::new();
+    for other_note in list_all_physical_notes(config)? {
+        if other_note.path(config) == current_path {
+            continue;
+        }
+        let Some(md) = other_note.read_contents(config).ok().and_then(|contents| markdown::parse_markdown(&contents).ok()) else {
+            continue;
+        };
+        let other_note = Note::Physical(other_note);
+        for link in markdown::get_all_link_targets(&md) {
+            let Some(anchor) = links::split_anchor(&link).1.map(ToString::to_string) else {
+                continue;
+            };
+            if !links::is_external_link(config, &link)
+                && links::resolve_link_path(config, &other_note, &link).is_ok_and(|target| target == current_path)
+            {
+                anchors.push(anchor);
+            }
+        }
+    }
 
-    let autogen_start_marker_regex = r#"\<wikiplugin_autogenerate\>\s*\(\w\+\)\(.*\)"#;
-    let autogen_end_marker_regex = r#"\<wikiplugin_autogenerate_end\>"#;
+    let lines = get_buffer_lines(&api::get_current_buf())?;
+    let counts = heatmap::section_link_counts(&lines, &anchors.iter().map(String::as_str).collect::<Vec<_>>());
 
-    let mut match_index = 1;
+    clear_link_heatmap()?;
+    let namespace = i64::from(api::create_namespace(HEATMAP_NAMESPACE));
+    for (line, count) in counts {
+        let virt_text = Array::from_iter([Object::from(Array::from_iter([
+            Object::from(heatmap::heatmap_label(count, anchors.len())),
+            Object::from(if count == 0 { "Comment" } else { "DiagnosticHint" }),
+        ]))]);
+        let opts = Dictionary::from_iter([("virt_text", Object::from(virt_text)), ("virt_text_pos", Object::from("eol"))]);
+        api::call_function::<_, i64>(
+            "nvim_buf_set_extmark",
+            Array::from_iter([Object::from(0i64), Object::from(namespace), Object::from(line as i64), Object::from(0i64), Object::from(opts)]),
+        )?;
+    }
 
-    let negative_one_to_option = |x: isize| -> Option<usize> {
-        if x == -1 {
-            None
-        } else {
-            Some(x as usize)
+    Ok(())
+}
+
+pub fn clear_link_heatmap() -> Result<(), api::Error> {
+    api::get_current_buf().clear_namespace(api::create_namespace(HEATMAP_NAMESPACE), ..)?;
+    Ok(())
+}
+
+/// Opens a vertical split listing the notes that link to the current note, with the lines that the links are on. Calling this from inside
+/// the panel refreshes it.
+pub fn open_backlinks_panel(config: &Config) -> Result<(), BacklinksPanelError> {
+    let current_buf = api::get_current_buf();
+    let (mut panel, target) = match current_buf.get_var::<String>(BACKLINKS_PANEL_VAR) {
+        Ok(target) => (current_buf, PathBuf::from(target)),
+        Err(_) => {
+            let target = Note::get_current_note(config)?.path(config).ok_or(NotAPhysicalNote)?;
+            api::command("vertical botright split")?;
+            (open_scratch_buffer(config, Vec::new())?, target)
         }
     };
+    panel.set_var(BACKLINKS_PANEL_VAR, target.to_str().ok_or(NonUtf8Path)?)?;
 
-    while let Some(start_line_index) =
-        negative_one_to_option(api::eval(&format!("match(getline(0, '$'), '{autogen_start_marker_regex}', 0, {match_index})"))?)
-    {
-        let start_matches: Vec<String> = api::eval(&format!("matchlist(getline(0, '$'), '{autogen_start_marker_regex}', 0, {match_index})"))?;
+    let target_title = read_wiki_file(config, &target)
+        .ok()
+        .and_then(|contents| markdown::parse_markdown(&contents).ok())
+        .and_then(|md| markdown::parse_frontmatter(&md).ok())
+        .and_then(|frontmatter| frontmatter.title)
+        .unwrap_or_else(|| target.display().to_string());
 
-        let end_line_index = {
-            let end_marker_line_index =
-                negative_one_to_option(api::eval(&format!("match(getline(0, '$'), '{}', {})", autogen_end_marker_regex, start_line_index + 1))?);
+    // links in the panel are formatted relative to the panel itself so that follow_link works in it
+    let panel_note = Note::Scratch(ScratchNote { buffer: panel.clone() });
+    let backlinks = find_backlinks(config, &target)?;
+    let mut lines = vec![format!("# backlinks to {target_title} ({})", backlinks.len()), "".to_string()];
+    for backlink in backlinks {
+        let link = links::format_link(config, &panel_note, &backlink.path, &backlink.title.unwrap_or_default(), None)?;
+        lines.push(format!("- {}{link}", note_icon_prefix(config, &backlink.note)));
+        // the lines that link here are the most useful preview of a backlink, so they go in the fold instead of the start of the note
+        lines.extend(folded_entry_body(backlink.context.into_iter().map(|(headings, line)| {
+            if headings.is_empty() {
+                format!("> {line}")
+            } else {
+                format!("> {}: {line}", headings.join(" / "))
+            }
+        })));
+    }
+    panel.set_lines(.., false, lines)?;
+    fold_entries_in_current_window()?;
 
-            let next_start_line_index =
-                negative_one_to_option(api::eval(&format!("match(getline(0, '$'), '{}', {})", autogen_start_marker_regex, start_line_index + 1))?);
+    Ok(())
+}
 
-            let mut insert_end_line = || {
-                current_buf.set_lines(start_line_index + 1..start_line_index + 1, false, vec!["wikiplugin_autogenerate_end".to_string()])?;
-                Ok::<_, AutogenerateError>(start_line_index + 1)
-            };
+/// Opens a buffer listing every note by when it was created (from its frontmatter date, or its id if that is a timestamp), grouped by
+/// month or week as `timeline_grouping` says. `range` limits it to notes created between two dates, written as `from..until`.
+pub fn open_timeline(config: &Config, range: Option<String>) -> Result<(), TimelineError> {
+    let range = match range.as_deref().filter(|range| !range.trim().is_empty()) {
+        Some(range) => timeline::TimelineRange::parse_from_str(range, &config.date_format).ok_or_else(|| InvalidTimelineRange(range.to_string()))?,
+        None => timeline::TimelineRange::default(),
+    };
 
-            match (end_marker_line_index, next_start_line_index) {
-                (None, _) => {
-                    // if there is no end marker line, we insert an end marker line immediately after
-                    insert_end_line()?
-                }
-                (Some(end_marker_line), None) => {
-                    // if there is an end marker line and no later start marker line, we replace until the end marker line
-                    end_marker_line
-                }
-                (Some(end_marker_line), Some(next_start_line)) => {
-                    // if there is both, it depends on which line comes first
-                    if end_marker_line < next_start_line {
-                        end_marker_line
-                    } else {
-                        // if the next start line comes first, then the end marker line actually applies to that next autogenerated section,
-                        // so we have to insert an end marker line
-                        insert_end_line()?
-                    }
-                }
-            }
+    let mut buffer = open_scratch_buffer(config, Vec::new())?;
+    // links are formatted relative to the timeline buffer so that follow_link works in it
+    let timeline_note = Note::Scratch(ScratchNote { buffer: buffer.clone() });
+    let mut entries = Vec::new();
+    let mut undated_count = 0;
+    for note in list_all_physical_notes(config)? {
+        let frontmatter = note
+            .read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+            .unwrap_or_default();
+        let Some(created) =
+            frontmatter.timestamp(config).ok().or_else(|| chrono::NaiveDateTime::parse_from_str(&note.id, &config.note_id_timestamp_format).ok())
+        else {
+            undated_count += 1;
+            continue;
         };
+        if !range.contains(created.date()) {
+            continue;
+        }
 
-        let autogenerate_command = start_matches
-            .get(1)
-            .expect("autogeneration is missing command name (this should never happen because the regex always contains this capturing group)")
-            .as_str();
-        let autogenerate_arguments = start_matches
-            .get(2)
-            .expect("autogeneration start marker should have second capturing group")
-            .as_str()
-            .split(";")
-            .map(str::trim)
-            .collect::<Vec<_>>();
-
-        // TODO: full blown dsl with filters and pipes and things here?
-        let replacement = match autogenerate_command {
-            "index" => {
-                let directory: Vec<_> = autogenerate_arguments.first().copied().unwrap_or("").split("/").collect();
-                let sort_by = autogenerate_arguments.get(1).copied().unwrap_or("title");
-
-                let mut files = Vec::new();
-                for file in list_all_physical_notes(config)? {
-                    if file.directories == directory {
-                        let md = markdown::parse_markdown(&file.read_contents(config)?)?; // TODO: don't error on this?
-                        let frontmatter = markdown::parse_frontmatter(&md).ok();
-                        let title = frontmatter.as_ref().and_then(|f| markdown::get_title(f).ok());
-                        files.push((file, md, frontmatter, title))
-                    }
-                }
+        let link = links::format_link(config, &timeline_note, &note.path(config), frontmatter.title.as_deref().unwrap_or_default(), None)?;
+        let tags: String = frontmatter.tags.iter().map(|tag| format!(" #{tag}")).collect();
+        entries.push((created, format!("- {} {}{link}{tags}", created.format(&config.date_format), note_icon_prefix(config, &note))));
+    }
 
-                type ComparatorTuple = (PhysicalNote, ::markdown::mdast::Node, Option<yaml_rust::Yaml>, Option<String>);
-                let comparator = match sort_by {
-                    "title" => {
-                        (&|(a, _, _, a_title): &ComparatorTuple, (b, _, _, b_title): &ComparatorTuple| {
-                            if a_title.is_none() || b_title.is_none() {
-                                a.id.cmp(&b.id)
-                            } else {
-                                a_title.cmp(b_title)
-                            }
-                        }) as &dyn Fn(&ComparatorTuple, &ComparatorTuple) -> _
-                    }
-                    "date" => &|(_, _, a_frontmatter, _): &ComparatorTuple, (_, _, b_frontmatter, _): &ComparatorTuple| {
-                        let a_timestamp = a_frontmatter.as_ref().and_then(|f| markdown::get_timestamp(f, config).ok());
-                        let b_timestamp = b_frontmatter.as_ref().and_then(|f| markdown::get_timestamp(f, config).ok());
-                        a_timestamp.cmp(&b_timestamp)
-                    },
-                    "id" => &|(a, _, _, _): &ComparatorTuple, (b, _, _, _): &ComparatorTuple| a.id.cmp(&b.id),
-                    _ => {
-                        api::err_writeln(&format!("error: invalid comparison '{sort_by}'"));
-                        &|(a, _, _, _): &ComparatorTuple, (b, _, _, _): &ComparatorTuple| a.id.cmp(&b.id)
-                    }
-                };
-                files.sort_by(comparator);
+    let mut lines = vec![format!("# timeline ({} notes)", entries.len()), String::new()];
+    lines.extend(timeline::timeline_lines(entries, config.timeline_grouping));
+    if undated_count > 0 {
+        lines.push(format!("{undated_count} note(s) without a date are not shown"));
+    }
+    buffer.set_lines(.., false, lines)?;
+    Ok(())
+}
 
-                let mut result = Vec::new();
-                for (file, _, _, title) in files {
-                    let link_path = links::format_link_path(config, &current_note, &file.path(config))?;
-                    result.push(format!("- [{}]({})", title.unwrap_or("".to_string()), link_path));
-                }
+/// Shows a report of the notes that were added, removed, retitled, or retagged since the given date (in the configured date format) or git
+/// ref. The wiki must be in a git repository.
+pub fn diff_since(config: &Config, since: String) -> Result<(), DiffSinceError> {
+    let old_ref = match chrono::NaiveDate::parse_from_str(&since, &config.date_format) {
+        Ok(date) => {
+            let commit = run_git(config, &["rev-list", "-1", &format!("--before={} 00:00:00", date.format("%Y-%m-%d")), "HEAD"])?;
+            // if there are no commits before the date, the wiki did not exist yet, so everything was added since then
+            Some(commit.trim().to_string()).filter(|commit| !commit.is_empty())
+        }
+        Err(_) => Some(run_git(config, &["rev-parse", "--verify", &format!("{since}^{{commit}}")])?.trim().to_string()),
+    };
 
-                Some(result)
+    let mut old = BTreeMap::new();
+    if let Some(old_ref) = &old_ref {
+        // TODO: this runs git once per note, which is slow on big wikis
+        for path in run_git(config, &["ls-tree", "-r", "--name-only", old_ref, "--", "."])?.lines() {
+            if !path.ends_with(".md") || path.starts_with(state::STATE_DIR_NAME) {
+                continue;
             }
+            let contents = run_git(config, &["show", &format!("{old_ref}:./{path}")])?;
+            old.insert(config.home_path.join(path), snapshot::NoteSnapshot::from_contents(&contents));
+        }
+    }
 
-            "backlinks" => {
-                // TODO: this is extremely slow
-                let current_note = Note::get_current_note(config)?;
-                let mut result = Vec::new();
+    let mut new = BTreeMap::new();
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        new.insert(note.path(config), snapshot::NoteSnapshot::from_contents(&contents));
+    }
 
-                for other_note in list_all_physical_notes(config)? {
-                    if current_note.as_physical() == Some(&other_note) {
-                        continue;
-                    }
+    let diff = snapshot::diff_snapshots(&old, &new);
 
-                    let other_note_contents = other_note.read_contents(config)?; // TODO: don't error out on this?
-                    let other_note_markdown = markdown::parse_markdown(&other_note_contents)?; // TODO: don't error out on this?
-                    let other_note_title = markdown::get_title(&markdown::parse_frontmatter(&other_note_markdown)?).unwrap_or_default(); // TODO: don't error out on this?
-                    let other_note_links = markdown::get_all_links(&other_note_markdown);
-
-                    for link in other_note_links {
-                        let link_to = links::resolve_link_path(config, &Note::Physical(other_note.clone()), &link.url)?; // TODO: do not clone
-                        if Some(&link_to) == current_note.path(config).as_ref() {
-                            result.push(format!(
-                                "- [{}]({})",
-                                other_note_title,
-                                links::format_link_path(config, &current_note, &other_note.path(config))?
-                            ));
-                            break;
-                        }
-                    }
-                }
+    let link = |title: &Option<String>, path: &Path| -> Result<String, NonUtf8Path> {
+        Ok(format!("[{}]({})", title.clone().unwrap_or_default(), path.to_str().ok_or(NonUtf8Path)?))
+    };
+    let format_tags = |tags: &[Tag]| tags.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
 
-                Some(result)
-            }
+    let mut lines = vec![format!("# changes since {since}"), "".to_string()];
+    lines.push(format!("## added ({})", diff.added.len()));
+    lines.push("".to_string());
+    for (path, title) in &diff.added {
+        lines.push(format!("- {}", link(title, path)?));
+    }
+    lines.push("".to_string());
+    lines.push(format!("## removed ({})", diff.removed.len()));
+    lines.push("".to_string());
+    for (path, title) in &diff.removed {
+        // the note does not exist anymore so there is nothing to link to
+        lines.push(format!("- {} ({})", title.clone().unwrap_or_default(), path.display()));
+    }
+    lines.push("".to_string());
+    lines.push(format!("## retitled ({})", diff.retitled.len()));
+    lines.push("".to_string());
+    for retitled in &diff.retitled {
+        lines.push(format!("- {} (was '{}')", link(&retitled.new_title, &retitled.path)?, retitled.old_title.clone().unwrap_or_default()));
+    }
+    lines.push("".to_string());
+    lines.push(format!("## retagged ({})", diff.retagged.len()));
+    lines.push("".to_string());
+    for retagged in &diff.retagged {
+        lines.push(format!(
+            "- {}: added '{}', removed '{}'",
+            link(&retagged.title, &retagged.path)?,
+            format_tags(&retagged.added_tags),
+            format_tags(&retagged.removed_tags)
+        ));
+    }
 
-            "explore" => {
-                let root = Note::get_current_note(config)?;
+    open_scratch_buffer(config, lines)?;
 
-                let mut explored = BTreeSet::new();
-                let mut frontier = vec![root.clone()];
-                while let Some(current) = frontier.pop() {
-                    let current_contents = current.read_contents(config)?; // TODO: don't error out on this?
-                    let current_markdown = markdown::parse_markdown(&current_contents)?; // TODO: don't error out on this?
-                    let current_links = markdown::get_all_links(&current_markdown);
+    Ok(())
+}
 
-                    for link in current_links {
-                        let linked = PhysicalNote::parse_from_filepath(config, &links::resolve_link_path(config, &current, &link.url)?)?; // TODO: don't error out on this
-                        let linked_as_note = Note::Physical(linked.clone()); // TODO: do not clone
-                        if linked_as_note != root && !explored.contains(&linked) {
-                            frontier.push(linked_as_note);
-                            explored.insert(linked);
-                        }
-                    }
-                }
+fn run_git(config: &Config, args: &[&str]) -> Result<String, GitError> {
+    let output = std::process::Command::new("git").arg("-C").arg(&config.home_path).args(args).output()?;
+    if !output.status.success() {
+        Err(GitCommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()))?;
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
 
-                let mut result = Vec::new();
+// the body of each entry in a generated view is indented under it, so with indent folding every entry starts out as a closed fold that can
+// be opened with zo to preview the note without leaving the view
+const FOLDED_ENTRY_INDENT: &str = "    ";
 
-                for note in explored {
-                    let title = note
-                        .read_contents(config)
-                        .ok()
-                        .and_then(|contents| markdown::parse_markdown(&contents).ok())
-                        .and_then(|markdown| markdown::parse_frontmatter(&markdown).ok())
-                        .and_then(|frontmatter| markdown::get_title(&frontmatter).ok())
-                        .unwrap_or_default();
+fn folded_entry_body(lines: impl IntoIterator<Item = String>) -> impl Iterator<Item = String> {
+    lines.into_iter().filter(|line| !line.trim().is_empty()).map(|line| format!("{FOLDED_ENTRY_INDENT}{line}"))
+}
 
-                    result.push(format!("- [{}]({})", title, links::format_link_path(config, &root, &note.path(config))?));
-                }
+fn fold_entries_in_current_window() -> Result<(), api::Error> {
+    api::command("setlocal foldmethod=indent foldlevel=0 foldenable")
+}
 
-                Some(result)
-            }
+/// Reads the start of a note to show under its entry in a generated view. Returns nothing if the note cannot be read.
+fn note_excerpt(config: &Config, note: &PhysicalNote) -> Vec<String> {
+    note.read_contents(config)
+        .ok()
+        .and_then(|contents| Some(markdown::excerpt(&contents, &markdown::parse_markdown(&contents).ok()?, None, config.preview_line_count)))
+        .unwrap_or_default()
+}
 
-            _ => {
-                api::err_writeln(&format!("error: invalid autogenerate function '{autogenerate_command}'"));
-                None
+fn find_named_buffer(name: &str) -> Result<Option<api::Buffer>, api::Error> {
+    for buffer in api::list_bufs() {
+        if buffer.get_name()?.to_str() == Some(name) {
+            return Ok(Some(buffer));
+        }
+    }
+    Ok(None)
+}
+
+/// The table that the lua formatting callbacks are given for a note.
+fn note_meta(config: &Config, note: &PhysicalNote, frontmatter: Option<&markdown::Frontmatter>) -> Dictionary {
+    let strings = |strings: Vec<String>| Object::from(Array::from_iter(strings.into_iter().map(Object::from)));
+    let mut meta = vec![
+        ("id", Object::from(note.id.clone())),
+        ("path", Object::from(note.path(config).to_string_lossy().into_owned())),
+        ("directories", strings(note.directories.clone())),
+    ];
+    if let Some(frontmatter) = frontmatter {
+        for (key, value) in [("title", &frontmatter.title), ("author", &frontmatter.author), ("date", &frontmatter.date)] {
+            if let Some(value) = value {
+                meta.push((key, Object::from(value.clone())));
             }
-        };
+        }
+        meta.push(("tags", strings(frontmatter.tags.iter().map(ToString::to_string).collect())));
+        meta.push(("aliases", strings(frontmatter.aliases.clone())));
+    }
+    Dictionary::from_iter(meta)
+}
 
-        if let Some(replacement) = replacement {
-            current_buf.set_lines((start_line_index + 1)..end_line_index, false, replacement)?;
+/// Calls one of the lua formatting callbacks from the config. Returns `None` if there is no callback or it returns nil, and reports the
+/// error and returns `None` if it errors, so that the default formatting is used instead.
+fn run_format_callback(callback: Option<&nvim_oxi::Function<Dictionary, Option<String>>>, meta: impl FnOnce() -> Dictionary) -> Option<String> {
+    match callback?.call(meta()) {
+        Ok(result) => result,
+        Err(e) => {
+            api::err_writeln(&format!("error in format callback: {e}"));
+            None
         }
+    }
+}
+
+/// The configured icon for a note followed by a space, to go in front of it in generated lists and pickers, or nothing if it has no icon.
+fn note_icon_prefix(config: &Config, note: &PhysicalNote) -> String {
+    // reading the note is only worth it if there are icons for kinds
+    let kind = if config.note_icons.uses_kinds() {
+        note.read_contents(config)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+            .and_then(|frontmatter| frontmatter.kind().map(ToString::to_string))
+    } else {
+        None
+    };
+    config.note_icons.icon_for(&note.directories, kind.as_deref()).map(|icon| format!("{icon} ")).unwrap_or_default()
+}
+
+/// Finds the scratch buffer with the given name, creating it if it does not exist, so that reopening a view does not pile up buffers.
+fn find_or_create_named_buffer(name: &str) -> Result<api::Buffer, api::Error> {
+    if let Some(buffer) = find_named_buffer(name)? {
+        return Ok(buffer);
+    }
+    let mut buffer = api::create_buf(true, true)?;
+    buffer.set_name(name)?;
+    api::set_option_value("filetype", "markdown", &OptionOpts::builder().scope(OptionScope::Local).buffer(buffer.clone()).build())?;
+    Ok(buffer)
+}
 
-        match_index += 1;
+/// Replaces the contents of a read only view buffer. If the buffer is the current buffer, the cursor stays where it was (as far as
+/// possible).
+fn set_view_lines(mut buffer: api::Buffer, lines: Vec<String>) -> Result<(), api::Error> {
+    let line_count = lines.len().max(1);
+    let cursor = if api::get_current_buf() == buffer { Some(api::get_current_win().get_cursor()?) } else { None };
+
+    api::set_option_value("modifiable", true, &OptionOpts::builder().buffer(buffer.clone()).build())?;
+    buffer.set_lines(.., false, lines)?;
+    api::set_option_value("modifiable", false, &OptionOpts::builder().buffer(buffer.clone()).build())?;
+
+    if let Some((line, column)) = cursor {
+        api::get_current_win().set_cursor(line.min(line_count), column)?;
     }
+    Ok(())
+}
 
+fn open_scratch_buffer(config: &Config, lines: Vec<String>) -> Result<api::Buffer, api::Error> {
+    let mut buffer = api::create_buf(true, true)?;
+    api::set_option_value("filetype", "markdown", &OptionOpts::builder().scope(OptionScope::Local).buffer(buffer.clone()).build())?;
+    buffer.set_lines(0..0, false, lines)?;
+    api::set_current_buf(&buffer)?;
+    apply_buffer_options(config)?;
+    Ok(buffer)
+}
+
+/// Sets the configured `buffer_options` on the current buffer and window, the way `:setlocal` would. This is called on notes as they are
+/// opened and on the views that the plugin opens.
+pub fn apply_buffer_options(config: &Config) -> Result<(), api::Error> {
+    for (option, value) in &config.buffer_options {
+        api::set_option_value(option, value.clone(), &OptionOpts::builder().scope(OptionScope::Local).build())?;
+    }
     Ok(())
 }
 
+fn get_buffer_lines(buffer: &api::Buffer) -> Result<Vec<String>, api::Error> {
+    Ok(buffer.get_lines(.., false)?.map(|s| s.to_string_lossy().to_string()).collect()) // TODO: find a better solution than to_string_lossy
+}
+
 fn list_all_physical_notes(config: &Config) -> Result<Vec<PhysicalNote>, ListAllPhysicalNotesError> {
-    glob::glob(&format!("{}/**/*.md", config.home_path.to_str().ok_or(NonUtf8Path)?))?
-        .map(|path| {
-            path.map_err(ListAllPhysicalNotesError::from)
-                .and_then(|path| PhysicalNote::parse_from_filepath(config, &path).map_err(ListAllPhysicalNotesError::from))
-        })
-        .collect::<Result<Vec<_>, _>>()
+    let mut ignore = config.ignore.clone();
+    if config.respect_gitignore {
+        if let Ok(gitignore) = std::fs::read_to_string(config.home_path.join(".gitignore")) {
+            ignore.extend(ignore::IgnorePatterns::parse_gitignore(&gitignore));
+        }
+    }
+    let mut notes = config.store.list()?;
+    notes.retain(|note| {
+        let relative_path = note.directories.iter().map(String::as_str).chain(std::iter::once(note.id.as_str())).collect::<Vec<_>>().join("/");
+        !ignore.is_ignored(&format!("{relative_path}.md"))
+    });
+    // sort so that everything generated from this list does not depend on the order that the filesystem returns files in
+    notes.sort();
+    Ok(notes)
 }
 
 pub(crate) fn list_notes_and_titles_for_search(config: &Config) -> Result<Vec<[(&'static str, String); 4]>, ListAllPhysicalNotesError> {
@@ -577,7 +4668,7 @@ pub(crate) fn list_notes_and_titles_for_search(config: &Config) -> Result<Vec<[(
             // TODO: regularize all error handling
 
             let path = physical_note.path(config).to_str().unwrap_or_default().to_string(); // TODO: properly deal with this
-            let title = physical_note
+            let frontmatter = physical_note
                 .read_contents(config)
                 .inspect_err(|err| /* TODO: log that this failed */ {})
                 .ok()
@@ -585,19 +4676,63 @@ pub(crate) fn list_notes_and_titles_for_search(config: &Config) -> Result<Vec<[(
                 .and_then(|md| {
                     markdown::parse_frontmatter(&md).inspect_err(|err| /* TODO: log that this has failed */{}).ok()
                 })
-                .and_then(|frontmatter| {
-                    markdown::get_title(&frontmatter).inspect_err(|err| /* TODO: log that this has failed */{}).ok()
-                });
+                .unwrap_or_default();
+            let title = frontmatter.title;
+            let aliases = if frontmatter.aliases.is_empty() { String::new() } else { format!(" ({})", frontmatter.aliases.join(", ")) };
             [
                 ("value", physical_note.id.clone()),
-                ("display", title.clone().unwrap_or_default()),
-                ("ordinal", title.unwrap_or(physical_note.id.clone())),
+                ("display", format!("{}{}{aliases}", note_icon_prefix(config, &physical_note), title.clone().unwrap_or_default())),
+                // the aliases are part of what is searched so that a note can be found by any of its names
+                ("ordinal", format!("{}{aliases}", title.unwrap_or(physical_note.id.clone()))),
                 ("path", path.clone()),
             ]
         })
         .collect())
 }
 
+/// Every configured wiki as (name, config) pairs, starting with this one.
+fn all_wikis(config: &Config) -> Vec<(String, Config)> {
+    let current_name = config.wikis.iter().find(|(_, wiki)| wiki.home_path == config.home_path).map_or("main".to_string(), |(name, _)| name.clone());
+    let mut wikis = vec![(current_name, config.clone())];
+    for (name, wiki) in &config.wikis {
+        if wiki.home_path != config.home_path {
+            wikis.push((name.clone(), wiki.clone()));
+        }
+    }
+    wikis
+}
+
+/// Searches the titles, aliases, and tags of the notes in every configured wiki, in the same form as
+/// `list_notes_and_titles_for_search` but with every entry labelled with the name of its wiki. An empty query matches every note.
+pub(crate) fn search_all_wikis(config: &Config, query: String) -> Result<Vec<[(&'static str, String); 4]>, ListAllPhysicalNotesError> {
+    let query = query.to_lowercase();
+    let mut results = Vec::new();
+    for (name, wiki_config) in all_wikis(config) {
+        for note in list_all_physical_notes(&wiki_config)? {
+            let frontmatter = note
+                .read_contents(&wiki_config)
+                .ok()
+                .and_then(|contents| markdown::parse_markdown(&contents).ok())
+                .and_then(|md| markdown::parse_frontmatter(&md).ok())
+                .unwrap_or_default();
+            let title = frontmatter.title.unwrap_or_else(|| note.id.clone());
+            let tags = frontmatter.tags.iter().map(|tag| format!(" #{tag}")).collect::<String>();
+            let searched = format!("{title} {}{tags}", frontmatter.aliases.join(" "));
+            if !searched.to_lowercase().contains(&query) {
+                continue;
+            }
+
+            results.push([
+                ("value", note.id.clone()),
+                ("display", format!("[{name}] {}{title}{tags}", note_icon_prefix(&wiki_config, &note))),
+                ("ordinal", format!("{name} {searched}")),
+                ("path", note.path(&wiki_config).to_string_lossy().into_owned()),
+            ]);
+        }
+    }
+    Ok(results)
+}
+
 pub(crate) fn list_notes_lines_for_search(config: &Config) -> Result<Vec<Dictionary>, ListAllPhysicalNotesError> {
     Ok(list_all_physical_notes(config)?
         .into_iter()
@@ -0,0 +1,92 @@
+use crate::plugin::{markdown, splits};
+
+/// The anchors that links into a note can point at: the slug of each heading, and each block id (`^id` at the end of a line) with its `^`.
+/// Headings and block ids inside code blocks do not count.
+pub fn note_anchors(lines: &[String]) -> Vec<String> {
+    let mut anchors: Vec<String> = splits::headings(lines).into_iter().map(|(_, _, text)| markdown::slugify(text)).collect();
+    let mut in_code_block = false;
+    for line in lines {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if let Some(block_id) = line.trim_end().rsplit(' ').next().filter(|last| last.len() > 1 && last.starts_with('^')) {
+            if !in_code_block {
+                anchors.push(block_id.to_string());
+            }
+        }
+    }
+    anchors
+}
+
+/// Whether `anchor` (without the `#`) points at something in a note with these anchors. Links to headings can use the heading's text
+/// instead of its slug.
+pub fn has_anchor(anchors: &[String], anchor: &str) -> bool {
+    if anchor.starts_with('^') {
+        anchors.iter().any(|other| other == anchor)
+    } else {
+        let slug = markdown::slugify(anchor);
+        anchors.iter().any(|other| *other == slug)
+    }
+}
+
+/// The anchor that a dead anchor most likely meant, which is the one that takes the fewest edits to get to. A block id is only matched to
+/// other block ids and a heading only to other headings.
+pub fn closest_anchor<'anchors>(anchors: &'anchors [String], anchor: &str) -> Option<&'anchors str> {
+    let is_block = anchor.starts_with('^');
+    let wanted = if is_block { anchor.to_string() } else { markdown::slugify(anchor) };
+    anchors.iter().filter(|other| other.starts_with('^') == is_block).min_by_key(|other| edit_distance(other, &wanted)).map(String::as_str)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn note_anchors_test() {
+        let lines = to_lines("# Some Heading\na paragraph ^para\n```\n# not a heading ^code\n```\n## Other");
+        assert_eq!(note_anchors(&lines), ["some-heading", "other", "^para"]);
+    }
+
+    #[test]
+    fn has_anchor_test() {
+        let anchors = vec!["some-heading".to_string(), "^para".to_string()];
+        assert!(has_anchor(&anchors, "some-heading"));
+        assert!(has_anchor(&anchors, "Some Heading"));
+        assert!(has_anchor(&anchors, "^para"));
+        assert!(!has_anchor(&anchors, "^other"));
+        assert!(!has_anchor(&anchors, "missing"));
+    }
+
+    #[test]
+    fn closest_anchor_test() {
+        let anchors = vec!["installation".to_string(), "usage".to_string(), "^para".to_string()];
+        assert_eq!(closest_anchor(&anchors, "instalation"), Some("installation"));
+        assert_eq!(closest_anchor(&anchors, "Usage Notes"), Some("usage"));
+        assert_eq!(closest_anchor(&anchors, "^par"), Some("^para"));
+        assert_eq!(closest_anchor(&[], "usage"), None);
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,698 @@
+use std::collections::BTreeMap;
+
+use crate::plugin::{
+    markdown,
+    note::{PhysicalNote, Tag},
+};
+
+/// What `open_index` fills a new index note with if there is no `index_template` configured.
+pub const DEFAULT_INDEX_TEMPLATE: &str = "\
+---
+title: index
+---
+
+# index
+
+## recent notes
+
+wikiplugin_autogenerate recent 10
+wikiplugin_autogenerate_end
+
+## directories
+
+wikiplugin_autogenerate directories
+wikiplugin_autogenerate_end
+
+## tags
+
+wikiplugin_autogenerate tags
+wikiplugin_autogenerate_end
+";
+
+/// What `open_directory_index` fills a new index note for a subdirectory with if there is no `directory_index_template` configured.
+pub fn default_directory_index_template(directories: &[String]) -> String {
+    let path = directories.join("/");
+    format!("---\ntitle: {path}\n---\n\n# {path}\n\nwikiplugin_autogenerate index {path}\nwikiplugin_autogenerate_end\n")
+}
+
+/// How the plugin writes the autogenerate markers that it adds to notes. Markers in either style are recognized no matter which one is
+/// configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerStyle {
+    /// `wikiplugin_autogenerate index`
+    Plain,
+    /// `<!-- wikiplugin_autogenerate index -->`, which does not show up when the note is rendered
+    HtmlComment,
+}
+
+impl MarkerStyle {
+    pub fn parse_from_str(s: &str) -> Option<MarkerStyle> {
+        match s {
+            "plain" => Some(MarkerStyle::Plain),
+            "html_comment" => Some(MarkerStyle::HtmlComment),
+            _ => None,
+        }
+    }
+
+    pub fn end_marker(self) -> String {
+        self.marker("wikiplugin_autogenerate_end")
+    }
+
+    fn marker(self, text: &str) -> String {
+        match self {
+            MarkerStyle::Plain => text.to_string(),
+            MarkerStyle::HtmlComment => format!("<!-- {text} -->"),
+        }
+    }
+
+    /// Rewrites the markers in a note (like one of the built in templates, which are written with plain markers) in this style.
+    pub fn restyle(self, contents: &str) -> String {
+        contents
+            .split_inclusive('\n')
+            .map(|line| {
+                if is_marker(line) {
+                    let newline = if line.ends_with('\n') { "\n" } else { "" };
+                    format!("{}{newline}", self.marker(marker_text(line)))
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// An autogenerated section in a note, found by its markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkedSection {
+    /// the 0 indexed line of the start marker
+    pub start_line: usize,
+    pub command: String,
+    /// everything after the command, which each command splits up itself
+    pub arguments: String,
+    /// the line of the end marker, or `None` if the section is missing one
+    pub end_line: Option<usize>,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The byte ranges of every place that `word` is in `line` as a whole word.
+fn whole_word_matches<'a>(line: &'a str, word: &'a str) -> impl Iterator<Item = std::ops::Range<usize>> + 'a {
+    line.match_indices(word).map(|(start, _)| start..start + word.len()).filter(|range| {
+        !line[..range.start].chars().next_back().is_some_and(is_word_char) && !line[range.end..].chars().next().is_some_and(is_word_char)
+    })
+}
+
+/// Parses `wikiplugin_autogenerate command arguments` out of a line into the command and the arguments. The marker can be anywhere in
+/// the line, including inside an html comment, in which case the closing `-->` is not part of the arguments.
+pub fn parse_start_marker(line: &str) -> Option<(String, String)> {
+    whole_word_matches(line, "wikiplugin_autogenerate").find_map(|range| {
+        let rest = line[range.end..].trim_start();
+        let command_end = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+        if command_end == 0 {
+            return None;
+        }
+        let arguments = rest[command_end..].trim_end();
+        let arguments = arguments.strip_suffix("-->").unwrap_or(arguments).trim_end();
+        Some((rest[..command_end].to_string(), arguments.to_string()))
+    })
+}
+
+/// Finds every autogenerated section in a note. A section ends at the first end marker after its start marker, unless another section
+/// starts first, in which case the end marker belongs to that section and this one does not have one.
+pub fn find_marked_sections(lines: &[String]) -> Vec<MarkedSection> {
+    let starts: Vec<(usize, (String, String))> =
+        lines.iter().enumerate().filter_map(|(i, line)| Some((i, parse_start_marker(line)?))).collect();
+    let ends: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| whole_word_matches(line, "wikiplugin_autogenerate_end").next().is_some())
+        .map(|(i, _)| i)
+        .collect();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, (start_line, (command, arguments)))| {
+            let next_start_line = starts.get(n + 1).map(|(line, _)| *line);
+            let end_line = ends.iter().copied().find(|end| end > start_line).filter(|end| next_start_line.is_none_or(|next| *end < next));
+            MarkedSection { start_line: *start_line, command: command.clone(), arguments: arguments.clone(), end_line }
+        })
+        .collect()
+}
+
+/// The text of a line with the html comment that it is in taken off, if it is in one.
+fn marker_text(line: &str) -> &str {
+    let trimmed = line.trim();
+    trimmed.strip_prefix("<!--").and_then(|inner| inner.strip_suffix("-->")).map_or(trimmed, str::trim)
+}
+
+/// Whether the line is an autogenerate start or end marker, in either style.
+pub fn is_marker(line: &str) -> bool {
+    marker_text(line).starts_with("wikiplugin_autogenerate")
+}
+
+pub fn is_end_marker(line: &str) -> bool {
+    marker_text(line).starts_with("wikiplugin_autogenerate_end")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexSortKey {
+    Title,
+    Date,
+    Id,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub note: PhysicalNote,
+    pub title: Option<String>,
+    pub timestamp: Option<chrono::NaiveDateTime>,
+    pub author: Option<String>,
+}
+
+impl IndexSortKey {
+    pub fn parse_from_str(s: &str) -> Option<IndexSortKey> {
+        match s {
+            "title" => Some(IndexSortKey::Title),
+            "date" => Some(IndexSortKey::Date),
+            "id" => Some(IndexSortKey::Id),
+            _ => None,
+        }
+    }
+}
+
+pub fn sort_index_entries(entries: &mut [IndexEntry], sort_key: IndexSortKey) {
+    entries.sort_by(|a, b| {
+        let primary = match sort_key {
+            // notes without titles are sorted by their id among the titled notes
+            IndexSortKey::Title => a.title.as_ref().unwrap_or(&a.note.id).cmp(b.title.as_ref().unwrap_or(&b.note.id)),
+            IndexSortKey::Date => a.timestamp.cmp(&b.timestamp),
+            IndexSortKey::Id => a.note.id.cmp(&b.note.id),
+        };
+        // ties are broken by the note itself so that the output never depends on the order that the notes were listed in
+        primary.then_with(|| a.note.cmp(&b.note))
+    });
+}
+
+/// Which timestamp the `recent` command orders notes by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentBy {
+    Created,
+    Modified,
+}
+
+impl RecentBy {
+    pub fn parse_from_str(s: &str) -> Option<RecentBy> {
+        match s {
+            "created" => Some(RecentBy::Created),
+            "modified" => Some(RecentBy::Modified),
+            _ => None,
+        }
+    }
+}
+
+/// What goes after an index entry when the author column is turned on.
+pub fn author_column(author: Option<&str>) -> String {
+    format!(" | {}", author.unwrap_or("unknown author"))
+}
+
+/// Splits the entries of a recursive index of `directory` by which subdirectory of it they are in, keeping the order of the entries
+/// within each group. The notes directly inside `directory` come first, under an empty path.
+pub fn group_by_subdirectory<Entry>(
+    entries: Vec<Entry>,
+    directory: &[String],
+    note: impl Fn(&Entry) -> &PhysicalNote,
+) -> Vec<(Vec<String>, Vec<Entry>)> {
+    let mut groups: BTreeMap<Vec<String>, Vec<Entry>> = BTreeMap::new();
+    for entry in entries {
+        let subdirectory = note(&entry).directories.get(directory.len()..).unwrap_or_default().to_vec();
+        groups.entry(subdirectory).or_default().push(entry);
+    }
+    groups.into_iter().collect()
+}
+
+/// The lines of a section made of groups from [`group_by_subdirectory`], with a heading at `heading_level` over each subdirectory and the
+/// lines that `group_lines` makes for its entries under it.
+pub fn grouped_lines<Entry, E>(
+    groups: Vec<(Vec<String>, Vec<Entry>)>,
+    heading_level: usize,
+    mut group_lines: impl FnMut(Vec<Entry>) -> Result<Vec<String>, E>,
+) -> Result<Vec<String>, E> {
+    let mut result = Vec::new();
+    for (subdirectory, entries) in groups {
+        if !subdirectory.is_empty() {
+            if !result.is_empty() {
+                result.push(String::new());
+            }
+            result.push(format!("{} {}", "#".repeat(heading_level), subdirectory.join("/")));
+            result.push(String::new());
+        }
+        result.extend(group_lines(entries)?);
+    }
+    Ok(result)
+}
+
+/// Returns the `count` most recent entries, newest first. Entries without timestamps are never included.
+pub fn most_recent_entries(mut entries: Vec<IndexEntry>, count: usize) -> Vec<IndexEntry> {
+    entries.retain(|entry| entry.timestamp.is_some());
+    sort_index_entries(&mut entries, IndexSortKey::Date);
+    entries.into_iter().rev().take(count).collect()
+}
+
+/// Counts how many notes have each tag, sorted by most used first.
+pub fn tag_counts<'tags>(note_tags: impl IntoIterator<Item = &'tags [Tag]>) -> Vec<(Tag, usize)> {
+    let mut counts: BTreeMap<&Tag, usize> = BTreeMap::new();
+    for tags in note_tags {
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<_> = counts.into_iter().map(|(tag, count)| (tag.clone(), count)).collect();
+    counts.sort_by(|(a_tag, a_count), (b_tag, b_count)| b_count.cmp(a_count).then_with(|| a_tag.cmp(b_tag)));
+    counts
+}
+
+/// Counts how many notes are directly inside each directory, sorted by path. The top level directory is not included.
+pub fn directory_counts<'notes>(notes: impl IntoIterator<Item = &'notes PhysicalNote>) -> Vec<(Vec<String>, usize)> {
+    let mut counts: BTreeMap<&[String], usize> = BTreeMap::new();
+    for note in notes {
+        if !note.directories.is_empty() {
+            *counts.entry(note.directories.as_slice()).or_default() += 1;
+        }
+    }
+    counts.into_iter().map(|(directories, count)| (directories.to_vec(), count)).collect()
+}
+
+/// What the `embed` command puts into a note from the lines of another note (with its frontmatter already taken off): everything, or
+/// only what is under the heading whose text or slug is `heading`, up to the next heading at the same level or above. Autogenerate
+/// markers are taken out, so that embedded sections are not regenerated as if they belonged to the note they were embedded in. Returns
+/// `None` if the heading is not there.
+pub fn embedded_lines(lines: &[String], heading: Option<&str>) -> Option<Vec<String>> {
+    let section = match heading {
+        None => lines,
+        Some(heading) => {
+            let mut in_code_block = false;
+            let mut start = None;
+            let mut end = lines.len();
+            for (i, line) in lines.iter().enumerate() {
+                if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+                    in_code_block = !in_code_block;
+                    continue;
+                }
+                let Some((level, text)) = markdown::atx_heading(line).filter(|_| !in_code_block) else {
+                    continue;
+                };
+                match start {
+                    None => {
+                        if text.eq_ignore_ascii_case(heading) || markdown::slugify(text) == heading {
+                            start = Some((i + 1, level));
+                        }
+                    }
+                    Some((_, start_level)) if level <= start_level => {
+                        end = i;
+                        break;
+                    }
+                    Some(_) => {}
+                }
+            }
+            let (start, _) = start?;
+            &lines[start..end]
+        }
+    };
+
+    let embedded: Vec<String> = section.iter().filter(|line| !is_marker(line)).cloned().collect();
+    let first = embedded.iter().position(|line| !line.trim().is_empty()).unwrap_or(embedded.len());
+    let last = embedded.iter().rposition(|line| !line.trim().is_empty()).map_or(first, |last| last + 1);
+    Some(embedded[first..last].to_vec())
+}
+
+/// The unchecked checkbox items in a note, as (0 indexed line, text) pairs. Items inside code blocks and autogenerated sections are
+/// skipped, so that a generated list of tasks is never picked up as tasks itself.
+pub fn unchecked_tasks(contents: &str) -> Vec<(usize, String)> {
+    let mut tasks = Vec::new();
+    let mut in_code_block = false;
+    let mut in_autogenerated = false;
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if is_end_marker(line) {
+            in_autogenerated = false;
+        } else if is_marker(line) {
+            in_autogenerated = true;
+        } else if !in_code_block && !in_autogenerated {
+            let task = ["- [ ] ", "* [ ] ", "+ [ ] "].into_iter().find_map(|checkbox| trimmed.strip_prefix(checkbox));
+            if let Some(task) = task.map(str::trim).filter(|task| !task.is_empty()) {
+                tasks.push((i, task.to_string()));
+            }
+        }
+    }
+    tasks
+}
+
+/// A note that might be related to the current note, along with the reasons why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeeAlsoCandidate {
+    pub note: PhysicalNote,
+    pub similarity: f64,
+    pub shared_tags: Vec<Tag>,
+    /// number of notes that link to both this note and the current note
+    pub cocitations: usize,
+}
+
+// below this, content similarity is mostly noise from common words
+const SEE_ALSO_SIMILARITY_THRESHOLD: f64 = 0.1;
+
+impl SeeAlsoCandidate {
+    pub fn score(&self) -> f64 {
+        let similarity = if self.similarity >= SEE_ALSO_SIMILARITY_THRESHOLD { self.similarity } else { 0.0 };
+        similarity + 0.3 * self.shared_tags.len() as f64 + 0.3 * self.cocitations as f64
+    }
+
+    pub fn reasons(&self) -> String {
+        let mut reasons = Vec::new();
+        if self.similarity >= SEE_ALSO_SIMILARITY_THRESHOLD {
+            reasons.push("similar content".to_string());
+        }
+        if !self.shared_tags.is_empty() {
+            reasons.push(format!("shared tags: {}", self.shared_tags.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")));
+        }
+        if self.cocitations > 0 {
+            reasons.push(format!("linked together from {} note(s)", self.cocitations));
+        }
+        reasons.join("; ")
+    }
+}
+
+pub fn rank_see_also(mut candidates: Vec<SeeAlsoCandidate>, count: usize) -> Vec<SeeAlsoCandidate> {
+    candidates.retain(|candidate| candidate.score() > 0.0);
+    candidates.sort_by(|a, b| b.score().total_cmp(&a.score()).then_with(|| a.note.cmp(&b.note)));
+    candidates.truncate(count);
+    candidates
+}
+
+/// A nested list of links to the headings between `min_depth` and `max_depth` (inclusive), indented by how much deeper than `min_depth`
+/// they are. The links are anchor-only so that they work no matter what the note is called.
+pub fn table_of_contents(headings: &[(u8, String)], min_depth: u8, max_depth: u8) -> Vec<String> {
+    headings
+        .iter()
+        .filter(|(depth, _)| (min_depth..=max_depth).contains(depth))
+        .map(|(depth, text)| format!("{}- [{text}](#{})", "  ".repeat(usize::from(depth - min_depth)), markdown::slugify(text)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_directory_index_template_test() {
+        let template = default_directory_index_template(&["projects".to_string(), "old".to_string()]);
+        assert!(template.starts_with("---\ntitle: projects/old\n---\n"));
+        let sections = find_marked_sections(&template.lines().map(ToString::to_string).collect::<Vec<_>>());
+        assert_eq!(sections.len(), 1);
+        assert_eq!((sections[0].command.as_str(), sections[0].arguments.trim()), ("index", "projects/old"));
+    }
+
+    fn entry(directories: &[&str], id: &str, title: Option<&str>, timestamp: Option<&str>) -> IndexEntry {
+        IndexEntry {
+            note: PhysicalNote { directories: directories.iter().map(ToString::to_string).collect(), id: id.to_string() },
+            title: title.map(ToString::to_string),
+            timestamp: timestamp.map(|t| chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M").expect("test timestamp should parse")),
+            author: None,
+        }
+    }
+
+    fn all_orderings_sort_identically(entries: Vec<IndexEntry>, sort_key: IndexSortKey) -> Vec<IndexEntry> {
+        let mut expected = entries.clone();
+        sort_index_entries(&mut expected, sort_key);
+
+        for rotation in 0..entries.len() {
+            for reverse in [false, true] {
+                let mut shuffled = entries.clone();
+                shuffled.rotate_left(rotation);
+                if reverse {
+                    shuffled.reverse();
+                }
+                sort_index_entries(&mut shuffled, sort_key);
+                assert_eq!(shuffled, expected);
+            }
+        }
+
+        expected
+    }
+
+    #[test]
+    fn group_by_subdirectory_test() {
+        let entries = vec![
+            entry(&["projects", "beta"], "1", None, None),
+            entry(&["projects"], "2", None, None),
+            entry(&["projects", "alpha"], "3", None, None),
+            entry(&["projects", "beta"], "4", None, None),
+        ];
+        let groups = group_by_subdirectory(entries, &["projects".to_string()], |entry| &entry.note);
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|(subdirectory, entries)| (subdirectory.join("/"), entries.iter().map(|e| e.note.id.as_str()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(groups, [("".to_string(), vec!["2"]), ("alpha".to_string(), vec!["3"]), ("beta".to_string(), vec!["1", "4"])]);
+    }
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn embed_whole_note_test() {
+        let lines = to_lines("\n# title\n\ntext\nwikiplugin_autogenerate tasks\n- generated\nwikiplugin_autogenerate_end\n\n");
+        assert_eq!(embedded_lines(&lines, None), Some(to_lines("# title\n\ntext\n- generated")));
+    }
+
+    #[test]
+    fn embed_heading_section_test() {
+        let lines = to_lines("# title\n## Part One\none\n```\n# not a heading\n```\n### detail\nmore\n## part two\ntwo");
+        assert_eq!(embedded_lines(&lines, Some("part one")), Some(to_lines("one\n```\n# not a heading\n```\n### detail\nmore")));
+        assert_eq!(embedded_lines(&lines, Some("part-two")), Some(to_lines("two")));
+        assert_eq!(embedded_lines(&lines, Some("missing")), None);
+    }
+
+    #[test]
+    fn unchecked_tasks_test() {
+        let contents = "\
+- [ ] first
+  * [x] done
+  + [ ] nested
+```
+- [ ] in code
+```
+wikiplugin_autogenerate tasks
+- [ ] generated
+wikiplugin_autogenerate_end
+- [ ]
+- [ ] last";
+        assert_eq!(unchecked_tasks(contents), [(0, "first".to_string()), (2, "nested".to_string()), (10, "last".to_string())]);
+    }
+
+    #[test]
+    fn markers_test() {
+        assert!(is_marker("wikiplugin_autogenerate index projects"));
+        assert!(is_marker("  <!-- wikiplugin_autogenerate index projects -->"));
+        assert!(is_end_marker("<!--wikiplugin_autogenerate_end-->"));
+        assert!(!is_end_marker("<!-- wikiplugin_autogenerate index -->"));
+        assert!(!is_marker("<!-- a comment -->"));
+    }
+
+    #[test]
+    fn parse_start_marker_test() {
+        let parsed = |command: &str, arguments: &str| Some((command.to_string(), arguments.to_string()));
+        assert_eq!(parse_start_marker("wikiplugin_autogenerate index projects; title"), parsed("index", " projects; title"));
+        assert_eq!(parse_start_marker("<!-- wikiplugin_autogenerate index projects -->"), parsed("index", " projects"));
+        assert_eq!(parse_start_marker("<!--wikiplugin_autogenerate toc-->"), parsed("toc", ""));
+        assert_eq!(parse_start_marker("wikiplugin_autogenerate_end"), None);
+        assert_eq!(parse_start_marker("my_wikiplugin_autogenerate index"), None);
+        assert_eq!(parse_start_marker("wikiplugin_autogenerate"), None);
+    }
+
+    #[test]
+    fn find_marked_sections_test() {
+        let lines = to_lines(
+            "wikiplugin_autogenerate toc\n- a\nwikiplugin_autogenerate_end\nwikiplugin_autogenerate index\nwikiplugin_autogenerate tags\n- b\n\
+             <!-- wikiplugin_autogenerate_end -->\nwikiplugin_autogenerate backlinks",
+        );
+        let sections: Vec<_> =
+            find_marked_sections(&lines).into_iter().map(|section| (section.start_line, section.command, section.end_line)).collect();
+        assert_eq!(
+            sections,
+            [
+                (0, "toc".to_string(), Some(2)),
+                (3, "index".to_string(), None),
+                (4, "tags".to_string(), Some(6)),
+                (7, "backlinks".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn restyle_markers_test() {
+        let contents = "# index\n\nwikiplugin_autogenerate recent 10\nwikiplugin_autogenerate_end";
+        let restyled = MarkerStyle::HtmlComment.restyle(contents);
+        assert_eq!(restyled, "# index\n\n<!-- wikiplugin_autogenerate recent 10 -->\n<!-- wikiplugin_autogenerate_end -->");
+        assert_eq!(MarkerStyle::Plain.restyle(&restyled), contents);
+    }
+
+    #[test]
+    fn sort_by_title_equal_titles_test() {
+        let sorted = all_orderings_sort_identically(
+            vec![entry(&[], "3", Some("same"), None), entry(&[], "1", Some("same"), None), entry(&[], "2", Some("a"), None)],
+            IndexSortKey::Title,
+        );
+        assert_eq!(sorted.iter().map(|e| e.note.id.as_str()).collect::<Vec<_>>(), ["2", "1", "3"]);
+    }
+
+    #[test]
+    fn sort_by_title_missing_titles_test() {
+        let sorted = all_orderings_sort_identically(
+            vec![entry(&[], "b", None, None), entry(&[], "a", None, None), entry(&[], "x", Some("c"), None)],
+            IndexSortKey::Title,
+        );
+        assert_eq!(sorted.iter().map(|e| e.note.id.as_str()).collect::<Vec<_>>(), ["a", "b", "x"]);
+    }
+
+    #[test]
+    fn sort_by_date_equal_dates_test() {
+        let sorted = all_orderings_sort_identically(
+            vec![
+                entry(&[], "2", None, Some("2024-01-01 00:00")),
+                entry(&[], "1", None, Some("2024-01-01 00:00")),
+                entry(&[], "3", None, None),
+                entry(&[], "0", None, Some("2023-01-01 00:00")),
+            ],
+            IndexSortKey::Date,
+        );
+        assert_eq!(sorted.iter().map(|e| e.note.id.as_str()).collect::<Vec<_>>(), ["3", "0", "1", "2"]);
+    }
+
+    #[test]
+    fn sort_by_id_equal_ids_test() {
+        let sorted = all_orderings_sort_identically(vec![entry(&["b"], "1", None, None), entry(&["a"], "1", None, None)], IndexSortKey::Id);
+        assert_eq!(sorted.iter().map(|e| e.note.directories.clone()).collect::<Vec<_>>(), [vec!["a".to_string()], vec!["b".to_string()]]);
+    }
+
+    /// A grouped index and a recent list of `entries`, as the text that would go into a note.
+    fn regenerated_sections(entries: Vec<IndexEntry>) -> String {
+        let format_entry = |entry: &IndexEntry| {
+            format!("- [{}]({}/{}.md)", entry.title.as_deref().unwrap_or_default(), entry.note.directories.join("/"), entry.note.id)
+        };
+        let mut sorted = entries.clone();
+        sort_index_entries(&mut sorted, IndexSortKey::Title);
+        let groups = group_by_subdirectory(sorted, &["projects".to_string()], |entry| &entry.note);
+        let index = grouped_lines(groups, 2, |entries| Ok::<_, std::convert::Infallible>(entries.iter().map(format_entry).collect()))
+            .expect("formatting cannot fail");
+        let recent: Vec<String> = most_recent_entries(entries, 3).iter().map(format_entry).collect();
+        index.join("\n") + "\n\n" + &recent.join("\n") + "\n"
+    }
+
+    #[test]
+    fn regenerated_sections_are_stable_test() {
+        let entries = vec![
+            entry(&["projects", "beta"], "1", Some("same"), Some("2024-01-01 00:00")),
+            entry(&["projects"], "2", Some("same"), Some("2024-01-01 00:00")),
+            entry(&["projects", "alpha"], "3", None, Some("2023-01-01 00:00")),
+            entry(&["projects", "beta"], "4", Some("a"), None),
+            entry(&["projects", "alpha"], "5", Some("same"), Some("2024-01-01 00:00")),
+        ];
+        let expected = regenerated_sections(entries.clone());
+        for rotation in 0..entries.len() {
+            for reverse in [false, true] {
+                let mut shuffled = entries.clone();
+                shuffled.rotate_left(rotation);
+                if reverse {
+                    shuffled.reverse();
+                }
+                assert_eq!(regenerated_sections(shuffled), expected);
+            }
+        }
+        assert_eq!(
+            expected,
+            "- [same](projects/2.md)\n\n## alpha\n\n- [](projects/alpha/3.md)\n- [same](projects/alpha/5.md)\n\n## beta\n\n\
+             - [a](projects/beta/4.md)\n- [same](projects/beta/1.md)\n\n\
+             - [same](projects/beta/1.md)\n- [same](projects/alpha/5.md)\n- [same](projects/2.md)\n"
+        );
+    }
+
+    #[test]
+    fn most_recent_entries_test() {
+        let recent = most_recent_entries(
+            vec![
+                entry(&[], "old", None, Some("2023-01-01 00:00")),
+                entry(&[], "undated", None, None),
+                entry(&[], "new", None, Some("2024-01-01 00:00")),
+                entry(&[], "middle", None, Some("2023-06-01 00:00")),
+            ],
+            2,
+        );
+        assert_eq!(recent.iter().map(|e| e.note.id.as_str()).collect::<Vec<_>>(), ["new", "middle"]);
+    }
+
+    #[test]
+    fn tag_counts_test() {
+        let a = [Tag::parse_from_str("a")];
+        let ab = [Tag::parse_from_str("a"), Tag::parse_from_str("b")];
+        let c = [Tag::parse_from_str("c")];
+        assert_eq!(
+            tag_counts([&a[..], &ab[..], &c[..]]),
+            vec![(Tag::parse_from_str("a"), 2), (Tag::parse_from_str("b"), 1), (Tag::parse_from_str("c"), 1)]
+        );
+    }
+
+    #[test]
+    fn directory_counts_test() {
+        let notes = [
+            entry(&[], "1", None, None),
+            entry(&["b"], "2", None, None),
+            entry(&["a", "x"], "3", None, None),
+            entry(&["b"], "4", None, None),
+        ];
+        assert_eq!(
+            directory_counts(notes.iter().map(|entry| &entry.note)),
+            vec![(vec!["a".to_string(), "x".to_string()], 1), (vec!["b".to_string()], 2)]
+        );
+    }
+
+    fn see_also_candidate(id: &str, similarity: f64, shared_tags: &[&str], cocitations: usize) -> SeeAlsoCandidate {
+        SeeAlsoCandidate {
+            note: PhysicalNote { directories: vec![], id: id.to_string() },
+            similarity,
+            shared_tags: shared_tags.iter().map(|tag| Tag::parse_from_str(tag)).collect(),
+            cocitations,
+        }
+    }
+
+    #[test]
+    fn rank_see_also_test() {
+        let ranked = rank_see_also(
+            vec![
+                see_also_candidate("unrelated", 0.05, &[], 0),
+                see_also_candidate("similar", 0.5, &[], 0),
+                see_also_candidate("tagged", 0.0, &["a", "b"], 0),
+                see_also_candidate("cocited", 0.0, &[], 1),
+            ],
+            10,
+        );
+        assert_eq!(ranked.iter().map(|c| c.note.id.as_str()).collect::<Vec<_>>(), ["tagged", "similar", "cocited"]);
+    }
+
+    #[test]
+    fn see_also_reasons_test() {
+        assert_eq!(see_also_candidate("x", 0.5, &["a::b"], 2).reasons(), "similar content; shared tags: a::b; linked together from 2 note(s)");
+    }
+
+    #[test]
+    fn table_of_contents_test() {
+        let headings = [(1, "Title".to_string()), (2, "First Part".to_string()), (3, "Detail".to_string()), (2, "Second".to_string())];
+        assert_eq!(table_of_contents(&headings, 2, 6), ["- [First Part](#first-part)", "  - [Detail](#detail)", "- [Second](#second)"]);
+        assert_eq!(table_of_contents(&headings, 1, 2), ["- [Title](#title)", "  - [First Part](#first-part)", "  - [Second](#second)"]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use crate::plugin::autogenerate::{self, MarkedSection};
+
+/// A link in a note that is being compiled, and the footnote that it turns into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FootnotedLink {
+    /// the byte range of the whole link
+    pub span: Range<usize>,
+    pub text: String,
+    pub footnote: String,
+}
+
+/// Takes the autogenerate markers out of a note, keeping what is between them. `regenerate` is asked for the contents of each section first,
+/// so that sections like embeds can be filled in fresh; if it returns `None`, what is already in the section is kept.
+pub fn inline_autogenerated_sections(lines: &[String], mut regenerate: impl FnMut(&MarkedSection) -> Option<Vec<String>>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut next_line = 0;
+    for section in autogenerate::find_marked_sections(lines) {
+        if section.start_line < next_line {
+            continue;
+        }
+        result.extend_from_slice(&lines[next_line..section.start_line]);
+        let existing_end = section.end_line.unwrap_or(section.start_line + 1);
+        match regenerate(&section) {
+            Some(contents) => result.extend(contents),
+            None => result.extend_from_slice(&lines[section.start_line + 1..existing_end]),
+        }
+        next_line = section.end_line.map_or(section.start_line + 1, |end_line| end_line + 1);
+    }
+    result.extend_from_slice(&lines[next_line.min(lines.len())..]);
+    result
+}
+
+/// Replaces each link with its text and a footnote reference, and lists the footnotes at the end. Links with the same footnote share a
+/// number, which is given out in the order that the links appear in.
+pub fn footnote_links(contents: &str, mut links: Vec<FootnotedLink>) -> String {
+    links.sort_by_key(|link| link.span.start);
+    let mut numbers: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut footnotes = Vec::new();
+    let mut result = String::new();
+    let mut next_offset = 0;
+    for link in &links {
+        if link.span.start < next_offset {
+            // links cannot overlap, but skip one that does rather than panicking
+            continue;
+        }
+        let number = *numbers.entry(&link.footnote).or_insert_with(|| {
+            footnotes.push(&link.footnote);
+            footnotes.len()
+        });
+        result.push_str(&contents[next_offset..link.span.start]);
+        result.push_str(&format!("{}[^{number}]", link.text));
+        next_offset = link.span.end;
+    }
+    result.push_str(&contents[next_offset..]);
+
+    if !footnotes.is_empty() {
+        if !result.ends_with('\n') {
+            result.push('\n');
+        }
+        result.push('\n');
+        for (i, footnote) in footnotes.iter().enumerate() {
+            result.push_str(&format!("[^{}]: {footnote}\n", i + 1));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn inline_autogenerated_sections_test() {
+        let lines = to_lines(
+            "before\nwikiplugin_autogenerate embed other.md\nold embed\nwikiplugin_autogenerate_end\nmiddle\n\
+             <!-- wikiplugin_autogenerate tags -->\n- tag\n<!-- wikiplugin_autogenerate_end -->\nwikiplugin_autogenerate index\nafter",
+        );
+        let inlined = inline_autogenerated_sections(&lines, |section| (section.command == "embed").then(|| to_lines("new embed")));
+        assert_eq!(inlined, to_lines("before\nnew embed\nmiddle\n- tag\nafter"));
+    }
+
+    #[test]
+    fn footnote_links_test() {
+        let contents = "see [a](a.md) and [[b|bee]], then [a again](a.md#heading)\n";
+        let link = |span: Range<usize>, text: &str, footnote: &str| FootnotedLink { span, text: text.to_string(), footnote: footnote.to_string() };
+        let links = vec![link(18..27, "bee", "B (/wiki/b.md)"), link(4..13, "a", "A (/wiki/a.md)"), link(34..57, "a again", "A (/wiki/a.md)")];
+        assert_eq!(
+            footnote_links(contents, links),
+            "see a[^1] and bee[^2], then a again[^1]\n\n[^1]: A (/wiki/a.md)\n[^2]: B (/wiki/b.md)\n"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+/// What the text in front of the cursor is in the middle of writing. `start` is the byte column where the text being completed starts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CompletionContext {
+    /// the target of a markdown link, like `[text](dir/no`
+    Path { start: usize },
+    /// the inside of a wikilink, like `[[2024`
+    Id { start: usize },
+    /// a tag in the frontmatter tags field, like `tags: one tw`
+    Tag { start: usize },
+}
+
+impl CompletionContext {
+    pub fn start(&self) -> usize {
+        match self {
+            CompletionContext::Path { start } | CompletionContext::Id { start } | CompletionContext::Tag { start } => *start,
+        }
+    }
+}
+
+/// Works out what is being completed at the given (0 indexed) row and byte column, or `None` if it is not something that can be completed.
+pub fn completion_context(lines: &[String], row: usize, col: usize) -> Option<CompletionContext> {
+    let line = lines.get(row)?;
+    let before = line.get(..col.min(line.len()))?;
+
+    let in_frontmatter = lines.first().is_some_and(|first| first.trim_end() == "---")
+        && row > 0
+        && lines.iter().skip(1).position(|line| line.trim_end() == "---").is_some_and(|end| row <= end);
+    if in_frontmatter {
+        // block sequence items belong to the closest key above them
+        let key_line = lines[1..=row].iter().rev().find(|line| !line.trim_start().starts_with("- ") && !line.trim().is_empty())?;
+        let is_tags = key_line.starts_with("tags:") && (key_line == line || line.trim_start().starts_with("- "));
+        if !is_tags {
+            return None;
+        }
+        let start = before.rfind([' ', '[', ',']).map_or(0, |i| i + 1);
+        return Some(CompletionContext::Tag { start });
+    }
+
+    // whichever link was opened last and has not been closed yet is the one that the cursor is in
+    let wikilink = before.rfind("[[").filter(|&open| !before[open..].contains("]]"));
+    let link = before.rfind("](").filter(|&open| !before[open..].contains(')'));
+    match (wikilink, link) {
+        (Some(wikilink), link) if !link.is_some_and(|link| link > wikilink) => Some(CompletionContext::Id { start: wikilink + 2 }),
+        (_, Some(link)) => Some(CompletionContext::Path { start: link + 2 }),
+        _ => None,
+    }
+}
+
+/// A candidate matches if any of the ways it can be referred to contains what has been typed so far, ignoring case.
+pub fn matches_base(base: &str, names: &[&str]) -> bool {
+    let base = base.to_lowercase();
+    names.iter().any(|name| name.to_lowercase().contains(&base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn link_context_test() {
+        let lines = to_lines("see [[2024]] and [text](dir/no and [done](a.md) x");
+        assert_eq!(completion_context(&lines, 0, 10), Some(CompletionContext::Id { start: 6 }));
+        assert_eq!(completion_context(&lines, 0, 30), Some(CompletionContext::Path { start: 24 }));
+        assert_eq!(completion_context(&lines, 0, lines[0].len()), None);
+    }
+
+    #[test]
+    fn open_link_kinds_test() {
+        // only a wikilink is open
+        assert_eq!(completion_context(&to_lines("see [[20"), 0, 8), Some(CompletionContext::Id { start: 6 }));
+        // only a markdown link is open
+        assert_eq!(completion_context(&to_lines("see [a](di"), 0, 10), Some(CompletionContext::Path { start: 8 }));
+        // both are open, and the one opened last wins
+        assert_eq!(completion_context(&to_lines("[a](x [[20"), 0, 10), Some(CompletionContext::Id { start: 8 }));
+        assert_eq!(completion_context(&to_lines("[[20 [a](di"), 0, 11), Some(CompletionContext::Path { start: 9 }));
+        // neither is open
+        assert_eq!(completion_context(&to_lines("plain text"), 0, 10), None);
+    }
+
+    #[test]
+    fn tag_context_test() {
+        let lines = to_lines("---\ntitle: a\ntags: one tw\ntags:\n  - thr\n---\ntags: body");
+        assert_eq!(completion_context(&lines, 1, 8), None);
+        assert_eq!(completion_context(&lines, 2, 12), Some(CompletionContext::Tag { start: 10 }));
+        assert_eq!(completion_context(&lines, 4, 7), Some(CompletionContext::Tag { start: 4 }));
+        assert_eq!(completion_context(&lines, 6, 10), None);
+    }
+
+    #[test]
+    fn matches_base_test() {
+        assert!(matches_base("", &["anything"]));
+        assert!(matches_base("note", &["20240101", "My Note"]));
+        assert!(!matches_base("other", &["20240101", "My Note"]));
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use nvim_oxi::{api, Array, Object};
+
+/// Whether to ask before going ahead with an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmPolicy {
+    Always,
+    Never,
+}
+
+impl ConfirmPolicy {
+    pub fn parse_from_str(s: &str) -> Option<ConfirmPolicy> {
+        match s {
+            "always" => Some(ConfirmPolicy::Always),
+            "never" => Some(ConfirmPolicy::Never),
+            _ => None,
+        }
+    }
+}
+
+/// The operations that can ask for confirmation, named by the keys of the `confirm` config table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Delete,
+    BulkReplace,
+    LinkRewrite,
+}
+
+impl Operation {
+    pub fn parse_from_str(s: &str) -> Option<Operation> {
+        match s {
+            "delete" => Some(Operation::Delete),
+            "bulk_replace" => Some(Operation::BulkReplace),
+            "link_rewrite" => Some(Operation::LinkRewrite),
+            _ => None,
+        }
+    }
+
+    /// Deleting asks unless configured otherwise because it cannot be undone. The others rewrite notes in place, which has never asked.
+    fn default_policy(self) -> ConfirmPolicy {
+        match self {
+            Operation::Delete => ConfirmPolicy::Always,
+            Operation::BulkReplace | Operation::LinkRewrite => ConfirmPolicy::Never,
+        }
+    }
+}
+
+/// The `confirm` config, which is either one policy for every operation or a table of policies by operation.
+#[derive(Debug, Clone, Default)]
+pub struct ConfirmPolicies {
+    by_operation: BTreeMap<Operation, ConfirmPolicy>,
+    all: Option<ConfirmPolicy>,
+}
+
+impl ConfirmPolicies {
+    pub fn set_all(&mut self, policy: ConfirmPolicy) {
+        self.all = Some(policy);
+    }
+
+    pub fn set(&mut self, operation: Operation, policy: ConfirmPolicy) {
+        self.by_operation.insert(operation, policy);
+    }
+
+    pub fn policy(&self, operation: Operation) -> ConfirmPolicy {
+        self.by_operation.get(&operation).copied().or(self.all).unwrap_or(operation.default_policy())
+    }
+}
+
+/// Asks `question` with yes and no choices, unless the policy for the operation is to never ask. Returns whether to go ahead.
+pub fn confirm(policies: &ConfirmPolicies, operation: Operation, question: &str) -> Result<bool, api::Error> {
+    if policies.policy(operation) == ConfirmPolicy::Never {
+        return Ok(true);
+    }
+    // the default choice is no, so that pressing enter by accident does not do anything
+    let choice: i64 =
+        api::call_function("confirm", Array::from_iter([Object::from(question), Object::from("&yes\n&no"), Object::from(2i64)]))?;
+    Ok(choice == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policies_test() {
+        let policies = ConfirmPolicies::default();
+        assert_eq!(policies.policy(Operation::Delete), ConfirmPolicy::Always);
+        assert_eq!(policies.policy(Operation::LinkRewrite), ConfirmPolicy::Never);
+    }
+
+    #[test]
+    fn configured_policies_test() {
+        let mut policies = ConfirmPolicies::default();
+        policies.set_all(ConfirmPolicy::Always);
+        policies.set(Operation::Delete, ConfirmPolicy::Never);
+        assert_eq!(policies.policy(Operation::Delete), ConfirmPolicy::Never);
+        assert_eq!(policies.policy(Operation::BulkReplace), ConfirmPolicy::Always);
+    }
+}
@@ -0,0 +1,155 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// line index of the `<<<<<<<` marker
+    pub start_line: usize,
+    /// line index of the `>>>>>>>` marker
+    pub end_line: usize,
+    pub ours: Vec<String>,
+    /// only present if the conflict was written in diff3 style
+    pub base: Option<Vec<String>>,
+    pub theirs: Vec<String>,
+}
+
+impl Resolution {
+    pub fn parse_from_str(s: &str) -> Option<Resolution> {
+        match s {
+            "ours" => Some(Resolution::Ours),
+            "theirs" => Some(Resolution::Theirs),
+            "both" => Some(Resolution::Both),
+            _ => None,
+        }
+    }
+}
+
+impl ConflictHunk {
+    pub fn resolved(&self, resolution: Resolution) -> Vec<String> {
+        match resolution {
+            Resolution::Ours => self.ours.clone(),
+            Resolution::Theirs => self.theirs.clone(),
+            Resolution::Both => self.ours.iter().chain(&self.theirs).cloned().collect(),
+        }
+    }
+
+    pub fn contains_line(&self, line: usize) -> bool {
+        line >= self.start_line && line <= self.end_line
+    }
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker).is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+pub fn has_conflicts(contents: &str) -> bool {
+    contents.lines().any(|line| is_marker(line, "<<<<<<<"))
+}
+
+pub fn find_conflict_hunks(lines: &[String]) -> Vec<ConflictHunk> {
+    #[derive(Clone, Copy)]
+    enum State {
+        Outside,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut hunks = Vec::new();
+    let mut state = State::Outside;
+    let (mut start_line, mut ours, mut base, mut theirs) = (0, Vec::new(), None, Vec::new());
+
+    for (i, line) in lines.iter().enumerate() {
+        match state {
+            State::Outside => {
+                if is_marker(line, "<<<<<<<") {
+                    state = State::Ours;
+                    start_line = i;
+                }
+            }
+            State::Ours | State::Base if is_marker(line, "=======") => state = State::Theirs,
+            State::Ours if is_marker(line, "|||||||") => {
+                state = State::Base;
+                base = Some(Vec::new());
+            }
+            State::Ours => ours.push(line.clone()),
+            State::Base => base.get_or_insert_with(Vec::new).push(line.clone()),
+            State::Theirs => {
+                if is_marker(line, ">>>>>>>") {
+                    hunks.push(ConflictHunk {
+                        start_line,
+                        end_line: i,
+                        ours: std::mem::take(&mut ours),
+                        base: base.take(),
+                        theirs: std::mem::take(&mut theirs),
+                    });
+                    state = State::Outside;
+                } else {
+                    theirs.push(line.clone());
+                }
+            }
+        }
+    }
+
+    hunks
+}
+
+/// Resolves every conflict hunk in the same way, which is used to construct the "ours" and "theirs" sides of the diff view.
+pub fn resolve_all(lines: &[String], hunks: &[ConflictHunk], resolution: Resolution) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut last_end = 0;
+    for hunk in hunks {
+        result.extend_from_slice(&lines[last_end..hunk.start_line]);
+        result.extend(hunk.resolved(resolution));
+        last_end = hunk.end_line + 1;
+    }
+    result.extend_from_slice(&lines[last_end..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn find_conflict_hunks_test() {
+        let lines = to_lines("a\n<<<<<<< HEAD\nb\n=======\nc\nd\n>>>>>>> origin/main\ne");
+        assert_eq!(
+            find_conflict_hunks(&lines),
+            vec![ConflictHunk { start_line: 1, end_line: 6, ours: to_lines("b"), base: None, theirs: to_lines("c\nd") }]
+        );
+    }
+
+    #[test]
+    fn find_conflict_hunks_diff3_test() {
+        let lines = to_lines("<<<<<<< HEAD\nb\n||||||| base\nx\n=======\nc\n>>>>>>> other");
+        assert_eq!(
+            find_conflict_hunks(&lines),
+            vec![ConflictHunk { start_line: 0, end_line: 6, ours: to_lines("b"), base: Some(to_lines("x")), theirs: to_lines("c") }]
+        );
+    }
+
+    #[test]
+    fn resolve_all_test() {
+        let lines = to_lines("a\n<<<<<<< HEAD\nb\n=======\nc\n>>>>>>> other\nd\n<<<<<<< HEAD\ne\n=======\nf\n>>>>>>> other");
+        let hunks = find_conflict_hunks(&lines);
+
+        assert_eq!(resolve_all(&lines, &hunks, Resolution::Ours), to_lines("a\nb\nd\ne"));
+        assert_eq!(resolve_all(&lines, &hunks, Resolution::Theirs), to_lines("a\nc\nd\nf"));
+        assert_eq!(resolve_all(&lines, &hunks, Resolution::Both), to_lines("a\nb\nc\nd\ne\nf"));
+    }
+
+    #[test]
+    fn markers_need_exact_prefix_test() {
+        assert!(!has_conflicts("<<<<<<<<< not a marker"));
+        assert!(has_conflicts("text\n<<<<<<< HEAD\n"));
+    }
+}
@@ -0,0 +1,74 @@
+/// Removes the frontmatter and the contents of autogenerated sections (along with their markers) from a note, since neither of them is
+/// useful context: the frontmatter is metadata and the autogenerated sections only repeat other notes.
+pub fn strip_for_context(contents: &str) -> String {
+    let mut lines = contents.lines().peekable();
+
+    if lines.peek().is_some_and(|line| line.trim_end() == "---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if line.trim_end() == "---" {
+                break;
+            }
+        }
+    }
+
+    let mut kept = Vec::new();
+    let mut in_autogenerated = false;
+    for line in lines {
+        if line.contains("wikiplugin_autogenerate_end") {
+            in_autogenerated = false;
+        } else if line.contains("wikiplugin_autogenerate") {
+            in_autogenerated = true;
+        } else if !in_autogenerated {
+            kept.push(line);
+        }
+    }
+
+    kept.join("\n").trim().to_string()
+}
+
+/// A rough token count, using the common estimate of 4 characters per token for english text.
+pub fn approximate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Joins the notes together, each one under a separator line with its title and path.
+pub fn join_context(notes: &[(String, String, String)]) -> String {
+    notes
+        .iter()
+        .map(|(title, path, body)| format!("===== {title} ({path}) =====\n\n{body}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_for_context_test() {
+        let contents = "---\ntitle: a\n---\n\n# a\n\nbody\n\nwikiplugin_autogenerate backlinks\n- [b](b.md)\nwikiplugin_autogenerate_end\n\nmore\n";
+        assert_eq!(strip_for_context(contents), "# a\n\nbody\n\n\nmore");
+    }
+
+    #[test]
+    fn strip_for_context_no_frontmatter_test() {
+        assert_eq!(strip_for_context("body\n---\nafter rule\n"), "body\n---\nafter rule");
+    }
+
+    #[test]
+    fn approximate_tokens_test() {
+        assert_eq!(approximate_tokens(""), 0);
+        assert_eq!(approximate_tokens("abcd"), 1);
+        assert_eq!(approximate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn join_context_test() {
+        let notes = [
+            ("a".to_string(), "a.md".to_string(), "body a".to_string()),
+            ("b".to_string(), "b.md".to_string(), "body b".to_string()),
+        ];
+        assert_eq!(join_context(&notes), "===== a (a.md) =====\n\nbody a\n\n===== b (b.md) =====\n\nbody b\n");
+    }
+}
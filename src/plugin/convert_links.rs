@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use nvim_oxi::api;
+
+use crate::plugin::{
+    confirm,
+    links::{self, LinkStyle},
+    list_all_physical_notes, markdown,
+    note::{self, Note, PhysicalNote},
+    open_scratch_buffer, Config, ListAllPhysicalNotesError,
+};
+
+#[derive(Debug)]
+pub struct InvalidLinkStyle(String);
+impl std::error::Error for InvalidLinkStyle {}
+impl std::fmt::Display for InvalidLinkStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid link style '{}' (expected 'relative', 'absolute', 'home-relative', 'id-only', or 'wikilink')", self.0)
+    }
+}
+error_union! {
+    pub enum ConvertLinksError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+        FormatLinkPathError(links::FormatLinkPathError),
+        IoError(std::io::Error),
+        InvalidLinkStyle(InvalidLinkStyle),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LinkChange {
+    pub old: String,
+    pub new: String,
+}
+
+/// Rewrites every link between notes into the given style and shows a report of what changed. If `dry_run` is set, only the report is
+/// shown and no notes are modified.
+pub fn convert_links(config: &Config, style: String, dry_run: bool) -> Result<(), ConvertLinksError> {
+    let link_style = LinkStyle::parse_from_str(&style).ok_or(InvalidLinkStyle(style.clone()))?;
+
+    let mut report = Vec::new();
+    let mut changed_notes = Vec::new();
+    let mut changed_link_count = 0;
+    for note in list_all_physical_notes(config)? {
+        let path = note.path(config);
+        let contents = note.read_contents(config)?;
+        let (new_contents, changes) = match convert_links_in_note(config, link_style, &note, &contents) {
+            Ok(converted) => converted,
+            Err(e) => {
+                report.push(format!("- skipped {}: {e}", path.display()));
+                continue;
+            }
+        };
+        if changes.is_empty() {
+            continue;
+        }
+
+        report.push(format!("## {}", path.display()));
+        report.push("".to_string());
+        for change in &changes {
+            report.push(format!("- `{}` -> `{}`", change.old, change.new));
+        }
+        report.push("".to_string());
+
+        changed_link_count += changes.len();
+        changed_notes.push((path, new_contents));
+    }
+
+    let changed_note_count = changed_notes.len();
+    if !dry_run && changed_note_count > 0 {
+        let question = format!("rewrite {changed_link_count} link(s) in {changed_note_count} note(s) to {style}?");
+        if !confirm::confirm(&config.confirm, confirm::Operation::LinkRewrite, &question)? {
+            api::command(r#"echo "\nnot converting links""#)?;
+            return Ok(());
+        }
+        for (path, new_contents) in changed_notes {
+            std::fs::write(path, new_contents)?;
+        }
+    }
+
+    let mut lines = vec![
+        if dry_run { format!("# converting links to {style} (dry run, nothing was changed)") } else { format!("# converted links to {style}") },
+        "".to_string(),
+        format!("{changed_link_count} link(s) in {changed_note_count} note(s)"),
+        "".to_string(),
+    ];
+    lines.extend(report);
+    open_scratch_buffer(config, lines)?;
+
+    if !dry_run {
+        // reload any notes that are open in buffers
+        api::command("checktime")?;
+    }
+
+    Ok(())
+}
+
+/// Returns the contents of the note with every link to another note rewritten into the given style, along with a list of the links that
+/// changed. Links that cannot be resolved to an existing note are left alone.
+pub fn convert_links_in_note(
+    config: &Config,
+    link_style: LinkStyle,
+    note: &PhysicalNote,
+    contents: &str,
+) -> Result<(String, Vec<LinkChange>), ConvertLinksError> {
+    let md = markdown::parse_markdown(contents)?;
+    let current_note = Note::Physical(note.clone());
+
+    let mut target_config = config.clone();
+    target_config.link_style = link_style;
+    // wikilinks only contain ids, so they need to be looked up by id no matter what style the wiki is currently using
+    let mut wikilink_config = config.clone();
+    wikilink_config.resolve_links_by_id = true;
+
+    // (start offset, end offset, replacement)
+    let mut replacements = Vec::new();
+    for link in markdown::get_all_links(&md) {
+        let Some(position) = &link.position else {
+            continue;
+        };
+        let Some(target) = resolve_note_link(config, &current_note, &link.url) else {
+            continue;
+        };
+        let (_, anchor) = links::split_anchor(&link.url);
+
+        if link_style == LinkStyle::Wikilink {
+            let text = match (link.children.first().and_then(|child| child.position()), link.children.last().and_then(|child| child.position())) {
+                (Some(first), Some(last)) => &contents[first.start.offset..last.end.offset],
+                _ => "",
+            };
+            let new_link = links::format_link(&target_config, &current_note, &target, text, anchor)?;
+            replacements.push((position.start.offset, position.end.offset, new_link));
+        } else {
+            // only replace the url so that the link text and title are kept exactly as they were written
+            let span = &contents[position.start.offset..position.end.offset];
+            if let Some(url_offset) = span.rfind(&link.url) {
+                let url_start = position.start.offset + url_offset;
+                let new_url = links::format_link_path(&target_config, &current_note, &target, anchor)?;
+                replacements.push((url_start, url_start + link.url.len(), new_url));
+            }
+        }
+    }
+    for wikilink in markdown::get_all_wikilinks(&md) {
+        let Some(target) = resolve_note_link(&wikilink_config, &current_note, &wikilink.target) else {
+            continue;
+        };
+        let (_, anchor) = links::split_anchor(&wikilink.target);
+        let text = wikilink.text.as_deref().unwrap_or(&wikilink.target);
+        let new_link = links::format_link(&target_config, &current_note, &target, text, anchor)?;
+        replacements.push((wikilink.start_offset, wikilink.end_offset, new_link));
+    }
+
+    replacements.retain(|(start, end, replacement)| contents[*start..*end] != *replacement);
+    replacements.sort_by_key(|(start, _, _)| *start);
+
+    let changes =
+        replacements.iter().map(|(start, end, replacement)| LinkChange { old: contents[*start..*end].to_string(), new: replacement.clone() }).collect();
+
+    // replace back to front so that the offsets of the links that have not been replaced yet stay valid
+    let mut new_contents = contents.to_string();
+    for (start, end, replacement) in replacements.into_iter().rev() {
+        new_contents.replace_range(start..end, &replacement);
+    }
+
+    Ok((new_contents, changes))
+}
+
+/// Resolves a link to an existing note, ignoring external links, links to attachments, and links to headings in the same note.
+fn resolve_note_link(config: &Config, current_note: &Note, link: &str) -> Option<PathBuf> {
+    let (path, _) = links::split_anchor(link);
+    if path.is_empty() || links::is_external_url(link) {
+        return None;
+    }
+    let resolved = links::resolve_link_path(config, current_note, link).ok()?;
+    (resolved.is_file() && resolved.extension().is_some_and(|extension| extension == "md")).then_some(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_links_round_trip_test() {
+        let home_path = std::env::temp_dir().join("wikiplugin_convert_links_round_trip_test");
+        std::fs::create_dir_all(home_path.join("dir")).unwrap();
+        std::fs::write(home_path.join("start.md"), "").unwrap();
+        std::fs::write(home_path.join("dir/end.md"), "").unwrap();
+
+        let config = Config::new(home_path.clone(), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let note = PhysicalNote { directories: vec![], id: "start".to_string() };
+        let contents = "see [the end](dir/end.md#heading) and [elsewhere](https://example.com)\n";
+
+        let (wikilinked, changes) = convert_links_in_note(&config, LinkStyle::Wikilink, &note, contents).unwrap();
+        assert_eq!(wikilinked, "see [[end#heading|the end]] and [elsewhere](https://example.com)\n");
+        assert_eq!(changes, vec![LinkChange { old: "[the end](dir/end.md#heading)".to_string(), new: "[[end#heading|the end]]".to_string() }]);
+
+        let (relative, _) = convert_links_in_note(&config, LinkStyle::Relative, &note, &wikilinked).unwrap();
+        assert_eq!(relative, "see [the end](dir/end.md#heading) and [elsewhere](https://example.com)\n");
+
+        let (home_relative, _) = convert_links_in_note(&config, LinkStyle::HomeRelative, &note, contents).unwrap();
+        assert_eq!(home_relative, "see [the end](/dir/end.md#heading) and [elsewhere](https://example.com)\n");
+
+        std::fs::remove_dir_all(home_path).unwrap();
+    }
+}
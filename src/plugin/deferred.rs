@@ -0,0 +1,88 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    error::Error,
+};
+
+use nvim_oxi::{
+    api::{self, opts::CreateAutocmdOpts, types::AutocmdCallbackArgs},
+    Array,
+};
+
+type Mutation = Box<dyn Fn() -> Result<(), Box<dyn Error>>>;
+
+// how many times a put off edit is tried again before giving up on it and reporting the error
+const MAX_ATTEMPTS: usize = 20;
+
+thread_local! {
+    static QUEUE: RefCell<VecDeque<(usize, Mutation)>> = RefCell::new(VecDeque::new());
+    static DRAIN_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether Neovim refused an edit because of the state that it is in, like while text is locked during some autocommands or while the
+/// command line window is open, rather than because something is actually wrong.
+fn is_restricted_state_error(e: &dyn Error) -> bool {
+    // E565: Not allowed to change text or change window, E523: Not allowed here, E11: Invalid in command-line window
+    let message = e.to_string();
+    ["E565", "E523", "E11:"].iter().any(|code| message.contains(code))
+}
+
+/// Runs a buffer edit, or if Neovim does not allow edits right now, puts it off until it does. `mutation` can be run more than once, so it
+/// has to be safe to run again after it failed partway through.
+pub fn run_or_defer<E: Error + 'static>(mutation: impl Fn() -> Result<(), E> + 'static) -> Result<(), E> {
+    match mutation() {
+        Err(e) if is_restricted_state_error(&e) => {
+            QUEUE.with_borrow_mut(|queue| queue.push_back((1, Box::new(move || mutation().map_err(|e| Box::new(e) as Box<dyn Error>)))));
+            schedule_drain();
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn schedule_drain() {
+    if DRAIN_SCHEDULED.replace(true) {
+        return;
+    }
+    let in_cmdwin = api::call_function::<_, String>("getcmdwintype", Array::new()).is_ok_and(|cmdwin_type| !cmdwin_type.is_empty());
+    if in_cmdwin {
+        // nothing can be edited until the command line window is closed, so there is no point in trying before then
+        let registered = api::create_autocmd(
+            ["CmdwinLeave"],
+            &CreateAutocmdOpts::builder()
+                .once(true)
+                .callback(|_: AutocmdCallbackArgs| {
+                    // the window is still open while the autocommand runs
+                    nvim_oxi::schedule(|_| {
+                        drain();
+                        Ok::<_, nvim_oxi::Error>(())
+                    });
+                    Ok::<_, api::Error>(false)
+                })
+                .build(),
+        );
+        if let Err(e) = registered {
+            crate::error::print_error(&e);
+        }
+    } else {
+        nvim_oxi::schedule(|_| {
+            drain();
+            Ok::<_, nvim_oxi::Error>(())
+        });
+    }
+}
+
+fn drain() {
+    DRAIN_SCHEDULED.set(false);
+    let mutations: Vec<(usize, Mutation)> = QUEUE.with_borrow_mut(|queue| queue.drain(..).collect());
+    for (attempts, mutation) in mutations {
+        match mutation() {
+            Ok(()) => {}
+            Err(e) if is_restricted_state_error(&*e) && attempts < MAX_ATTEMPTS => {
+                QUEUE.with_borrow_mut(|queue| queue.push_back((attempts + 1, mutation)));
+                schedule_drain();
+            }
+            Err(e) => crate::error::print_error(&*e),
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use ::markdown::mdast;
+use nvim_oxi::api;
+
+use crate::plugin::{
+    compile, convert_links, embed_lines, filter, find_backlinks, frontmatter, get_buffer_lines, links, list_all_physical_notes, markdown, note,
+    note_index, open_externally, state, Config, FollowLinkError, ListAllPhysicalNotesError, NonUtf8Path, NotAPhysicalNote,
+};
+
+error_union! {
+    pub enum ExportError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+        IoError(std::io::Error),
+        UnknownExportProfile(UnknownExportProfile),
+        ConvertLinksError(convert_links::ConvertLinksError),
+    }
+}
+
+error_union! {
+    pub enum PreviewNoteError {
+        ExportError(ExportError),
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        NonUtf8Path(NonUtf8Path),
+        MdParseError(markdown::MdParseError),
+        IoError(std::io::Error),
+        FollowLinkError(FollowLinkError),
+    }
+}
+
+error_union! {
+    pub enum CompileNoteError {
+        ApiError(api::Error),
+        GetCurrentNoteError(note::GetCurrentNoteError),
+        NotAPhysicalNote(NotAPhysicalNote),
+        ParseFromFilepathError(note::ParseFromFilepathError),
+        ReadContentsError(note::ReadContentsError),
+        MdParseError(markdown::MdParseError),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        IoError(std::io::Error),
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownExportProfile(String);
+impl std::error::Error for UnknownExportProfile {}
+impl std::fmt::Display for UnknownExportProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no export profile named '{}' in the export_profiles config", self.0)
+    }
+}
+
+/// A named destination for exports, set up in the `export_profiles` config.
+#[derive(Debug, Clone)]
+pub struct ExportProfile {
+    pub output_dir: PathBuf,
+    /// If set, only notes whose `audience` frontmatter includes one of these are exported. Notes without an audience are left out, so
+    /// that nothing is shared with an audience by accident.
+    pub audiences: Option<Vec<String>>,
+}
+
+pub fn export_html(config: &Config, output_dir: String) -> Result<(), ExportError> {
+    export_notes(config, &PathBuf::from(output_dir), None)
+}
+
+/// Exports the notes that the profile's audiences are allowed to see to the profile's output directory.
+pub fn export_profile(config: &Config, profile_name: String) -> Result<(), ExportError> {
+    let profile = config.export_profiles.get(&profile_name).ok_or(UnknownExportProfile(profile_name))?;
+    export_notes(config, &profile.output_dir, profile.audiences.as_deref())
+}
+
+fn export_notes(config: &Config, output_dir: &Path, audiences: Option<&[String]>) -> Result<(), ExportError> {
+    let mut exported = Vec::new();
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        let md = markdown::parse_markdown(&contents)?;
+        if is_exported(&md, audiences) {
+            exported.push((note, contents, md));
+        }
+    }
+
+    // links to the notes that are left out are turned into plain text, so that the export does not give away that they exist
+    let exported_notes: BTreeSet<note::PhysicalNote> = exported.iter().map(|(note, _, _)| note.clone()).collect();
+    let exported_count = exported.len();
+    let mut attachments = BTreeSet::new();
+    for (note, contents, md) in exported {
+        attachments.extend(write_note_html(config, &note, &contents, &md, &exported_notes, output_dir, "")?);
+    }
+
+    copy_attachments(config, &attachments, output_dir)?;
+
+    api::command(&format!(
+        r#"echo "exported {} note(s) and {} attachment(s) to {}""#,
+        exported_count,
+        attachments.len(),
+        output_dir.to_string_lossy()
+    ))?;
+
+    Ok(())
+}
+
+/// Renders a note to html and writes it to the same place in the output directory as it is in the wiki. Links to notes that are not in
+/// `rendered` are replaced by their text. `head` is put into the `<head>` of the page. Returns the attachments that the note links to,
+/// which have to be copied separately.
+fn write_note_html(
+    config: &Config,
+    note: &note::PhysicalNote,
+    contents: &str,
+    md: &mdast::Node,
+    rendered: &BTreeSet<note::PhysicalNote>,
+    output_dir: &Path,
+    head: &str,
+) -> Result<BTreeSet<PathBuf>, ExportError> {
+    let mut attachments = BTreeSet::new();
+    let title = markdown::parse_frontmatter(md).ok().and_then(|f| f.title).unwrap_or_else(|| note.id.clone());
+
+    let note_path = note.path(config);
+    let note_dir = note_path.parent().expect("note path should always have a parent because it is inside the home directory");
+
+    let link_texts = link_text_ranges(md);
+    let from = note::Note::Physical(note.clone());
+    let is_rendered = |url: &str| {
+        links::resolve_link_path(config, &from, url)
+            .ok()
+            .and_then(|path| note::PhysicalNote::parse_from_filepath(config, &path).ok())
+            .is_some_and(|target| rendered.contains(&target))
+    };
+
+    // replace back to front so that the offsets of the links that have not been replaced yet stay valid
+    let mut urls = markdown::get_all_link_and_image_urls(md);
+    urls.sort_by_key(|(_, position)| std::cmp::Reverse(position.start.offset));
+    let mut rewritten = contents.to_string();
+    for (url, position) in urls {
+        if links::is_external_url(&url) {
+            continue;
+        }
+
+        match html_link_url(&url) {
+            Some(_) if !is_rendered(&url) => {
+                let text = link_texts.get(&position.start.offset).map_or("", |text| &contents[text.clone()]);
+                rewritten.replace_range(position.start.offset..position.end.offset, text);
+            }
+            Some(new_url) => {
+                let span = &rewritten[position.start.offset..position.end.offset];
+                if let Some(url_offset) = span.rfind(&url) {
+                    let url_start = position.start.offset + url_offset;
+                    rewritten.replace_range(url_start..url_start + url.len(), &new_url);
+                }
+            }
+            None => attachments.extend(attachment_path(config, note_dir, &url)),
+        }
+    }
+
+    let html = markdown::render_html(&rewritten)?;
+
+    let output_path = html_output_path(note, output_dir);
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output_path, html_page(&title, head, &html))?;
+
+    Ok(attachments)
+}
+
+/// The file that a link to something other than a note points to, if it exists and is inside the wiki.
+fn attachment_path(config: &Config, note_dir: &Path, url: &str) -> Option<PathBuf> {
+    let (attachment_path, _) = links::split_anchor(url);
+    let attachment_path = note_dir.join(attachment_path).canonicalize().ok()?;
+    (attachment_path.is_file() && attachment_path.starts_with(&config.home_path)).then_some(attachment_path)
+}
+
+fn html_output_path(note: &note::PhysicalNote, output_dir: &Path) -> PathBuf {
+    let mut output_path = output_dir.to_path_buf();
+    output_path.extend(&note.directories);
+    output_path.push(&note.id);
+    output_path.set_extension("html");
+    output_path
+}
+
+fn copy_attachments(config: &Config, attachments: &BTreeSet<PathBuf>, output_dir: &Path) -> Result<(), std::io::Error> {
+    for attachment in attachments {
+        let relative = attachment.strip_prefix(&config.home_path).expect("attachments are only collected if they are inside the home directory");
+        let output_path = output_dir.join(relative);
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(attachment, output_path)?;
+    }
+    Ok(())
+}
+
+/// Copies the wiki into an Obsidian vault at `output_dir`: links between notes become wikilinks, nested tags use `/` instead of `::`, and
+/// every directory gets a folder note (a note named after the directory inside it) listing its notes, unless it already has one.
+pub fn export_obsidian(config: &Config, output_dir: String) -> Result<(), ExportError> {
+    let output_dir = PathBuf::from(output_dir);
+    let mut attachments = BTreeSet::new();
+    let mut directories: BTreeMap<Vec<String>, Vec<(String, String)>> = BTreeMap::new();
+    let mut exported_count = 0;
+
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        let md = markdown::parse_markdown(&contents)?;
+        let frontmatter = markdown::parse_frontmatter(&md).unwrap_or_default();
+
+        let note_path = note.path(config);
+        let note_dir = note_path.parent().expect("note path should always have a parent because it is inside the home directory");
+        for (url, _) in markdown::get_all_link_and_image_urls(&md) {
+            if !links::is_external_url(&url) && !links::split_anchor(&url).0.ends_with(".md") {
+                attachments.extend(attachment_path(config, note_dir, &url));
+            }
+        }
+
+        let (converted, _) = convert_links::convert_links_in_note(config, links::LinkStyle::Wikilink, &note, &contents)?;
+        let lines: Vec<String> = converted.lines().map(ToString::to_string).collect();
+        let tags: Vec<String> = frontmatter.tags.iter().map(|tag| tag.components().join("/")).collect();
+        let lines = if frontmatter.tags.is_empty() { lines } else { frontmatter::replace_tags(&lines, &tags) };
+
+        let mut output_path = output_dir.clone();
+        output_path.extend(&note.directories);
+        std::fs::create_dir_all(&output_path)?;
+        output_path.push(&note.id);
+        output_path.set_extension("md");
+        std::fs::write(output_path, lines.join("\n") + "\n")?;
+
+        if !note.directories.is_empty() {
+            directories.entry(note.directories.clone()).or_default().push((note.id.clone(), frontmatter.title.unwrap_or_default()));
+        }
+        exported_count += 1;
+    }
+
+    for (directory, notes) in directories {
+        let name = directory.last().expect("only notes that are in a directory are counted");
+        let mut folder_note_path = output_dir.clone();
+        folder_note_path.extend(&directory);
+        folder_note_path.push(format!("{name}.md"));
+        if folder_note_path.exists() {
+            continue;
+        }
+        let mut lines = vec![format!("# {name}"), String::new()];
+        lines.extend(notes.into_iter().map(|(id, title)| if title.is_empty() { format!("- [[{id}]]") } else { format!("- [[{id}|{title}]]") }));
+        std::fs::write(folder_note_path, lines.join("\n") + "\n")?;
+    }
+
+    copy_attachments(config, &attachments, &output_dir)?;
+
+    api::command(&format!(
+        r#"echo "exported {} note(s) and {} attachment(s) to the obsidian vault at {}""#,
+        exported_count,
+        attachments.len(),
+        output_dir.to_string_lossy()
+    ))?;
+
+    Ok(())
+}
+
+/// Where previews are rendered. This is in the state directory rather than a shared temporary directory so that only whoever can read
+/// the wiki can read the preview.
+fn preview_dir(config: &Config) -> PathBuf {
+    state::state_dir(config).join("preview")
+}
+
+// the page checks for changes by reloading itself, which is enough for a file that is opened straight from the disk
+const LIVE_RELOAD_HEAD: &str = "<meta http-equiv=\"refresh\" content=\"2\">\n";
+
+/// Renders the current note (as it is in the buffer, even if it is not saved) the same way `export_html` does, and opens it in the
+/// browser. The notes that it links to are rendered next to it so that links out of the note work, except for the ones that would be
+/// left out of an export. With `live_reload`, the page keeps reloading itself so that it picks up the changes that [`refresh_preview`]
+/// writes.
+pub fn preview_note(config: &Config, live_reload: bool) -> Result<(), PreviewNoteError> {
+    let current_note = current_physical_note(config)?;
+    let output_dir = preview_dir(config);
+    // the preview of another note can have notes in it that this one does not link to
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir)?;
+    }
+
+    let linked = linked_preview_notes(config, &current_note)?;
+    let rendered = preview_rendered_notes(&current_note, &linked);
+    let mut attachments = BTreeSet::new();
+    for (note, contents, md) in &linked {
+        attachments.extend(write_note_html(config, note, contents, md, &rendered, &output_dir, "")?);
+    }
+    attachments.extend(render_current_note(config, &current_note, &rendered, &output_dir, live_reload)?);
+    copy_attachments(config, &attachments, &output_dir)?;
+
+    open_externally(config, html_output_path(&current_note, &output_dir).to_str().ok_or(NonUtf8Path)?)?;
+
+    Ok(())
+}
+
+/// Renders the current note into the preview again, without touching the rest of the wiki.
+pub fn refresh_preview(config: &Config, live_reload: bool) -> Result<(), PreviewNoteError> {
+    let current_note = current_physical_note(config)?;
+    let output_dir = preview_dir(config);
+    let rendered = preview_rendered_notes(&current_note, &linked_preview_notes(config, &current_note)?);
+    let attachments = render_current_note(config, &current_note, &rendered, &output_dir, live_reload)?;
+    copy_attachments(config, &attachments, &output_dir)?;
+    Ok(())
+}
+
+/// The notes that the current note (as it is in the buffer) links to that would be in an export, with their contents.
+fn linked_preview_notes(
+    config: &Config,
+    current_note: &note::PhysicalNote,
+) -> Result<Vec<(note::PhysicalNote, String, mdast::Node)>, PreviewNoteError> {
+    let current_md = markdown::parse_markdown(&get_buffer_lines(&api::get_current_buf())?.join("\n"))?;
+    let mut linked = Vec::new();
+    for linked_path in note_index::resolved_links(config, &note::Note::Physical(current_note.clone()), &current_md) {
+        if linked_path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+        let Ok(note) = note::PhysicalNote::parse_from_filepath(config, &linked_path) else { continue };
+        if note == *current_note || linked.iter().any(|(linked_note, _, _)| *linked_note == note) {
+            continue;
+        }
+        let Ok(contents) = note.read_contents(config) else { continue };
+        let Ok(md) = markdown::parse_markdown(&contents) else { continue };
+        if is_exported(&md, None) {
+            linked.push((note, contents, md));
+        }
+    }
+    Ok(linked)
+}
+
+fn preview_rendered_notes(current_note: &note::PhysicalNote, linked: &[(note::PhysicalNote, String, mdast::Node)]) -> BTreeSet<note::PhysicalNote> {
+    linked.iter().map(|(note, _, _)| note.clone()).chain([current_note.clone()]).collect()
+}
+
+fn current_physical_note(config: &Config) -> Result<note::PhysicalNote, PreviewNoteError> {
+    match note::Note::get_current_note(config)? {
+        note::Note::Physical(note) => Ok(note),
+        note::Note::Scratch(_) => Err(NotAPhysicalNote)?,
+    }
+}
+
+/// Writes a standalone markdown version of a note (the current note if `note` is not given) to `dest`, for sharing outside the wiki:
+/// embeds are filled in, links to notes become footnotes with the absolute path of what they point to, and the notes that link to it are
+/// listed at the end.
+pub fn compile_note(config: &Config, note: Option<String>, dest: String) -> Result<(), CompileNoteError> {
+    let physical_note = match note {
+        Some(note) => note::PhysicalNote::parse_from_filepath(config, Path::new(&note))?,
+        None => match note::Note::get_current_note(config)? {
+            note::Note::Physical(note) => note,
+            note::Note::Scratch(_) => Err(NotAPhysicalNote)?,
+        },
+    };
+    let as_note = note::Note::Physical(physical_note.clone());
+    let contents = physical_note.read_contents(config)?;
+    let title = markdown::parse_frontmatter(&markdown::parse_markdown(&contents)?).ok().and_then(|frontmatter| frontmatter.title);
+
+    let lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+    let inlined = compile::inline_autogenerated_sections(frontmatter::strip_frontmatter(&lines), |section| {
+        if section.command != "embed" {
+            return None;
+        }
+        let all_arguments = section.arguments.split(';').map(str::trim).collect::<Vec<_>>();
+        let (_, arguments) = filter::split_filters(&all_arguments);
+        embed_lines(config, &as_note, &arguments)
+    });
+    let mut body = inlined.join("\n") + "\n";
+    if let Some(title) = title {
+        body = format!("# {title}\n\n{body}");
+    }
+
+    // links in embedded lines are resolved as if they were written in this note, which is right as long as both are in the same directory
+    let md = markdown::parse_markdown(&body)?;
+    let footnoted = markdown::get_all_link_spans(&md)
+        .into_iter()
+        .filter(|span| !links::is_external_link(config, &span.target) && !links::split_anchor(&span.target).0.is_empty())
+        .filter_map(|span| {
+            let (_, anchor) = links::split_anchor(&span.target);
+            let path = links::resolve_link_path(config, &as_note, &span.target).ok()?;
+            let location = match anchor {
+                Some(anchor) => format!("{}#{anchor}", path.to_string_lossy()),
+                None => path.to_string_lossy().to_string(),
+            };
+            let title = note::PhysicalNote::parse_from_filepath(config, &path)
+                .ok()
+                .and_then(|note| note.read_contents(config).ok())
+                .and_then(|contents| markdown::parse_markdown(&contents).ok())
+                .and_then(|md| markdown::parse_frontmatter(&md).ok())
+                .and_then(|frontmatter| frontmatter.title);
+            Some(compile::FootnotedLink {
+                span: span.start_offset..span.end_offset,
+                text: span.text,
+                footnote: match title {
+                    Some(title) => format!("{title} ({location})"),
+                    None => location,
+                },
+            })
+        })
+        .collect();
+    let mut compiled = compile::footnote_links(&body, footnoted);
+
+    let backlinks = find_backlinks(config, &physical_note.path(config))?;
+    if !backlinks.is_empty() {
+        compiled.push_str("\n## Backlinks\n\n");
+        for backlink in backlinks {
+            let location = backlink.path.to_string_lossy();
+            let mut item = match &backlink.title {
+                Some(title) => format!("- {title} ({location})"),
+                None => format!("- {location}"),
+            };
+            let sections = backlink.sections();
+            if !sections.is_empty() {
+                item.push_str(&format!(", in {}", sections.join(", ")));
+            }
+            compiled.push_str(&item);
+            compiled.push('\n');
+        }
+    }
+
+    std::fs::write(&dest, compiled)?;
+    api::command(&format!(r#"echo "compiled {} to {dest}""#, physical_note.path(config).to_string_lossy()))?;
+
+    Ok(())
+}
+
+fn render_current_note(
+    config: &Config,
+    note: &note::PhysicalNote,
+    rendered: &BTreeSet<note::PhysicalNote>,
+    output_dir: &Path,
+    live_reload: bool,
+) -> Result<BTreeSet<PathBuf>, ExportError> {
+    let contents = get_buffer_lines(&api::get_current_buf())?.join("\n");
+    let md = markdown::parse_markdown(&contents)?;
+    write_note_html(config, note, &contents, &md, rendered, output_dir, if live_reload { LIVE_RELOAD_HEAD } else { "" })
+}
+
+/// Whether a note goes into an export: it is not marked `publish: false`, and if there are audiences, it is shared with one of them.
+fn is_exported(md: &mdast::Node, audiences: Option<&[String]>) -> bool {
+    let frontmatter = markdown::parse_frontmatter(md).ok();
+    if frontmatter.as_ref().and_then(|f| f.publish()) == Some(false) {
+        return false;
+    }
+    audiences.is_none_or(|audiences| shared_with(&frontmatter.as_ref().map(|f| f.audience()).unwrap_or_default(), audiences))
+}
+
+/// The source range of the text of each link, by where the link starts, which is what is left of a link to a note that is not exported.
+fn link_text_ranges(md: &mdast::Node) -> BTreeMap<usize, Range<usize>> {
+    markdown::get_all_links(md)
+        .into_iter()
+        .filter_map(|link| {
+            let start = link.position.as_ref()?.start.offset;
+            let text_start = link.children.first()?.position()?.start.offset;
+            let text_end = link.children.last()?.position()?.end.offset;
+            Some((start, text_start..text_end))
+        })
+        .collect()
+}
+
+fn shared_with(note_audience: &[String], audiences: &[String]) -> bool {
+    note_audience.iter().any(|audience| audiences.contains(audience))
+}
+
+/// Turns a link to a markdown note into a link to the exported html file, or returns `None` if the link is not to a markdown file.
+fn html_link_url(url: &str) -> Option<String> {
+    let (path, anchor) = links::split_anchor(url);
+    let path = path.strip_suffix(".md")?;
+    match anchor {
+        Some(anchor) => Some(format!("{path}.html#{anchor}")),
+        None => Some(format!("{path}.html")),
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn html_page(title: &str, head: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        head,
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_link_url_test() {
+        assert_eq!(html_link_url("note.md"), Some("note.html".to_string()));
+        assert_eq!(html_link_url("../dir/note.md#heading"), Some("../dir/note.html#heading".to_string()));
+        assert_eq!(html_link_url("image.png"), None);
+    }
+
+    #[test]
+    fn link_text_ranges_test() {
+        let contents = "[shared *note*](b.md) and [private note](c.md)";
+        let md = markdown::parse_markdown(contents).unwrap();
+        let texts: Vec<(usize, &str)> = link_text_ranges(&md).into_iter().map(|(start, text)| (start, &contents[text])).collect();
+        assert_eq!(texts, vec![(0, "shared *note*"), (26, "private note")]);
+    }
+
+    #[test]
+    fn shared_with_test() {
+        let audiences = ["team".to_string(), "public".to_string()];
+        assert!(shared_with(&["team".to_string()], &audiences));
+        assert!(!shared_with(&["private".to_string()], &audiences));
+        assert!(!shared_with(&[], &audiences));
+    }
+
+    #[test]
+    fn is_exported_test() {
+        let public = markdown::parse_markdown("---\naudience: [team]\n---\n").unwrap();
+        let unpublished = markdown::parse_markdown("---\npublish: false\n---\n").unwrap();
+        let team = ["team".to_string()];
+        assert!(is_exported(&public, None));
+        assert!(is_exported(&public, Some(&team)));
+        assert!(!is_exported(&unpublished, None));
+        assert!(!is_exported(&markdown::parse_markdown("no frontmatter").unwrap(), Some(&team)));
+    }
+
+    #[test]
+    fn write_note_html_unlinks_left_out_notes_test() {
+        let home_path = std::env::temp_dir().join("wikiplugin_write_note_html_test");
+        let _ = std::fs::remove_dir_all(&home_path);
+        std::fs::create_dir_all(&home_path).unwrap();
+        // parsing link targets canonicalizes them, so the home path has to be canonical too
+        let home_path = home_path.canonicalize().unwrap();
+        let config = Config::new(home_path.clone(), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let note = |id: &str| note::PhysicalNote { directories: vec![], id: id.to_string() };
+        let contents = "[shared *note*](b.md) and [private note](c.md)\n";
+        for (id, contents) in [("a", contents), ("b", ""), ("c", "")] {
+            std::fs::write(home_path.join(format!("{id}.md")), contents).unwrap();
+        }
+
+        let output_dir = home_path.join("out");
+        let rendered = BTreeSet::from([note("a"), note("b")]);
+        let md = markdown::parse_markdown(contents).unwrap();
+        write_note_html(&config, &note("a"), contents, &md, &rendered, &output_dir, "").unwrap();
+        let html = std::fs::read_to_string(output_dir.join("a.html")).unwrap();
+        assert!(html.contains("<a href=\"b.html\">shared <em>note</em></a>"));
+        assert!(html.contains("and private note"));
+        assert!(!html.contains("c.html") && !html.contains("c.md"));
+        std::fs::remove_dir_all(home_path).unwrap();
+    }
+
+    #[test]
+    fn html_page_escapes_title_test() {
+        assert!(html_page("a < b", "", "").contains("<title>a &lt; b</title>"));
+    }
+}
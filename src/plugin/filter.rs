@@ -0,0 +1,83 @@
+use crate::plugin::{markdown::Frontmatter, note::Tag};
+
+/// A condition on a note's frontmatter that autogenerate commands take as an argument, like `tag=project::wiki` or `field.status=active`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// the note has the tag or a tag nested inside it
+    Tag(Tag),
+    /// the frontmatter field is exactly the value
+    Field { name: String, value: String },
+}
+
+impl Filter {
+    /// Returns `None` if the argument is not a filter, so that commands can tell filters apart from their other arguments.
+    pub fn parse_from_str(s: &str) -> Option<Filter> {
+        let (key, value) = s.split_once('=')?;
+        let (key, value) = (key.trim(), value.trim());
+        match key.strip_prefix("field.") {
+            Some(name) if !name.is_empty() => Some(Filter::Field { name: name.to_string(), value: value.to_string() }),
+            Some(_) => None,
+            None if key == "tag" => Some(Filter::Tag(Tag::parse_from_str(value))),
+            None => None,
+        }
+    }
+
+    pub fn matches(&self, frontmatter: &Frontmatter) -> bool {
+        match self {
+            Filter::Tag(tag) => frontmatter.tags.iter().any(|note_tag| note_tag.is_within(tag)),
+            Filter::Field { name, value } => frontmatter.field_str(name).is_some_and(|field| &field == value),
+        }
+    }
+}
+
+/// Splits autogenerate arguments into the filters and the rest of the arguments, which keep their order.
+pub fn split_filters<'args>(arguments: &[&'args str]) -> (Vec<Filter>, Vec<&'args str>) {
+    let mut filters = Vec::new();
+    let mut rest = Vec::new();
+    for argument in arguments {
+        match Filter::parse_from_str(argument) {
+            Some(filter) => filters.push(filter),
+            None => rest.push(*argument),
+        }
+    }
+    (filters, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frontmatter(yaml: &str) -> Frontmatter {
+        Frontmatter::from_yaml(yaml_rust::YamlLoader::load_from_str(yaml).expect("yaml should parse").swap_remove(0))
+            .expect("frontmatter should be valid")
+    }
+
+    #[test]
+    fn parse_filter_test() {
+        assert_eq!(Filter::parse_from_str("tag=project::wiki"), Some(Filter::Tag(Tag::parse_from_str("project::wiki"))));
+        assert_eq!(
+            Filter::parse_from_str("field.status = active"),
+            Some(Filter::Field { name: "status".to_string(), value: "active".to_string() })
+        );
+        assert_eq!(Filter::parse_from_str("projects"), None);
+        assert_eq!(Filter::parse_from_str("field.=a"), None);
+        assert_eq!(Filter::parse_from_str("other=a"), None);
+    }
+
+    #[test]
+    fn filter_matches_test() {
+        let note = frontmatter("title: a\nstatus: active\ntags: project::wiki::plugin other");
+        assert!(Filter::parse_from_str("tag=project::wiki").unwrap().matches(&note));
+        assert!(!Filter::parse_from_str("tag=project::other").unwrap().matches(&note));
+        assert!(Filter::parse_from_str("field.status=active").unwrap().matches(&note));
+        assert!(Filter::parse_from_str("field.title=a").unwrap().matches(&note));
+        assert!(!Filter::parse_from_str("field.status=done").unwrap().matches(&note));
+    }
+
+    #[test]
+    fn split_filters_test() {
+        let (filters, rest) = split_filters(&["projects", "tag=a", "date", "field.status=active"]);
+        assert_eq!(filters.len(), 2);
+        assert_eq!(rest, ["projects", "date"]);
+    }
+}
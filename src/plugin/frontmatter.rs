@@ -0,0 +1,470 @@
+// edits to frontmatter are done line by line instead of by reserializing the yaml, so that the formatting that the user wrote is kept intact
+
+/// Returns the line indexes of the opening and closing `---` of the frontmatter.
+fn frontmatter_range(lines: &[String]) -> Option<(usize, usize)> {
+    if lines.first()?.trim_end() != "---" {
+        return None;
+    }
+    let end = lines.iter().enumerate().skip(1).find(|(_, line)| line.trim_end() == "---")?.0;
+    Some((0, end))
+}
+
+/// Toml and json frontmatter can be read but not edited, and a yaml block must not be added in front of them.
+fn has_other_frontmatter(lines: &[String]) -> bool {
+    matches!(lines.first().map(|line| line.trim_end()), Some("+++" | "{"))
+}
+
+fn find_key_line(lines: &[String], (start, end): (usize, usize), key: &str) -> Option<usize> {
+    (start + 1..end).find(|&i| lines[i].strip_prefix(key).is_some_and(|rest| rest.starts_with(':')))
+}
+
+/// The index just past the last line of the value of the key on `key_line`. This is more than one line for block scalars, block sequences,
+/// and anything else that continues onto indented lines. Comments and blank lines after the value are not part of it.
+fn value_end(lines: &[String], key_line: usize, (_, end): (usize, usize)) -> usize {
+    let mut value_end = key_line + 1;
+    for (i, line) in lines.iter().enumerate().take(end).skip(key_line + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // block sequences are allowed to be at the same indentation as their key
+        if line.starts_with([' ', '\t']) || trimmed == "-" || trimmed.starts_with("- ") {
+            value_end = i + 1;
+        } else {
+            break;
+        }
+    }
+    value_end
+}
+
+/// Splits a trailing comment (along with the whitespace in front of it) off of a value. A `#` only starts a comment if it comes after
+/// whitespace and is not inside a quoted scalar.
+fn split_comment(value: &str) -> (&str, &str) {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match quote {
+            Some(quote_char) if c == quote_char => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && (previous.is_whitespace() || previous == '[' || previous == ',') => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => {
+                let before = value[..i].trim_end();
+                return (before, &value[before.len()..]);
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    (value, "")
+}
+
+/// The lines after the frontmatter, or all of the lines if there is no frontmatter.
+pub fn strip_frontmatter(lines: &[String]) -> &[String] {
+    match frontmatter_range(lines) {
+        Some((_, end)) => &lines[end + 1..],
+        None => lines,
+    }
+}
+
+pub fn add_tags(lines: &[String], tags: &[String]) -> Vec<String> {
+    let mut lines = lines.to_vec();
+    if tags.is_empty() || has_other_frontmatter(&lines) {
+        return lines;
+    }
+
+    let Some(range @ (_, end)) = frontmatter_range(&lines) else {
+        let mut new_lines = vec!["---".to_string(), format!("tags: {}", tags.join(" ")), "---".to_string()];
+        new_lines.extend(lines);
+        return new_lines;
+    };
+
+    match find_key_line(&lines, range, "tags") {
+        None => lines.insert(end, format!("tags: {}", tags.join(" "))),
+        Some(tags_line) => {
+            let (key, value) = lines[tags_line].split_once(':').expect("tags line should contain a colon because find_key_line checks for it");
+            let (value, comment) = split_comment(value.trim());
+            let (key, value, comment) = (key.to_string(), value.to_string(), comment.to_string());
+
+            if value.is_empty() {
+                // block sequence: add more items after the last one, with the same indentation
+                let block_end = value_end(&lines, tags_line, range);
+                let items: Vec<usize> = (tags_line + 1..block_end).filter(|&i| lines[i].trim_start().starts_with("- ")).collect();
+                let indent = match items.first() {
+                    Some(&first) => lines[first][..lines[first].len() - lines[first].trim_start().len()].to_string(),
+                    None => "  ".to_string(),
+                };
+                let insert_at = items.last().map_or(tags_line + 1, |&last| last + 1);
+                for (i, tag) in tags.iter().enumerate() {
+                    lines.insert(insert_at + i, format!("{indent}- {tag}"));
+                }
+            } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                // flow sequence
+                let inner = inner.trim();
+                lines[tags_line] = if inner.is_empty() {
+                    format!("{key}: [{}]{comment}", tags.join(", "))
+                } else {
+                    format!("{key}: [{inner}, {}]{comment}", tags.join(", "))
+                };
+            } else {
+                // space separated string
+                lines[tags_line] = format!("{key}: {value} {}{comment}", tags.join(" "));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Adds `key: value` to the frontmatter (creating the frontmatter if there is none) unless the key is already there.
+pub fn add_field_if_missing(lines: &[String], key: &str, value: &str) -> Vec<String> {
+    let mut lines = lines.to_vec();
+    match frontmatter_range(&lines) {
+        None if has_other_frontmatter(&lines) => lines,
+        None => {
+            let mut new_lines = vec!["---".to_string(), format!("{key}: {value}"), "---".to_string()];
+            new_lines.extend(lines);
+            new_lines
+        }
+        Some(range @ (_, end)) => {
+            if find_key_line(&lines, range, key).is_none() {
+                lines.insert(end, format!("{key}: {value}"));
+            }
+            lines
+        }
+    }
+}
+
+/// Sets `key` to `value`, replacing the value that is already there or adding the field if there is not one. A comment after the old value
+/// is kept, and so are its quotes if the new value is a plain scalar.
+pub fn set_field(lines: &[String], key: &str, value: &str) -> Vec<String> {
+    let mut lines = lines.to_vec();
+    if let Some(range) = frontmatter_range(&lines) {
+        if let Some(key_line) = find_key_line(&lines, range, key) {
+            let (_, old_value) = lines[key_line].split_once(':').expect("key line should contain a colon because find_key_line checks for it");
+            let (old_value, comment) = split_comment(old_value.trim());
+            let new_line = format!("{key}: {}{comment}", keep_quote_style(old_value, value));
+            lines.splice(key_line..value_end(&lines, key_line, range), [new_line]);
+            return lines;
+        }
+    }
+    add_field_if_missing(&lines, key, value)
+}
+
+/// Quotes `value` if it would not be read back as the same string when written as a plain yaml scalar.
+pub fn yaml_scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.starts_with(|c: char| c.is_whitespace() || "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || value.ends_with(char::is_whitespace);
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn quote_of(value: &str) -> Option<char> {
+    ['"', '\''].into_iter().find(|&quote| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote))
+}
+
+/// Puts `new` in the same quotes that `old` was in, unless it is already quoted, is a flow collection, or contains the quote character.
+fn keep_quote_style(old: &str, new: &str) -> String {
+    let plain = !new.starts_with(['"', '\'', '[', '{']);
+    match quote_of(old) {
+        Some(quote) if plain && !new.contains(quote) => format!("{quote}{new}{quote}"),
+        _ => new.to_string(),
+    }
+}
+
+/// Replaces the whole tags field with a flow sequence of the given tags, whatever form it was written in before. The field is removed if
+/// there are no tags.
+pub fn replace_tags(lines: &[String], tags: &[String]) -> Vec<String> {
+    let lines = edit_tags(lines, |_| TagEdit::Remove).unwrap_or_else(|| lines.to_vec());
+    if tags.is_empty() {
+        lines
+    } else {
+        set_field(&lines, "tags", &format!("[{}]", tags.join(", ")))
+    }
+}
+
+/// The status after `current` in the configured order. A note without a status (or with one that is not configured) goes to the first
+/// status, and a note that is already at the last status stays there.
+pub fn next_status<'states>(states: &'states [String], current: Option<&str>) -> Option<&'states str> {
+    let next = match current.and_then(|current| states.iter().position(|state| state == current)) {
+        Some(index) => (index + 1).min(states.len() - 1),
+        None => 0,
+    };
+    states.get(next).map(String::as_str)
+}
+
+/// Renames `old` to `new` in the tags field, along with every tag nested inside `old` (so renaming `a` to `b` turns `a::x` into `b::x`).
+/// Returns `None` if none of the tags were renamed.
+pub fn rename_tag(lines: &[String], old: &str, new: &str) -> Option<Vec<String>> {
+    edit_tags(lines, |tag| match tag.strip_prefix(old) {
+        Some(rest) if rest.is_empty() || rest.starts_with("::") => TagEdit::Replace(format!("{new}{rest}")),
+        _ => TagEdit::Keep,
+    })
+}
+
+/// Removes `tag` from the tags field, removing the field entirely if it ends up empty. Returns `None` if the note did not have the tag.
+pub fn remove_tag(lines: &[String], tag: &str) -> Option<Vec<String>> {
+    edit_tags(lines, |existing| if existing == tag { TagEdit::Remove } else { TagEdit::Keep })
+}
+
+enum TagEdit {
+    Keep,
+    Replace(String),
+    Remove,
+}
+
+/// Applies `edit` to every tag in the tags field, in whichever of the forms that `get_tags` accepts it is written in. Returns `None` if
+/// nothing changed.
+fn edit_tags(lines: &[String], mut edit: impl FnMut(&str) -> TagEdit) -> Option<Vec<String>> {
+    let range = frontmatter_range(lines)?;
+    let tags_line = find_key_line(lines, range, "tags")?;
+
+    let mut changed = false;
+    // returns None if the item should be removed
+    let mut edit_item = |item: &str| -> Option<String> {
+        // keep any quotes around the tag
+        let quote = quote_of(item);
+        let unquoted = if quote.is_some() { &item[1..item.len() - 1] } else { item };
+        match edit(unquoted) {
+            TagEdit::Keep => Some(item.to_string()),
+            TagEdit::Replace(new) => {
+                changed = true;
+                Some(match quote {
+                    Some(quote) => format!("{quote}{new}{quote}"),
+                    None => new,
+                })
+            }
+            TagEdit::Remove => {
+                changed = true;
+                None
+            }
+        }
+    };
+
+    let mut lines = lines.to_vec();
+    let (key, value) = lines[tags_line].split_once(':').expect("tags line should contain a colon because find_key_line checks for it");
+    let (value, comment) = split_comment(value.trim());
+    let (key, value, comment) = (key.to_string(), value.to_string(), comment.to_string());
+
+    let remaining = if value.is_empty() {
+        // block sequence; comments and blank lines between the items stay where they are
+        let mut remaining = 0;
+        let block_end = value_end(&lines, tags_line, range);
+        let block: Vec<String> = lines
+            .drain(tags_line + 1..block_end)
+            .filter_map(|line| {
+                let Some((indent, item)) = line.split_once("- ").filter(|(indent, _)| indent.trim().is_empty()) else {
+                    return Some(line);
+                };
+                let (item, item_comment) = split_comment(item.trim());
+                let edited = edit_item(item).map(|item| format!("{indent}- {item}{item_comment}"));
+                remaining += usize::from(edited.is_some());
+                edited
+            })
+            .collect();
+        lines.splice(tags_line + 1..tags_line + 1, block);
+        remaining
+    } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        // flow sequence
+        let items: Vec<String> = inner.split(',').map(str::trim).filter(|item| !item.is_empty()).filter_map(&mut edit_item).collect();
+        lines[tags_line] = format!("{key}: [{}]{comment}", items.join(", "));
+        items.len()
+    } else {
+        // space separated string
+        let items: Vec<String> = value.split(' ').filter(|item| !item.is_empty()).filter_map(&mut edit_item).collect();
+        lines[tags_line] = format!("{key}: {}{comment}", items.join(" "));
+        items.len()
+    };
+
+    if !changed {
+        return None;
+    }
+    if remaining == 0 {
+        // the block sequence items (if any) are already gone, so only the key is left
+        lines.remove(tags_line);
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn strip_frontmatter_test() {
+        assert_eq!(strip_frontmatter(&to_lines("---\ntitle: a\n---\n\nbody")), to_lines("\nbody"));
+        assert_eq!(strip_frontmatter(&to_lines("body")), to_lines("body"));
+    }
+
+    #[test]
+    fn add_tags_string_test() {
+        let lines = to_lines("---\ntitle: a\ntags: one two\n---\nbody");
+        assert_eq!(add_tags(&lines, &tags(&["three"])), to_lines("---\ntitle: a\ntags: one two three\n---\nbody"));
+    }
+
+    #[test]
+    fn add_tags_flow_sequence_test() {
+        let lines = to_lines("---\ntags: [one, two]\n---");
+        assert_eq!(add_tags(&lines, &tags(&["three", "four"])), to_lines("---\ntags: [one, two, three, four]\n---"));
+    }
+
+    #[test]
+    fn add_tags_block_sequence_test() {
+        let lines = to_lines("---\ntags:\n    - one\n# comment\ntitle: a\n---");
+        assert_eq!(add_tags(&lines, &tags(&["two"])), to_lines("---\ntags:\n    - one\n    - two\n# comment\ntitle: a\n---"));
+    }
+
+    #[test]
+    fn add_tags_no_tags_field_test() {
+        let lines = to_lines("---\ntitle: a\n---\nbody");
+        assert_eq!(add_tags(&lines, &tags(&["one"])), to_lines("---\ntitle: a\ntags: one\n---\nbody"));
+    }
+
+    #[test]
+    fn add_field_if_missing_test() {
+        let lines = to_lines("---\ntitle: a\n---\nbody");
+        assert_eq!(add_field_if_missing(&lines, "author", "someone"), to_lines("---\ntitle: a\nauthor: someone\n---\nbody"));
+        let lines = to_lines("---\nauthor: someone else\n---");
+        assert_eq!(add_field_if_missing(&lines, "author", "someone"), lines);
+        assert_eq!(add_field_if_missing(&[], "author", "someone"), to_lines("---\nauthor: someone\n---"));
+    }
+
+    #[test]
+    fn set_field_test() {
+        let lines = to_lines("---\nstatus: seedling\ntitle: a\n---");
+        assert_eq!(set_field(&lines, "status", "budding"), to_lines("---\nstatus: budding\ntitle: a\n---"));
+        assert_eq!(set_field(&to_lines("---\ntitle: a\n---"), "status", "seedling"), to_lines("---\ntitle: a\nstatus: seedling\n---"));
+    }
+
+    #[test]
+    fn split_comment_test() {
+        assert_eq!(split_comment("a # c"), ("a", " # c"));
+        assert_eq!(split_comment("'a # b' # c"), ("'a # b'", " # c"));
+        assert_eq!(split_comment("[\"#x\", y]  # c"), ("[\"#x\", y]", "  # c"));
+        assert_eq!(split_comment("a#b"), ("a#b", ""));
+        assert_eq!(split_comment("don't # c"), ("don't", " # c"));
+        assert_eq!(split_comment("# c"), ("", "# c"));
+    }
+
+    #[test]
+    fn set_field_keeps_comment_and_quotes_test() {
+        let lines = to_lines("---\nstatus: \"seedling\" # stage\n---");
+        assert_eq!(set_field(&lines, "status", "budding"), to_lines("---\nstatus: \"budding\" # stage\n---"));
+        let lines = to_lines("---\nstatus: 'seedling'\n---");
+        assert_eq!(set_field(&lines, "status", "[a, b]"), to_lines("---\nstatus: [a, b]\n---"));
+    }
+
+    #[test]
+    fn set_field_multi_line_value_test() {
+        let lines = to_lines("---\nstatus: |\n  first line\n\n  second line\n# comment\ntitle: a\n---");
+        assert_eq!(set_field(&lines, "status", "budding"), to_lines("---\nstatus: budding\n# comment\ntitle: a\n---"));
+        let lines = to_lines("---\nstatus:\n- a\n- b\ntitle: a\n---");
+        assert_eq!(set_field(&lines, "status", "budding"), to_lines("---\nstatus: budding\ntitle: a\n---"));
+    }
+
+    #[test]
+    fn add_tags_keeps_comment_test() {
+        assert_eq!(add_tags(&to_lines("---\ntags: [a] # mine\n---"), &tags(&["b"])), to_lines("---\ntags: [a, b] # mine\n---"));
+        assert_eq!(add_tags(&to_lines("---\ntags: a # mine\n---"), &tags(&["b"])), to_lines("---\ntags: a b # mine\n---"));
+        let lines = to_lines("---\ntags:\n  - a\n  # between\n  - b\ntitle: t\n---");
+        assert_eq!(add_tags(&lines, &tags(&["c"])), to_lines("---\ntags:\n  - a\n  # between\n  - b\n  - c\ntitle: t\n---"));
+    }
+
+    #[test]
+    fn edit_block_sequence_with_comments_test() {
+        let lines = to_lines("---\ntags:\n  # first\n  - one # main\n  - two\n---");
+        assert_eq!(remove_tag(&lines, "two"), Some(to_lines("---\ntags:\n  # first\n  - one # main\n---")));
+        assert_eq!(rename_tag(&lines, "one", "uno"), Some(to_lines("---\ntags:\n  # first\n  - uno # main\n  - two\n---")));
+        let lines = to_lines("---\ntags: [one, two] # list\n---");
+        assert_eq!(remove_tag(&lines, "one"), Some(to_lines("---\ntags: [two] # list\n---")));
+    }
+
+    #[test]
+    fn yaml_scalar_test() {
+        assert_eq!(yaml_scalar("plain title"), "plain title");
+        assert_eq!(yaml_scalar("part one: the start"), "\"part one: the start\"");
+        assert_eq!(yaml_scalar("\"quoted\" # not a comment"), "\"\\\"quoted\\\" # not a comment\"");
+        assert_eq!(yaml_scalar("- list"), "\"- list\"");
+    }
+
+    #[test]
+    fn replace_tags_test() {
+        let lines = to_lines("---\ntags:\n  - a::b\n  - c\ntitle: t\n---");
+        assert_eq!(replace_tags(&lines, &tags(&["a/b", "c"])), to_lines("---\ntitle: t\ntags: [a/b, c]\n---"));
+        assert_eq!(replace_tags(&to_lines("---\ntags: a\n---"), &[]), to_lines("---\n---"));
+    }
+
+    #[test]
+    fn next_status_test() {
+        let states = tags(&["seedling", "budding", "evergreen"]);
+        assert_eq!(next_status(&states, None), Some("seedling"));
+        assert_eq!(next_status(&states, Some("seedling")), Some("budding"));
+        assert_eq!(next_status(&states, Some("evergreen")), Some("evergreen"));
+        assert_eq!(next_status(&states, Some("unknown")), Some("seedling"));
+        assert_eq!(next_status(&[], None), None);
+    }
+
+    #[test]
+    fn rename_tag_string_test() {
+        let lines = to_lines("---\ntags: one two one::child onesie\n---\nbody one");
+        assert_eq!(rename_tag(&lines, "one", "uno"), Some(to_lines("---\ntags: uno two uno::child onesie\n---\nbody one")));
+    }
+
+    #[test]
+    fn rename_tag_flow_sequence_test() {
+        let lines = to_lines("---\ntags: [one, 'two', \"one::child\"]\n---");
+        assert_eq!(rename_tag(&lines, "one", "uno"), Some(to_lines("---\ntags: [uno, 'two', \"uno::child\"]\n---")));
+    }
+
+    #[test]
+    fn rename_tag_block_sequence_test() {
+        let lines = to_lines("---\ntags:\n  - two\n  - one\ntitle: one\n---");
+        assert_eq!(rename_tag(&lines, "one", "uno"), Some(to_lines("---\ntags:\n  - two\n  - uno\ntitle: one\n---")));
+    }
+
+    #[test]
+    fn rename_tag_missing_test() {
+        assert_eq!(rename_tag(&to_lines("---\ntags: two\n---"), "one", "uno"), None);
+        assert_eq!(rename_tag(&to_lines("---\ntitle: one\n---"), "one", "uno"), None);
+        assert_eq!(rename_tag(&to_lines("one"), "one", "uno"), None);
+    }
+
+    #[test]
+    fn remove_tag_test() {
+        assert_eq!(remove_tag(&to_lines("---\ntags: one two\n---"), "one"), Some(to_lines("---\ntags: two\n---")));
+        assert_eq!(remove_tag(&to_lines("---\ntags: ['one', 'two']\n---"), "one"), Some(to_lines("---\ntags: ['two']\n---")));
+        assert_eq!(remove_tag(&to_lines("---\ntags:\n  - one\n  - two\n---"), "one"), Some(to_lines("---\ntags:\n  - two\n---")));
+        assert_eq!(remove_tag(&to_lines("---\ntags: one::child\n---"), "one"), None);
+    }
+
+    #[test]
+    fn remove_last_tag_test() {
+        assert_eq!(remove_tag(&to_lines("---\ntitle: a\ntags: one\n---"), "one"), Some(to_lines("---\ntitle: a\n---")));
+        assert_eq!(remove_tag(&to_lines("---\ntags:\n  - one\ntitle: a\n---"), "one"), Some(to_lines("---\ntitle: a\n---")));
+    }
+
+    #[test]
+    fn add_tags_other_frontmatter_test() {
+        let lines = to_lines("+++\ntitle = \"a\"\n+++");
+        assert_eq!(add_tags(&lines, &tags(&["one"])), lines);
+    }
+
+    #[test]
+    fn add_tags_no_frontmatter_test() {
+        let lines = to_lines("body");
+        assert_eq!(add_tags(&lines, &tags(&["one"])), to_lines("---\ntags: one\n---\nbody"));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::plugin::markdown;
+
+/// How many of the links into a note point at each of its headings, as (0 indexed line of the heading, count) pairs in the order that
+/// the headings are in. `anchors` are the anchors of the links, without the `#`; a link to a block (`^id`) counts towards the heading
+/// that the block is under.
+pub fn section_link_counts(lines: &[String], anchors: &[&str]) -> Vec<(usize, usize)> {
+    let mut sections: Vec<(usize, String)> = Vec::new();
+    let mut block_sections: Vec<(String, usize)> = Vec::new();
+    let mut in_code_block = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if in_code_block {
+            continue;
+        } else if let Some((_, text)) = markdown::atx_heading(line) {
+            sections.push((i, markdown::slugify(text)));
+        } else if let Some(block_id) = line.trim_end().rsplit(' ').next().and_then(|last| last.strip_prefix('^')) {
+            if let Some(section) = sections.len().checked_sub(1) {
+                block_sections.push((block_id.to_string(), section));
+            }
+        }
+    }
+
+    let mut counts = vec![0; sections.len()];
+    for anchor in anchors {
+        let section = match anchor.strip_prefix('^') {
+            Some(block_id) => block_sections.iter().find(|(id, _)| id == block_id).map(|(_, section)| *section),
+            None => sections.iter().position(|(_, slug)| *slug == markdown::slugify(anchor)),
+        };
+        if let Some(section) = section {
+            counts[section] += 1;
+        }
+    }
+    sections.into_iter().map(|(line, _)| line).zip(counts).collect()
+}
+
+/// The virtual text shown next to a heading: its share of all of the links into the note that target a section.
+pub fn heatmap_label(count: usize, total: usize) -> String {
+    let percent = if total == 0 { 0 } else { count * 100 / total };
+    format!("{percent}% ({count} link{})", if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn section_link_counts_test() {
+        let lines = to_lines("# Title\nintro\n## First Part\na paragraph ^para\n```\n# code\n```\n## second");
+        let counts = section_link_counts(&lines, &["first-part", "^para", "title", "second", "missing", "^missing"]);
+        assert_eq!(counts, [(0, 1), (2, 2), (7, 1)]);
+    }
+
+    #[test]
+    fn heatmap_label_test() {
+        assert_eq!(heatmap_label(1, 4), "25% (1 link)");
+        assert_eq!(heatmap_label(0, 0), "0% (0 links)");
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+/// Icons (or any other prefix strings) to put in front of notes in generated lists and pickers so that different kinds of notes can be
+/// told apart at a glance.
+#[derive(Debug, Clone, Default)]
+pub struct NoteIcons {
+    /// keyed by directory path relative to the wiki home, like `people` or `diary/2024`
+    pub by_directory: BTreeMap<String, String>,
+    /// keyed by the `kind` field in the frontmatter
+    pub by_kind: BTreeMap<String, String>,
+}
+
+impl NoteIcons {
+    /// The icon for the note's kind if there is one, otherwise the icon of the innermost directory that contains the note and has an icon.
+    pub fn icon_for(&self, directories: &[String], kind: Option<&str>) -> Option<&str> {
+        kind.and_then(|kind| self.by_kind.get(kind))
+            .or_else(|| (1..=directories.len()).rev().find_map(|depth| self.by_directory.get(&directories[..depth].join("/"))))
+            .map(String::as_str)
+    }
+
+    /// Whether finding the icon for a note requires reading its frontmatter.
+    pub fn uses_kinds(&self) -> bool {
+        !self.by_kind.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icons() -> NoteIcons {
+        NoteIcons {
+            by_directory: BTreeMap::from([("people".to_string(), "P".to_string()), ("diary/2024".to_string(), "D".to_string())]),
+            by_kind: BTreeMap::from([("meeting".to_string(), "M".to_string())]),
+        }
+    }
+
+    #[test]
+    fn icon_for_directory_test() {
+        let icons = icons();
+        assert_eq!(icons.icon_for(&["people".to_string()], None), Some("P"));
+        assert_eq!(icons.icon_for(&["people".to_string(), "family".to_string()], None), Some("P"));
+        assert_eq!(icons.icon_for(&["diary".to_string(), "2024".to_string()], None), Some("D"));
+        assert_eq!(icons.icon_for(&["diary".to_string()], None), None);
+        assert_eq!(icons.icon_for(&[], None), None);
+    }
+
+    #[test]
+    fn icon_for_kind_test() {
+        let icons = icons();
+        assert_eq!(icons.icon_for(&["people".to_string()], Some("meeting")), Some("M"));
+        assert_eq!(icons.icon_for(&["people".to_string()], Some("unknown")), Some("P"));
+    }
+}
@@ -0,0 +1,74 @@
+/// Globs for files under the wiki home directory that are not notes, like the `.git` directory or an archive. A pattern that matches a
+/// directory ignores everything in it.
+#[derive(Debug, Clone, Default)]
+pub struct IgnorePatterns {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl IgnorePatterns {
+    pub fn parse(patterns: &[String]) -> Result<IgnorePatterns, (String, glob::PatternError)> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| glob::Pattern::new(pattern.trim_matches('/')).map_err(|e| (pattern.clone(), e)))
+            .collect::<Result<_, _>>()?;
+        Ok(IgnorePatterns { patterns })
+    }
+
+    /// Reads the patterns out of a `.gitignore` at the wiki home directory. Only the parts of the format that make sense for notes are
+    /// understood: negated patterns are skipped, and patterns that are invalid as globs are too.
+    pub fn parse_gitignore(contents: &str) -> IgnorePatterns {
+        let patterns = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+            .filter_map(|line| {
+                let line = line.trim_end_matches('/');
+                // like git, a pattern with no slash except at the end matches at any depth
+                let pattern = match line.strip_prefix('/') {
+                    Some(anchored) => anchored.to_string(),
+                    None if !line.contains('/') => format!("**/{line}"),
+                    None => line.to_string(),
+                };
+                glob::Pattern::new(&pattern).ok()
+            })
+            .collect();
+        IgnorePatterns { patterns }
+    }
+
+    pub fn extend(&mut self, other: IgnorePatterns) {
+        self.patterns.extend(other.patterns);
+    }
+
+    /// Whether the file at `relative_path` (relative to the home directory, with `/` between directories) or any directory that it is in
+    /// is ignored.
+    pub fn is_ignored(&self, relative_path: &str) -> bool {
+        let mut prefixes = relative_path.match_indices('/').map(|(i, _)| &relative_path[..i]).chain(std::iter::once(relative_path));
+        prefixes.any(|prefix| self.patterns.iter().any(|pattern| pattern.matches(prefix)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_ignored_test() {
+        let ignore = IgnorePatterns::parse(&[".git".to_string(), "archive/".to_string(), "*.draft.md".to_string()]).unwrap();
+        assert!(ignore.is_ignored(".git/notes.md"));
+        assert!(ignore.is_ignored("archive/2020/old.md"));
+        assert!(ignore.is_ignored("ideas/post.draft.md"));
+        assert!(!ignore.is_ignored("projects/archive.md"));
+        assert!(!ignore.is_ignored("note.md"));
+    }
+
+    #[test]
+    fn parse_gitignore_test() {
+        let ignore = IgnorePatterns::parse_gitignore("# comment\n\nnode_modules/\n/build\n!keep.md\ndocs/generated\n");
+        assert!(ignore.is_ignored("node_modules/readme.md"));
+        assert!(ignore.is_ignored("tools/node_modules/readme.md"));
+        assert!(ignore.is_ignored("build/out.md"));
+        assert!(!ignore.is_ignored("tools/build/out.md"));
+        assert!(ignore.is_ignored("docs/generated/api.md"));
+        assert!(!ignore.is_ignored("keep.md"));
+    }
+}
@@ -0,0 +1,110 @@
+/// Reads the width and height out of the header of a png, gif, or jpeg image. Returns `None` for other formats and for files that are
+/// too short or malformed.
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // the IHDR chunk always comes first: 4 bytes of length, then "IHDR", then the big endian width and height
+        let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+        Some((width, height))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        let width = u16::from_le_bytes(bytes.get(6..8)?.try_into().ok()?);
+        let height = u16::from_le_bytes(bytes.get(8..10)?.try_into().ok()?);
+        Some((width.into(), height.into()))
+    } else if bytes.starts_with(b"\xff\xd8") {
+        jpeg_dimensions(bytes)
+    } else {
+        None
+    }
+}
+
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    loop {
+        if *bytes.get(i)? != 0xff {
+            return None;
+        }
+        let marker = *bytes.get(i + 1)?;
+        // markers can be padded with any number of 0xff bytes
+        if marker == 0xff {
+            i += 1;
+            continue;
+        }
+        // these markers stand alone without a length
+        if marker == 0x01 || (0xd0..=0xd9).contains(&marker) {
+            i += 2;
+            continue;
+        }
+        let length = usize::from(u16::from_be_bytes(bytes.get(i + 2..i + 4)?.try_into().ok()?));
+        // start of frame markers, except for the ones that are used for huffman tables, arithmetic coding, and so on
+        if (0xc0..=0xcf).contains(&marker) && ![0xc4, 0xc8, 0xcc].contains(&marker) {
+            // after the length comes 1 byte of sample precision, then the height and the width
+            let height = u16::from_be_bytes(bytes.get(i + 5..i + 7)?.try_into().ok()?);
+            let width = u16::from_be_bytes(bytes.get(i + 7..i + 9)?.try_into().ok()?);
+            return Some((width.into(), height.into()));
+        }
+        i += 2 + length;
+    }
+}
+
+/// Formats a number of bytes for people to read, like `512 B` or `1.5 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_dimensions_test() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend(640u32.to_be_bytes());
+        png.extend(480u32.to_be_bytes());
+        assert_eq!(image_dimensions(&png), Some((640, 480)));
+        assert_eq!(image_dimensions(&png[..18]), None);
+    }
+
+    #[test]
+    fn gif_dimensions_test() {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(300u16.to_le_bytes());
+        gif.extend(200u16.to_le_bytes());
+        assert_eq!(image_dimensions(&gif), Some((300, 200)));
+    }
+
+    #[test]
+    fn jpeg_dimensions_test() {
+        let mut jpeg = b"\xff\xd8".to_vec();
+        // an APP0 segment that has to be skipped over
+        jpeg.extend(b"\xff\xe0\x00\x04ab");
+        // a huffman table, which is in the start of frame range but is not one
+        jpeg.extend(b"\xff\xc4\x00\x03c");
+        jpeg.extend(b"\xff\xc0\x00\x11\x08");
+        jpeg.extend(768u16.to_be_bytes());
+        jpeg.extend(1024u16.to_be_bytes());
+        assert_eq!(image_dimensions(&jpeg), Some((1024, 768)));
+    }
+
+    #[test]
+    fn unknown_format_test() {
+        assert_eq!(image_dimensions(b"<svg></svg>"), None);
+        assert_eq!(image_dimensions(b""), None);
+    }
+
+    #[test]
+    fn format_size_test() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     fmt::Display,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 use pathdiff::diff_paths;
 
 use crate::plugin::{
+    list_all_physical_notes, markdown,
     note::{Note, PhysicalNote, ScratchNote},
-    Config,
+    remap, state, wiki_file_exists, Config,
 };
 
 #[derive(Debug)]
@@ -16,10 +17,13 @@ pub enum FormatLinkPathError {
     CurrentFilePathNoParent,
     CouldNotConstructLink,
     PathNotUtf8,
+    TargetOutsideWiki,
+    TargetNoFileStem,
 }
 #[derive(Debug)]
 pub enum ResolveLinkPathError {
     CurrentNoteNoParent,
+    AnchorOnlyLinkInScratchNote,
 }
 
 impl Display for FormatLinkPathError {
@@ -29,6 +33,8 @@ impl Display for FormatLinkPathError {
             FormatLinkPathError::CurrentFilePathNoParent => write!(f, "could not get parent of current file path"),
             FormatLinkPathError::CouldNotConstructLink => write!(f, "could not construct link from"),
             FormatLinkPathError::PathNotUtf8 => write!(f, "link path is not valid unicode"),
+            FormatLinkPathError::TargetOutsideWiki => write!(f, "cannot make a home-relative link to a file outside of the wiki"),
+            FormatLinkPathError::TargetNoFileStem => write!(f, "cannot make an id link to a file with no name"),
         }
     }
 }
@@ -36,41 +42,260 @@ impl Display for ResolveLinkPathError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ResolveLinkPathError::CurrentNoteNoParent => write!(f, "note path has no parent"),
+            ResolveLinkPathError::AnchorOnlyLinkInScratchNote => write!(f, "cannot resolve a link to a heading in a scratch note"),
         }
     }
 }
 
-pub fn format_link_path(config: &Config, current_note: &Note, target_file_path: &Path) -> Result<String, FormatLinkPathError> {
+/// How links to other notes are written when the plugin inserts them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// `[text](../dir/note.md)`
+    Relative,
+    /// `[text](/path/to/wiki/dir/note.md)`
+    Absolute,
+    /// `[text](/dir/note.md)`, relative to the wiki home directory
+    HomeRelative,
+    /// `[text](note)`, found by id wherever it is in the wiki
+    IdOnly,
+    /// `[[note|text]]`
+    Wikilink,
+}
+
+impl LinkStyle {
+    pub fn parse_from_str(s: &str) -> Option<LinkStyle> {
+        match s {
+            "relative" => Some(LinkStyle::Relative),
+            "absolute" => Some(LinkStyle::Absolute),
+            "home-relative" => Some(LinkStyle::HomeRelative),
+            "id-only" => Some(LinkStyle::IdOnly),
+            "wikilink" => Some(LinkStyle::Wikilink),
+            _ => None,
+        }
+    }
+
+    /// Whether links written in this style only contain the note id, so they need to be looked up by id to be resolved.
+    fn links_by_id(self) -> bool {
+        matches!(self, LinkStyle::IdOnly | LinkStyle::Wikilink)
+    }
+}
+
+/// Returns `true` if the url has a scheme (like `https:` or `mailto:`), meaning that it does not point at a file in the wiki.
+pub fn is_external_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) => scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)),
+        None => false,
+    }
+}
+
+/// Splits an interwiki link like `work:projects/foo.md` into the configured wiki that it points into and the path inside that wiki, which
+/// is relative to that wiki's home directory.
+pub fn split_interwiki<'a>(config: &'a Config, link: &'a str) -> Option<(&'a Config, &'a str)> {
+    let (name, path) = link.split_once(':')?;
+    Some((config.wikis.get(name)?, path))
+}
+
+/// Like [`is_external_url`], except that interwiki links, which look like urls, are not external.
+pub fn is_external_link(config: &Config, link: &str) -> bool {
+    is_external_url(link) && split_interwiki(config, link).is_none()
+}
+
+/// Splits a link like `note.md#heading` into the path and the heading anchor.
+pub fn split_anchor(link: &str) -> (&str, Option<&str>) {
+    match link.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (link, None),
+    }
+}
+
+/// Formats the path part of a link to the target file according to the configured [`LinkStyle`].
+pub fn format_link_path(config: &Config, current_note: &Note, target_file_path: &Path, anchor: Option<&str>) -> Result<String, FormatLinkPathError> {
     if !(target_file_path.is_absolute()) {
         return Err(FormatLinkPathError::TargetNotAbsolute);
     }
-    match current_note {
-        Note::Physical(pn @ PhysicalNote { directories: _, id: _ }) => {
+    // notes in other wikis are linked to with the name of their wiki, whatever the link style is
+    let other_wiki = config
+        .wikis
+        .iter()
+        .filter(|(_, wiki)| !target_file_path.starts_with(&config.home_path) && target_file_path.starts_with(&wiki.home_path))
+        .max_by_key(|(_, wiki)| wiki.home_path.as_os_str().len());
+    let path = match (config.link_style, current_note, other_wiki) {
+        (_, _, Some((name, wiki))) => {
+            let relative = target_file_path.strip_prefix(&wiki.home_path).expect("target should be inside the wiki that it was found in");
+            format!("{name}:{}", relative.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?)
+        }
+        (LinkStyle::Absolute, _, None) => target_file_path.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string(),
+        (LinkStyle::HomeRelative, _, None) => {
+            let relative = target_file_path.strip_prefix(&config.home_path).map_err(|_| FormatLinkPathError::TargetOutsideWiki)?;
+            format!("/{}", relative.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?)
+        }
+        (LinkStyle::IdOnly | LinkStyle::Wikilink, _, None) => {
+            target_file_path.file_stem().ok_or(FormatLinkPathError::TargetNoFileStem)?.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string()
+        }
+        (LinkStyle::Relative, Note::Physical(pn @ PhysicalNote { directories: _, id: _ }), None) => {
             let current_note_path = pn.path(config);
             let current_file_parent_dir = current_note_path.parent().ok_or(FormatLinkPathError::CurrentFilePathNoParent)?;
             let result = diff_paths(target_file_path, current_file_parent_dir).ok_or(FormatLinkPathError::CouldNotConstructLink)?;
-            Ok(result.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string())
+            result.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string()
         }
-        Note::Scratch(ScratchNote { buffer: _ }) => Ok(target_file_path.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string()),
+        (LinkStyle::Relative, Note::Scratch(ScratchNote { buffer: _ }), None) => {
+            target_file_path.to_str().ok_or(FormatLinkPathError::PathNotUtf8)?.to_string()
+        }
+    };
+    match anchor {
+        Some(anchor) => Ok(format!("{path}#{anchor}")),
+        None => Ok(path),
     }
 }
 
+/// Formats a whole link to the target file with the given text, according to the configured [`LinkStyle`].
+pub fn format_link(
+    config: &Config,
+    current_note: &Note,
+    target_file_path: &Path,
+    text: &str,
+    anchor: Option<&str>,
+) -> Result<String, FormatLinkPathError> {
+    let path = format_link_path(config, current_note, target_file_path, anchor)?;
+    match config.link_style {
+        LinkStyle::Wikilink if text.is_empty() || text == path => Ok(format!("[[{path}]]")),
+        LinkStyle::Wikilink => Ok(format!("[[{path}|{text}]]")),
+        _ => Ok(format!("[{text}]({path})")),
+    }
+}
+
+/// Resolves the file that a link points to. Heading anchors are ignored; use [`split_anchor`] to get them.
+///
+/// If `resolve_links_by_id` is enabled (or the link style only writes ids) and the link does not point to an existing file, a link that is
+/// just a note id (like `20240101123000` or `20240101123000.md`) resolves to the note with that id in whatever directory it is in.
+///
+/// If the link still does not point to an existing file, it is looked up by name with [`find_notes_by_name`], so `[[Some Title]]` works. If
+/// more than one note has that name, the first one is used. Failing that, it is looked up in the table of paths that notes had before
+/// they were given ids.
+///
+/// A link that starts with the name of a configured wiki and a `:` (like `work:projects/foo.md`) points into that wiki.
 pub fn resolve_link_path(config: &Config, current_note: &Note, link_path_text: &str) -> Result<PathBuf, ResolveLinkPathError> {
+    // interwiki links are resolved from the home directory of the other wiki, with its settings
+    if let Some((wiki, path_in_wiki)) = split_interwiki(config, link_path_text) {
+        let (path_in_wiki, _) = split_anchor(path_in_wiki);
+        let resolved = normalize_path(&wiki.home_path.join(path_in_wiki.trim_start_matches('/')));
+        return Ok(resolve_missing_link_path(wiki, resolved, path_in_wiki));
+    }
+    let resolved = resolve_link_path_relative(config, current_note, link_path_text)?;
+    let (link_path_text, _) = split_anchor(link_path_text);
+    Ok(resolve_missing_link_path(config, resolved, link_path_text))
+}
+
+/// The fallbacks for when a link does not point to an existing file, described in [`resolve_link_path`].
+fn resolve_missing_link_path(config: &Config, resolved: PathBuf, link_path_text: &str) -> PathBuf {
+    if wiki_file_exists(config, &resolved) {
+        return resolved;
+    }
+    if config.resolve_links_by_id || config.link_style.links_by_id() {
+        if let Some(by_id) = find_note_by_id(config, link_path_text.strip_suffix(".md").unwrap_or(link_path_text)) {
+            return by_id;
+        }
+    }
+    if let Some(by_name) = find_notes_by_name(config, link_path_text).into_iter().next() {
+        return by_name;
+    }
+    if config.resolve_links_by_remap {
+        if let Some(remapped) = find_remapped_note(config, &resolved, link_path_text) {
+            return remapped;
+        }
+    }
+    resolved
+}
+
+/// Looks a link up in the table of where notes were before `remap_note_ids` gave them ids, so that links in imported notes keep working
+/// until they are updated. Links written by other tools often have their spaces percent encoded. The table is only read once per config,
+/// since reading it can mean decrypting it.
+fn find_remapped_note(config: &Config, resolved: &Path, link_path_text: &str) -> Option<PathBuf> {
+    let table = config
+        .remap_table
+        .get_or_init(|| {
+            let contents = state::read_state_file(config, remap::REMAP_STATE_FILE).ok()??;
+            Some(remap::RemapTable::parse(&String::from_utf8(contents).ok()?))
+        })
+        .as_ref()?;
+    let relative = resolved.strip_prefix(&config.home_path).ok().and_then(Path::to_str).map(|relative| relative.replace("%20", " "));
+    let new_path = relative.as_deref().and_then(|relative| table.lookup(relative)).or_else(|| table.lookup(&link_path_text.replace("%20", " ")))?;
+    Some(config.home_path.join(new_path))
+}
+
+fn resolve_link_path_relative(config: &Config, current_note: &Note, link_path_text: &str) -> Result<PathBuf, ResolveLinkPathError> {
+    let (link_path_text, _) = split_anchor(link_path_text);
     let link_path = Path::new(link_path_text);
+    // absolute paths that are not inside the wiki are home-relative links
+    if link_path.is_absolute() && !link_path.starts_with(&config.home_path) {
+        return Ok(normalize_path(&config.home_path.join(link_path.strip_prefix("/").unwrap_or(link_path))));
+    }
     match current_note {
+        // a link that is only an anchor points to a heading in the current note
+        Note::Physical(pn) if link_path_text.is_empty() => Ok(pn.path(config)),
+        Note::Scratch(_) if link_path_text.is_empty() => Err(ResolveLinkPathError::AnchorOnlyLinkInScratchNote),
         Note::Physical(pn @ PhysicalNote { directories: _, id: _ }) => {
-            Ok(pn.path(config).parent().ok_or(ResolveLinkPathError::CurrentNoteNoParent)?.join(link_path))
+            Ok(normalize_path(&pn.path(config).parent().ok_or(ResolveLinkPathError::CurrentNoteNoParent)?.join(link_path)))
         }
         Note::Scratch(ScratchNote { buffer: _ }) => {
             // if this is a scratch buffer, there is no current path
             // so we open the target directory if it is absolute, and if not, make it absolute by prepending the config home directory
             if link_path.is_absolute() {
-                Ok(link_path.to_path_buf())
+                Ok(normalize_path(link_path))
             } else {
-                Ok(config.home_path.join(link_path))
+                Ok(normalize_path(&config.home_path.join(link_path)))
+            }
+        }
+    }
+}
+
+fn find_note_by_id(config: &Config, id: &str) -> Option<PathBuf> {
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    let pattern = format!("{}/**/{}.md", glob::Pattern::escape(config.home_path.to_str()?), glob::Pattern::escape(id));
+    glob::glob(&pattern).ok()?.filter_map(Result::ok).min()
+}
+
+/// Finds every note whose title (if `resolve_links_by_title` is enabled) or one of whose `aliases` (if `resolve_links_by_alias` is enabled)
+/// is the link text. The anchor should already be split off of the link.
+pub fn find_notes_by_name(config: &Config, link_path_text: &str) -> Vec<PathBuf> {
+    if !(config.resolve_links_by_title || config.resolve_links_by_alias) || link_path_text.is_empty() || link_path_text.contains('/') {
+        return Vec::new();
+    }
+    let Ok(notes) = list_all_physical_notes(config) else { return Vec::new() };
+    notes
+        .into_iter()
+        .filter_map(|note| {
+            let contents = note.read_contents(config).ok()?;
+            let frontmatter = markdown::parse_frontmatter(&markdown::parse_markdown(&contents).ok()?).ok()?;
+            let title = frontmatter.title.filter(|_| config.resolve_links_by_title);
+            let aliases = if config.resolve_links_by_alias { frontmatter.aliases } else { Vec::new() };
+            title.iter().chain(&aliases).any(|name| name_matches(name, link_path_text)).then(|| note.path(config))
+        })
+        .collect()
+}
+
+/// Titles and aliases are names that people type, so they match without caring about case, a `.md` on the end of the link, or whether the
+/// link was written slugified (`my-note-title` for `My Note Title`).
+fn name_matches(name: &str, link_path_text: &str) -> bool {
+    let link_path_text = link_path_text.strip_suffix(".md").unwrap_or(link_path_text);
+    name.trim().to_lowercase() == link_path_text.trim().to_lowercase() || markdown::slugify(name) == markdown::slugify(link_path_text)
+}
+
+/// Removes `.` and `..` components without touching the filesystem, so that resolved links can be compared against note paths.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
             }
+            component => result.push(component),
         }
     }
+    result
 }
 
 #[cfg(test)]
@@ -84,11 +309,12 @@ mod tests {
             note_id_timestamp_format: "%Y%m%d%H%M%S".to_string(),
             date_format: "%Y-%m-%d".to_string(),
             time_format: "%H:%M:%S".to_string(),
+            ..Default::default()
         };
         let current_note = Note::new_physical(vec![], "start".to_string());
         let target_note = &PathBuf::from("/path/to/wiki/end.md");
 
-        assert_eq!(format_link_path(&config, &current_note, target_note).unwrap(), "end.md");
+        assert_eq!(format_link_path(&config, &current_note, target_note, None).unwrap(), "end.md");
     }
     #[test]
     fn format_link_path_to_rel_test() {
@@ -97,11 +323,12 @@ mod tests {
             note_id_timestamp_format: "%Y%m%d%H%M%S".to_string(),
             date_format: "%Y-%m-%d".to_string(),
             time_format: "%H:%M:%S".to_string(),
+            ..Default::default()
         };
         let current_note = Note::new_physical(vec![], "start".to_string());
         let target_path = Path::new("end.md");
 
-        format_link_path(&config, &current_note, target_path).unwrap_err();
+        format_link_path(&config, &current_note, target_path, None).unwrap_err();
     }
 
     #[test]
@@ -111,11 +338,12 @@ mod tests {
             note_id_timestamp_format: "%Y%m%d%H%M%S".to_string(),
             date_format: "%Y-%m-%d".to_string(),
             time_format: "%H:%M:%S".to_string(),
+            ..Default::default()
         };
         let current_note = Note::new_physical(vec!["dir".to_string()], "start".to_string());
         let target_path = Path::new("/path/to/wiki/dir/dir2/end.md");
 
-        assert_eq!(format_link_path(&config, &current_note, target_path).unwrap(), "dir2/end.md");
+        assert_eq!(format_link_path(&config, &current_note, target_path, None).unwrap(), "dir2/end.md");
     }
     #[test]
     fn format_link_target_same_directory_test() {
@@ -124,11 +352,12 @@ mod tests {
             note_id_timestamp_format: "%Y%m%d%H%M%S".to_string(),
             date_format: "%Y-%m-%d".to_string(),
             time_format: "%H:%M:%S".to_string(),
+            ..Default::default()
         };
         let current_note = Note::new_physical(vec!["dir".to_string(), "dir2".to_string()], "start".to_string());
         let target_path = Path::new("/path/to/wiki/dir/dir2/end.md");
 
-        assert_eq!(format_link_path(&config, &current_note, target_path).unwrap(), "end.md");
+        assert_eq!(format_link_path(&config, &current_note, target_path, None).unwrap(), "end.md");
     }
     #[test]
     fn format_link_target_less_nested_test() {
@@ -137,10 +366,144 @@ mod tests {
             note_id_timestamp_format: "%Y%m%d%H%M%S".to_string(),
             date_format: "%Y-%m-%d".to_string(),
             time_format: "%H:%M:%S".to_string(),
+            ..Default::default()
         };
         let current_note = Note::new_physical(vec!["dir".to_string(), "dir2".to_string()], "start".to_string());
         let target_path = Path::new("/path/to/wiki/dir/end.md");
 
-        assert_eq!(format_link_path(&config, &current_note, target_path).unwrap(), "../end.md");
+        assert_eq!(format_link_path(&config, &current_note, target_path, None).unwrap(), "../end.md");
+    }
+
+    #[test]
+    fn format_link_with_anchor_test() {
+        let config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec![], "start".to_string());
+        let target_path = Path::new("/path/to/wiki/end.md");
+
+        assert_eq!(format_link_path(&config, &current_note, target_path, Some("some-heading")).unwrap(), "end.md#some-heading");
+    }
+
+    #[test]
+    fn format_link_path_styles_test() {
+        let mut config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec!["dir".to_string()], "start".to_string());
+        let target_path = Path::new("/path/to/wiki/dir2/end.md");
+
+        config.link_style = LinkStyle::Absolute;
+        assert_eq!(format_link_path(&config, &current_note, target_path, None).unwrap(), "/path/to/wiki/dir2/end.md");
+        config.link_style = LinkStyle::HomeRelative;
+        assert_eq!(format_link_path(&config, &current_note, target_path, None).unwrap(), "/dir2/end.md");
+        format_link_path(&config, &current_note, Path::new("/somewhere/else.md"), None).unwrap_err();
+        config.link_style = LinkStyle::IdOnly;
+        assert_eq!(format_link_path(&config, &current_note, target_path, Some("heading")).unwrap(), "end#heading");
+    }
+
+    #[test]
+    fn format_link_test() {
+        let mut config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec![], "start".to_string());
+        let target_path = Path::new("/path/to/wiki/end.md");
+
+        assert_eq!(format_link(&config, &current_note, target_path, "the end", None).unwrap(), "[the end](end.md)");
+        config.link_style = LinkStyle::Wikilink;
+        assert_eq!(format_link(&config, &current_note, target_path, "the end", None).unwrap(), "[[end|the end]]");
+        assert_eq!(format_link(&config, &current_note, target_path, "end", None).unwrap(), "[[end]]");
+    }
+
+    #[test]
+    fn resolve_home_relative_link_test() {
+        let config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec!["dir".to_string()], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "/dir2/end.md").unwrap(), Path::new("/path/to/wiki/dir2/end.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "/path/to/wiki/dir2/end.md").unwrap(), Path::new("/path/to/wiki/dir2/end.md"));
+    }
+
+    #[test]
+    fn resolve_link_with_anchor_test() {
+        let config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec!["dir".to_string()], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "end.md#some-heading").unwrap(), Path::new("/path/to/wiki/dir/end.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "#some-heading").unwrap(), Path::new("/path/to/wiki/dir/start.md"));
+    }
+
+    #[test]
+    fn resolve_link_less_nested_test() {
+        let config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec!["dir".to_string(), "dir2".to_string()], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "../end.md").unwrap(), Path::new("/path/to/wiki/dir/end.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "./end.md").unwrap(), Path::new("/path/to/wiki/dir/dir2/end.md"));
+    }
+
+    #[test]
+    fn resolve_link_by_id_test() {
+        let home_path = std::env::temp_dir().join("wikiplugin_resolve_link_by_id_test");
+        std::fs::create_dir_all(home_path.join("dir")).unwrap();
+        std::fs::write(home_path.join("dir/20240101123000.md"), "").unwrap();
+
+        let mut config = Config::new(home_path.clone(), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec![], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "20240101123000").unwrap(), home_path.join("20240101123000"));
+
+        config.resolve_links_by_id = true;
+        assert_eq!(resolve_link_path(&config, &current_note, "20240101123000").unwrap(), home_path.join("dir/20240101123000.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "20240101123000.md#heading").unwrap(), home_path.join("dir/20240101123000.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "nonexistent").unwrap(), home_path.join("nonexistent"));
+
+        std::fs::remove_dir_all(home_path).unwrap();
+    }
+
+    #[test]
+    fn resolve_link_by_alias_test() {
+        let home_path = std::env::temp_dir().join("wikiplugin_resolve_link_by_alias_test");
+        std::fs::create_dir_all(home_path.join("dir")).unwrap();
+        std::fs::write(home_path.join("dir/note.md"), "---\naliases: [Some Alias]\n---\n").unwrap();
+
+        let mut config = Config::new(home_path.clone(), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let current_note = Note::new_physical(vec![], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "Some Alias").unwrap(), home_path.join("Some Alias"));
+
+        config.resolve_links_by_alias = true;
+        assert_eq!(resolve_link_path(&config, &current_note, "Some Alias").unwrap(), home_path.join("dir/note.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "some alias.md#heading").unwrap(), home_path.join("dir/note.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "Other Alias").unwrap(), home_path.join("Other Alias"));
+
+        std::fs::remove_dir_all(home_path).unwrap();
+    }
+
+    #[test]
+    fn interwiki_link_test() {
+        let mut config = Config::new(PathBuf::from("/path/to/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let work = Config::new(PathBuf::from("/path/to/work"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        config.wikis.insert("work".to_string(), work);
+        let current_note = Note::new_physical(vec!["dir".to_string()], "start".to_string());
+
+        assert_eq!(resolve_link_path(&config, &current_note, "work:projects/foo.md#heading").unwrap(), Path::new("/path/to/work/projects/foo.md"));
+        assert_eq!(resolve_link_path(&config, &current_note, "work:/projects/foo.md").unwrap(), Path::new("/path/to/work/projects/foo.md"));
+        assert_eq!(format_link_path(&config, &current_note, Path::new("/path/to/work/projects/foo.md"), None).unwrap(), "work:projects/foo.md");
+        assert_eq!(format_link_path(&config, &current_note, Path::new("/path/to/wiki/end.md"), None).unwrap(), "../end.md");
+        assert!(!is_external_link(&config, "work:projects/foo.md"));
+        assert!(is_external_link(&config, "https://example.com"));
+    }
+
+    #[test]
+    fn name_matches_test() {
+        assert!(name_matches("My Note Title", "my note title"));
+        assert!(name_matches("My Note Title", "My Note Title.md"));
+        assert!(name_matches("My Note Title", "my-note-title"));
+        assert!(!name_matches("My Note Title", "my note"));
+    }
+
+    #[test]
+    fn is_external_url_test() {
+        assert!(is_external_url("https://example.com"));
+        assert!(is_external_url("mailto:someone@example.com"));
+        assert!(!is_external_url("dir/note.md"));
+        assert!(!is_external_url("/path/to/wiki/note.md"));
+        assert!(!is_external_url("C:/wiki/note.md"));
     }
 }
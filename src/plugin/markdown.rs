@@ -1,7 +1,9 @@
+use std::collections::BTreeMap;
+
 use markdown::{mdast, to_mdast};
 use yaml_rust::Yaml;
 
-use crate::plugin::{note::Tag, Config};
+use crate::plugin::{note::Tag, relations, Config};
 
 #[derive(Debug)]
 pub struct MdParseError(markdown::message::Message);
@@ -24,110 +26,405 @@ error_union! {
     pub enum InvalidFrontmatter {
         NoFrontmatter(NoFrontmatter),
         YamlScanError(yaml_rust::ScanError),
+        TomlError(toml::de::Error),
+        FrontmatterFieldError(FrontmatterFieldError),
     }
 }
 
 pub fn parse_markdown(contents: &str) -> Result<mdast::Node, MdParseError> {
-    to_mdast(
+    let mut md = to_mdast(
         contents,
         &markdown::ParseOptions {
             constructs: markdown::Constructs { frontmatter: true, ..markdown::Constructs::gfm() },
             ..markdown::ParseOptions::gfm()
         },
     )
+    .map_err(MdParseError)?;
+
+    // the markdown parser only knows about yaml and toml frontmatter, so json frontmatter comes out as paragraphs, which are replaced
+    // with a yaml node here (json is also valid yaml, so the yaml parser can read it)
+    if let (Some(json_end), mdast::Node::Root(root)) = (json_frontmatter_end(contents), &mut md) {
+        let covered = root.children.iter().take_while(|node| node.position().is_some_and(|position| position.start.offset < json_end.offset)).count();
+        let fits = covered > 0 && root.children[covered - 1].position().is_some_and(|position| position.end.offset <= json_end.offset);
+        if fits {
+            root.children.splice(
+                ..covered,
+                [mdast::Node::Yaml(mdast::Yaml {
+                    value: contents[..json_end.offset].to_string(),
+                    position: Some(markdown::unist::Position { start: markdown::unist::Point::new(1, 1, 0), end: json_end }),
+                })],
+            );
+        }
+    }
+
+    Ok(md)
+}
+
+/// Json frontmatter starts with a line that is just `{` and ends with a line that is just `}`. Returns the point right after the `}`.
+fn json_frontmatter_end(contents: &str) -> Option<markdown::unist::Point> {
+    if contents.lines().next()?.trim_end() != "{" {
+        return None;
+    }
+    let mut line_start = 0;
+    for (line_index, line) in contents.split_inclusive('\n').enumerate() {
+        if line.trim_end() == "}" {
+            return Some(markdown::unist::Point::new(line_index + 1, 2, line_start + 1));
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+pub fn render_html(contents: &str) -> Result<String, MdParseError> {
+    // json frontmatter is not part of the markdown syntax, so it has to be cut off here
+    let contents = &contents[json_frontmatter_end(contents).map_or(0, |end| end.offset)..];
+    markdown::to_html_with_options(
+        contents,
+        &markdown::Options {
+            parse: markdown::ParseOptions {
+                constructs: markdown::Constructs { frontmatter: true, ..markdown::Constructs::gfm() },
+                ..markdown::ParseOptions::gfm()
+            },
+            ..markdown::Options::gfm()
+        },
+    )
     .map_err(MdParseError)
 }
-pub fn find_frontmatter(md: &mdast::Node) -> Result<String, NoFrontmatter> {
+/// The text inside the frontmatter delimiters. Json frontmatter is counted as yaml.
+pub enum RawFrontmatter {
+    Yaml(String),
+    Toml(String),
+}
+
+pub fn find_frontmatter(md: &mdast::Node) -> Result<RawFrontmatter, NoFrontmatter> {
     Ok(rec_find_preorder(md, &mut |node| match node {
-        mdast::Node::Yaml(yaml) => Some(yaml.value.clone()),
+        mdast::Node::Yaml(yaml) => Some(RawFrontmatter::Yaml(yaml.value.clone())),
+        mdast::Node::Toml(toml) => Some(RawFrontmatter::Toml(toml.value.clone())),
         _ => None,
     })
     .ok_or(NoFrontmatter)?
     .1)
 }
 
-pub fn parse_frontmatter(md: &mdast::Node) -> Result<Yaml, InvalidFrontmatter> {
-    // TODO: swap_remove will panic if the yaml parser does not output any documents (i am not sure how that will happen though)
-    Ok(yaml_rust::YamlLoader::load_from_str(&find_frontmatter(md)?)?.swap_remove(0))
+pub fn parse_frontmatter(md: &mdast::Node) -> Result<Frontmatter, InvalidFrontmatter> {
+    match find_frontmatter(md)? {
+        // a frontmatter block with nothing (or only comments) in it has no documents
+        RawFrontmatter::Yaml(yaml) => match yaml_rust::YamlLoader::load_from_str(&yaml)?.into_iter().next() {
+            Some(yaml) => Ok(Frontmatter::from_yaml(yaml)?),
+            None => Ok(Frontmatter::default()),
+        },
+        RawFrontmatter::Toml(toml) => Ok(Frontmatter::from_yaml(toml_to_yaml(toml::Value::Table(toml.parse()?)))?),
+    }
+}
+
+/// Converts toml into the same representation as yaml frontmatter so that the rest of the plugin does not have to care which one a note
+/// uses. Dates and times become strings, like they would be in yaml.
+pub fn toml_to_yaml(toml: toml::Value) -> Yaml {
+    match toml {
+        toml::Value::String(s) => Yaml::String(s),
+        toml::Value::Integer(i) => Yaml::Integer(i),
+        toml::Value::Float(f) => Yaml::Real(f.to_string()),
+        toml::Value::Boolean(b) => Yaml::Boolean(b),
+        toml::Value::Datetime(datetime) => Yaml::String(datetime.to_string()),
+        toml::Value::Array(items) => Yaml::Array(items.into_iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(table) => Yaml::Hash(table.into_iter().map(|(key, value)| (Yaml::String(key), toml_to_yaml(value))).collect()),
+    }
 }
 
 #[derive(Debug)]
-pub enum GetFrontmatterFieldError {
+pub enum FrontmatterFieldError {
     NotHashTable,
-    NoField(&'static str),
-    FieldWrongType { expected_type: &'static str },
+    FieldWrongType { field: String, expected_type: &'static str },
 }
-impl std::fmt::Display for GetFrontmatterFieldError {
+impl std::fmt::Display for FrontmatterFieldError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            GetFrontmatterFieldError::NotHashTable => write!(f, "frontmatter is not hash table"),
-            GetFrontmatterFieldError::NoField(field) => write!(f, "no field called '{field}'"),
-            GetFrontmatterFieldError::FieldWrongType { expected_type } => write!(f, "frontmatter field type is not {expected_type}"),
+            FrontmatterFieldError::NotHashTable => write!(f, "frontmatter is not hash table"),
+            FrontmatterFieldError::FieldWrongType { field, expected_type } => write!(f, "frontmatter field '{field}' is not {expected_type}"),
+        }
+    }
+}
+impl std::error::Error for FrontmatterFieldError {}
+
+/// The frontmatter of a note, with the fields that the plugin knows about already pulled out of the yaml.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// in the configured date format
+    pub date: Option<String>,
+    /// in the configured time format
+    pub time: Option<String>,
+    pub tags: Vec<Tag>,
+    pub aliases: Vec<String>,
+    /// every other field, as it was written
+    pub custom: BTreeMap<String, Yaml>,
+}
+
+impl Frontmatter {
+    /// Fields that are missing or empty are left as `None` or empty. Numbers and booleans are accepted where text is expected, since
+    /// something like `title: 2024` is still meant to be text.
+    pub fn from_yaml(yaml: Yaml) -> Result<Frontmatter, FrontmatterFieldError> {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            Yaml::Null => return Ok(Frontmatter::default()),
+            _ => return Err(FrontmatterFieldError::NotHashTable),
+        };
+
+        let mut frontmatter = Frontmatter::default();
+        for (key, value) in hash {
+            let Some(key) = scalar_to_string(&key) else { continue };
+            let wrong_type = |expected_type| FrontmatterFieldError::FieldWrongType { field: key.clone(), expected_type };
+            match key.as_str() {
+                "title" => frontmatter.title = optional_scalar(&value).ok_or_else(|| wrong_type("string"))?,
+                "author" => frontmatter.author = optional_scalar(&value).ok_or_else(|| wrong_type("string"))?,
+                "date" => frontmatter.date = optional_scalar(&value).ok_or_else(|| wrong_type("string"))?,
+                "time" => frontmatter.time = optional_scalar(&value).ok_or_else(|| wrong_type("string"))?,
+                "tags" => {
+                    frontmatter.tags = match &value {
+                        Yaml::String(tags) => tags.split(' ').filter(|tag| !tag.is_empty()).map(Tag::parse_from_str).collect(),
+                        _ => {
+                            let tags = scalar_list(&value).ok_or_else(|| wrong_type("array of strings or string"))?;
+                            tags.iter().map(|tag| Tag::parse_from_str(tag)).collect()
+                        }
+                    }
+                }
+                // unlike tags, a single alias can have spaces in it
+                "aliases" => frontmatter.aliases = scalar_list(&value).ok_or_else(|| wrong_type("array of strings or string"))?,
+                _ => {
+                    frontmatter.custom.insert(key, value);
+                }
+            }
+        }
+        Ok(frontmatter)
+    }
+
+    /// Any field that has a single value, as text, whether the plugin knows about it or not.
+    pub fn field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "title" => self.title.clone(),
+            "author" => self.author.clone(),
+            "date" => self.date.clone(),
+            "time" => self.time.clone(),
+            _ => self.custom.get(field).and_then(scalar_to_string),
+        }
+    }
+
+    pub fn custom_str(&self, field: &str) -> Option<&str> {
+        self.custom.get(field).and_then(Yaml::as_str)
+    }
+
+    pub fn kind(&self) -> Option<&str> {
+        self.custom_str("kind")
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.custom_str("status")
+    }
+
+    /// Who the note is for, which export profiles use to decide whether to include it. Can be written as one audience or a list.
+    pub fn audience(&self) -> Vec<String> {
+        self.custom.get("audience").and_then(scalar_list).unwrap_or_default()
+    }
+
+    pub fn publish(&self) -> Option<bool> {
+        self.custom.get("publish").and_then(Yaml::as_bool)
+    }
+
+    /// Whether the note's autogenerated sections should be regenerated when it is saved, if it says.
+    pub fn regenerate_on_save(&self) -> Option<bool> {
+        self.custom.get("regenerate_on_save").and_then(Yaml::as_bool)
+    }
+
+    pub fn timestamp(&self, config: &Config) -> Result<chrono::NaiveDateTime, GetTimestampError> {
+        let date = self.date.as_ref().ok_or(GetTimestampError::NoDateField)?;
+        let date = chrono::NaiveDate::parse_from_str(date, &config.date_format).map_err(GetTimestampError::TimestampParseError)?;
+        let time = match &self.time {
+            Some(time) => chrono::NaiveTime::parse_from_str(time, &config.time_format).map_err(GetTimestampError::TimestampParseError)?,
+            None => chrono::NaiveTime::MIN,
+        };
+        Ok(chrono::NaiveDateTime::new(date, time))
+    }
+
+    /// Gets the `remind` field, which is a date in the configured date format, optionally followed by a space and a time in the configured
+    /// time format.
+    pub fn remind(&self, config: &Config) -> Result<chrono::NaiveDateTime, GetTimestampError> {
+        let remind = match self.custom.get("remind") {
+            None | Some(Yaml::Null) => return Err(GetTimestampError::NoRemindField),
+            Some(remind) => remind.as_str().ok_or(GetTimestampError::TimestampFieldsNotString)?,
+        };
+
+        match chrono::NaiveDateTime::parse_from_str(remind, &format!("{} {}", config.date_format, config.time_format)) {
+            Ok(remind) => Ok(remind),
+            Err(_) => Ok(chrono::NaiveDate::parse_from_str(remind, &config.date_format)
+                .map_err(GetTimestampError::TimestampParseError)?
+                .and_time(chrono::NaiveTime::MIN)),
         }
     }
 }
-pub fn get_title(frontmatter: &Yaml) -> Result<String, GetFrontmatterFieldError> {
-    Ok(frontmatter
-        .as_hash()
-        .ok_or(GetFrontmatterFieldError::NotHashTable)?
-        .get(&Yaml::String("title".to_string()))
-        .ok_or(GetFrontmatterFieldError::NoField("title"))?
-        .as_str()
-        .ok_or(GetFrontmatterFieldError::FieldWrongType { expected_type: "string" })?
-        .to_string())
+
+fn scalar_to_string(yaml: &Yaml) -> Option<String> {
+    match yaml {
+        Yaml::String(s) | Yaml::Real(s) => Some(s.clone()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// `Some(None)` for an empty field, `None` if the field is not a scalar.
+fn optional_scalar(yaml: &Yaml) -> Option<Option<String>> {
+    match yaml {
+        Yaml::Null => Some(None),
+        _ => scalar_to_string(yaml).map(Some),
+    }
+}
+
+/// A list of scalars, or a single scalar as a list of one.
+fn scalar_list(yaml: &Yaml) -> Option<Vec<String>> {
+    match yaml {
+        Yaml::Null => Some(Vec::new()),
+        Yaml::Array(items) => items.iter().map(scalar_to_string).collect(),
+        _ => Some(vec![scalar_to_string(yaml)?]),
+    }
 }
 
 #[derive(Debug)]
 pub enum GetTimestampError {
-    NotHashTable,
     NoDateField,
+    NoRemindField,
     TimestampFieldsNotString,
     TimestampParseError(chrono::ParseError),
 }
 impl std::fmt::Display for GetTimestampError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            GetTimestampError::NotHashTable => write!(f, "frontmatter is not hash table"),
             GetTimestampError::NoDateField => write!(f, "no date field"),
+            GetTimestampError::NoRemindField => write!(f, "no remind field"),
             GetTimestampError::TimestampFieldsNotString => write!(f, "timestamp fields are not a string"),
             GetTimestampError::TimestampParseError(e) => e.fmt(f),
         }
     }
 }
-pub fn get_timestamp(frontmatter: &Yaml, config: &Config) -> Result<chrono::NaiveDateTime, GetTimestampError> {
-    let frontmatter = frontmatter.as_hash().ok_or(GetTimestampError::NotHashTable)?;
-    let date = frontmatter
-        .get(&Yaml::String("date".to_string()))
-        .ok_or(GetTimestampError::NoDateField)?
-        .as_str()
-        .ok_or(GetTimestampError::TimestampFieldsNotString)?
-        .to_string();
-    let time = frontmatter.get(&Yaml::String("time".to_string()));
 
-    let date = chrono::NaiveDate::parse_from_str(&date, &config.date_format).map_err(GetTimestampError::TimestampParseError)?;
-    let time = match time {
-        Some(time) => chrono::NaiveTime::parse_from_str(time.as_str().ok_or(GetTimestampError::TimestampFieldsNotString)?, &config.time_format)
-            .map_err(GetTimestampError::TimestampParseError)?,
-        None => chrono::NaiveTime::MIN,
-    };
+/// A `[[target]]` or `[[target|text]]` link. These are not part of commonmark, so they show up as plain text in the mdast.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Wikilink {
+    pub target: String,
+    pub text: Option<String>,
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+pub fn get_all_wikilinks(md: &mdast::Node) -> Vec<Wikilink> {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Text(mdast::Text { value, position: Some(position) }) => Some((value.clone(), position.start.offset)),
+        _ => None,
+    })
+    .into_iter()
+    .flat_map(|(value, node_offset)| {
+        let mut wikilinks = Vec::new();
+        let mut search_from = 0;
+        while let Some(start) = value[search_from..].find("[[").map(|i| i + search_from) {
+            let Some(end) = value[start + 2..].find("]]").map(|i| i + start + 2) else {
+                break;
+            };
+            let inner = &value[start + 2..end];
+            if !inner.is_empty() && !inner.contains('\n') {
+                let (target, text) = match inner.split_once('|') {
+                    Some((target, text)) => (target, Some(text.to_string())),
+                    None => (inner, None),
+                };
+                wikilinks.push(Wikilink { target: target.to_string(), text, start_offset: node_offset + start, end_offset: node_offset + end + 2 });
+            }
+            search_from = end + 2;
+        }
+        wikilinks
+    })
+    .collect()
+}
+
+/// Returns the targets of every normal link and wikilink in the document.
+pub fn get_all_link_targets(md: &mdast::Node) -> Vec<String> {
+    get_all_link_targets_with_offsets(md).into_iter().map(|(target, _)| target).collect()
+}
 
-    Ok(chrono::NaiveDateTime::new(date, time))
+/// Like [`get_all_link_targets`], but also returns the byte offset of the start of each link.
+pub fn get_all_link_targets_with_offsets(md: &mdast::Node) -> Vec<(String, usize)> {
+    get_all_links(md)
+        .into_iter()
+        .filter_map(|link| Some((link.url.clone(), link.position.as_ref()?.start.offset)))
+        .chain(get_all_wikilinks(md).into_iter().map(|wikilink| (wikilink.target, wikilink.start_offset)))
+        .collect()
 }
 
-pub fn get_tags(frontmatter: &Yaml) -> Result<Vec<Tag>, GetFrontmatterFieldError> {
-    let s = frontmatter
-        .as_hash()
-        .ok_or(GetFrontmatterFieldError::NotHashTable)?
-        .get(&Yaml::String("tags".to_string()))
-        .ok_or(GetFrontmatterFieldError::NoField("tags"))?;
-    match s {
-        Yaml::String(s) => Ok(s.split(" ").map(Tag::parse_from_str).collect()),
-        Yaml::Array(vec) => Ok(vec
-            .iter()
-            .map(|tag| Some(Tag::parse_from_str(tag.as_str()?)))
-            .collect::<Option<Vec<_>>>()
-            .ok_or(GetFrontmatterFieldError::FieldWrongType { expected_type: "array of strings (or string)" })?),
-        _ => Err(GetFrontmatterFieldError::FieldWrongType { expected_type: "array of strings or string" }),
+/// A link whose target is qualified with a relation, like `[text](note.md "rel=parent")`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TypedLink {
+    pub target: String,
+    pub relation: String,
+}
+
+/// Returns every normal link and wikilink in the document that has a relation.
+pub fn get_all_typed_links(md: &mdast::Node) -> Vec<TypedLink> {
+    get_all_links(md)
+        .into_iter()
+        .filter_map(|link| {
+            let relation = relations::link_relation(link.title.as_deref(), &nodes_text(&link.children))?;
+            Some(TypedLink { target: link.url.clone(), relation })
+        })
+        .chain(get_all_wikilinks(md).into_iter().filter_map(|wikilink| {
+            let relation = relations::link_relation(None, wikilink.text.as_deref()?)?;
+            Some(TypedLink { target: wikilink.target, relation })
+        }))
+        .collect()
+}
+
+/// A normal link or wikilink, with where it is in the document.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LinkSpan {
+    pub target: String,
+    pub text: String,
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+/// Returns every normal link and wikilink in the document, in the order that they appear.
+pub fn get_all_link_spans(md: &mdast::Node) -> Vec<LinkSpan> {
+    let mut spans: Vec<LinkSpan> = get_all_links(md)
+        .into_iter()
+        .filter_map(|link| {
+            let position = link.position.as_ref()?;
+            Some(LinkSpan {
+                target: link.url.clone(),
+                text: nodes_text(&link.children),
+                start_offset: position.start.offset,
+                end_offset: position.end.offset,
+            })
+        })
+        .chain(get_all_wikilinks(md).into_iter().map(|wikilink| LinkSpan {
+            text: wikilink.text.unwrap_or_else(|| wikilink.target.clone()),
+            target: wikilink.target,
+            start_offset: wikilink.start_offset,
+            end_offset: wikilink.end_offset,
+        }))
+        .collect();
+    spans.sort_by_key(|span| span.start_offset);
+    spans
+}
+
+/// Picks the link that the cursor refers to: the link that the cursor is on if there is one, otherwise the `count`th link on the cursor
+/// line if a count is given (starting at 1), otherwise the link on the cursor line that is closest to the cursor.
+pub fn pick_link(spans: &[LinkSpan], cursor: usize, line: std::ops::Range<usize>, count: usize) -> Option<&LinkSpan> {
+    if let Some(span) = spans.iter().find(|span| (span.start_offset..span.end_offset).contains(&cursor)) {
+        return Some(span);
+    }
+
+    let mut on_line = spans.iter().filter(|span| span.start_offset < line.end && span.end_offset > line.start);
+    if count > 0 {
+        on_line.nth(count - 1)
+    } else {
+        on_line.min_by_key(|span| if cursor < span.start_offset { span.start_offset - cursor } else { cursor + 1 - span.end_offset })
     }
 }
 
@@ -162,6 +459,134 @@ pub fn get_all_links(md: &mdast::Node) -> Vec<&mdast::Link> {
     result
 }
 
+/// Returns the url and source position of every link and image, since both of these can point at other files in the wiki.
+pub fn get_all_link_and_image_urls(md: &mdast::Node) -> Vec<(String, markdown::unist::Position)> {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Link(mdast::Link { url, position: Some(position), .. })
+        | mdast::Node::Image(mdast::Image { url, position: Some(position), .. }) => Some((url.clone(), position.clone())),
+        _ => None,
+    })
+}
+
+/// Returns the url and source position of every link reference definition (`[label]: url`), which is where the target of a reference
+/// style link is written.
+pub fn get_all_definition_urls(md: &mdast::Node) -> Vec<(String, markdown::unist::Position)> {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Definition(mdast::Definition { url, position: Some(position), .. }) => Some((url.clone(), position.clone())),
+        _ => None,
+    })
+}
+
+/// Finds where `target` is written in the source of a link, image, wikilink, or definition. The target comes first in a wikilink, and
+/// after the bracketed text or label in the others, which is skipped since it can contain the target too, as can the title after it.
+pub fn link_target_offset(link: &str, target: &str) -> Option<usize> {
+    if link.starts_with("[[") {
+        return link.find(target);
+    }
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut text_end = None;
+    for (i, c) in link.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    text_end = Some(i + 1);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let text_end = text_end?;
+    Some(text_end + link[text_end..].find(target)?)
+}
+
+/// Returns the url, alt text, and source position of every image.
+pub fn get_all_images(md: &mdast::Node) -> Vec<(String, String, markdown::unist::Position)> {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Image(mdast::Image { url, alt, position: Some(position), .. }) => Some((url.clone(), alt.clone(), position.clone())),
+        _ => None,
+    })
+}
+
+/// Returns the depth, text, and position of every heading in the document.
+pub fn get_headings(md: &mdast::Node) -> Vec<(u8, String, markdown::unist::Position)> {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Heading(mdast::Heading { depth, children, position: Some(position) }) => Some((*depth, nodes_text(children), position.clone())),
+        _ => None,
+    })
+}
+
+/// Converts heading text into the anchor used to link to it, in the same way that github does (`## Some Heading!` becomes `some-heading`).
+/// The level and text of an ATX heading line (`## text`), for code that works on lines instead of parsed markdown. Does not know whether
+/// the line is inside a code block.
+pub fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '))).then_some((level, rest.trim()))
+}
+
+pub fn slugify(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Concatenates all of the text inside the given nodes, which is used to get the text of a link.
+/// Returns up to `count` lines from the start of the body of the note (after the frontmatter), or from the heading that the anchor points
+/// to if there is one. Leading blank lines are skipped.
+pub fn excerpt(contents: &str, md: &mdast::Node, anchor: Option<&str>, count: usize) -> Vec<String> {
+    let frontmatter_end = rec_find_preorder(md, &mut |node| match node {
+        mdast::Node::Yaml(mdast::Yaml { position: Some(position), .. }) | mdast::Node::Toml(mdast::Toml { position: Some(position), .. }) => {
+            Some(position.end.line)
+        }
+        _ => None,
+    });
+    // line numbers in positions start at 1, so the line after the end of the frontmatter has the same index as the end line number
+    let mut start = frontmatter_end.map_or(0, |(_, end_line)| end_line);
+
+    if let Some(anchor) = anchor {
+        let anchor = slugify(anchor);
+        if let Some((_, _, position)) = get_headings(md).into_iter().find(|(_, text, _)| slugify(text) == anchor) {
+            start = position.start.line - 1;
+        }
+    }
+
+    contents.lines().skip(start).skip_while(|line| line.trim().is_empty()).take(count).map(ToString::to_string).collect()
+}
+
+pub fn nodes_text(nodes: &[mdast::Node]) -> String {
+    nodes
+        .iter()
+        .flat_map(|node| {
+            rec_filter_preorder(node, |node| match node {
+                mdast::Node::Text(mdast::Text { value, .. }) | mdast::Node::InlineCode(mdast::InlineCode { value, .. }) => Some(value.clone()),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Returns all of the prose text in the document (no frontmatter, link urls, etc.), one text node per line.
+pub fn plain_text(md: &mdast::Node) -> String {
+    rec_filter_preorder(md, |node| match node {
+        mdast::Node::Text(mdast::Text { value, .. }) => Some(value.clone()),
+        _ => None,
+    })
+    .join("\n")
+}
+
 pub fn rec_filter_preorder<R>(node: &mdast::Node, mut pred: impl for<'a> FnMut(&'a mdast::Node) -> Option<R>) -> Vec<R> {
     fn helper<R>(acc: &mut Vec<R>, pred: &mut impl FnMut(&mdast::Node) -> Option<R>, node: &mdast::Node) {
         if let Some(res) = pred(node) {
@@ -186,3 +611,172 @@ pub fn rec_find_postorder<'md, R>(node: &'md mdast::Node, pred: &mut impl FnMut(
 pub fn point_in_position(position: &markdown::unist::Position, byte_index: usize) -> bool {
     byte_index >= position.start.offset && byte_index < position.end.offset
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_test() {
+        assert_eq!(slugify("Some Heading!"), "some-heading");
+        assert_eq!(slugify("  what's `new` in 2.0?"), "whats-new-in-20");
+        assert_eq!(slugify("snake_case and-dashes"), "snake_case-and-dashes");
+    }
+
+    #[test]
+    fn link_target_offset_test() {
+        assert_eq!(link_target_offset("[a.md](a.md \"see a.md\")", "a.md"), Some(7));
+        assert_eq!(link_target_offset("![img.png](img.png)", "img.png"), Some(11));
+        assert_eq!(link_target_offset("[a [b] c](b)", "b"), Some(10));
+        assert_eq!(link_target_offset("[notes/a.md]: notes/a.md \"notes/a.md\"", "notes/a.md"), Some(14));
+        assert_eq!(link_target_offset("[[a.md|a.md]]", "a.md"), Some(2));
+        assert_eq!(link_target_offset("[a](b.md)", "c.md"), None);
+    }
+
+    #[test]
+    fn excerpt_test() {
+        let contents = "---\ntitle: a\n---\n\nline 1\nline 2\n\n# Some Heading\nheading body\n";
+        let md = parse_markdown(contents).expect("markdown should parse");
+        assert_eq!(excerpt(contents, &md, None, 2), ["line 1", "line 2"]);
+        assert_eq!(excerpt(contents, &md, Some("some-heading"), 2), ["# Some Heading", "heading body"]);
+        assert_eq!(excerpt("no frontmatter", &parse_markdown("no frontmatter").unwrap(), None, 5), ["no frontmatter"]);
+    }
+
+    fn frontmatter(yaml: &str) -> Result<Frontmatter, FrontmatterFieldError> {
+        Frontmatter::from_yaml(yaml_rust::YamlLoader::load_from_str(yaml).expect("yaml should parse").swap_remove(0))
+    }
+
+    #[test]
+    fn frontmatter_fields_test() {
+        let parsed = frontmatter("title: a note\nauthor: someone\ndate: 2024-01-02\ntags: [one, two::three]\naliases: another name\nkind: person\n")
+            .expect("frontmatter should be valid");
+        assert_eq!(parsed.title.as_deref(), Some("a note"));
+        assert_eq!(parsed.author.as_deref(), Some("someone"));
+        assert_eq!(parsed.date.as_deref(), Some("2024-01-02"));
+        assert_eq!(parsed.time, None);
+        assert_eq!(parsed.tags, [Tag::parse_from_str("one"), Tag::parse_from_str("two::three")]);
+        assert_eq!(parsed.aliases, ["another name"]);
+        assert_eq!(parsed.kind(), Some("person"));
+        assert_eq!(parsed.custom.len(), 1);
+        assert_eq!(frontmatter("audience: team").unwrap().audience(), ["team"]);
+        assert_eq!(frontmatter("audience: [team, public]").unwrap().audience(), ["team", "public"]);
+    }
+
+    #[test]
+    fn toml_frontmatter_test() {
+        let md = parse_markdown("+++\ntitle = \"a note\"\ndate = 2024-01-02\ntags = [\"one\", \"two\"]\n+++\nbody").expect("markdown should parse");
+        let parsed = parse_frontmatter(&md).expect("frontmatter should be valid");
+        assert_eq!(parsed.title.as_deref(), Some("a note"));
+        assert_eq!(parsed.date.as_deref(), Some("2024-01-02"));
+        assert_eq!(parsed.tags, [Tag::parse_from_str("one"), Tag::parse_from_str("two")]);
+    }
+
+    #[test]
+    fn json_frontmatter_test() {
+        let contents = "{\n  \"title\": \"a *note*\",\n\n  \"tags\": [\"one\"]\n}\n\nline 1\n";
+        let md = parse_markdown(contents).expect("markdown should parse");
+        let parsed = parse_frontmatter(&md).expect("frontmatter should be valid");
+        assert_eq!(parsed.title.as_deref(), Some("a *note*"));
+        assert_eq!(parsed.tags, [Tag::parse_from_str("one")]);
+        assert_eq!(excerpt(contents, &md, None, 1), ["line 1"]);
+        assert_eq!(plain_text(&md), "line 1");
+    }
+
+    #[test]
+    fn frontmatter_loose_types_test() {
+        let parsed = frontmatter("title: 2024\ntags: one  two\naliases:\n").expect("frontmatter should be valid");
+        assert_eq!(parsed.title.as_deref(), Some("2024"));
+        assert_eq!(parsed.tags, [Tag::parse_from_str("one"), Tag::parse_from_str("two")]);
+        assert!(parsed.aliases.is_empty());
+    }
+
+    #[test]
+    fn frontmatter_wrong_types_test() {
+        assert!(matches!(frontmatter("title: [a, b]"), Err(FrontmatterFieldError::FieldWrongType { field, .. }) if field == "title"));
+        assert!(matches!(frontmatter("tags: {a: b}"), Err(FrontmatterFieldError::FieldWrongType { field, .. }) if field == "tags"));
+        assert!(matches!(frontmatter("- a\n- b"), Err(FrontmatterFieldError::NotHashTable)));
+    }
+
+    #[test]
+    fn frontmatter_timestamp_test() {
+        let config = Config::new(std::path::PathBuf::from("/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let timestamp = |yaml: &str| frontmatter(yaml).expect("frontmatter should be valid").timestamp(&config);
+
+        assert_eq!(timestamp("date: 2024-01-02\ntime: 09:30:00").unwrap().to_string(), "2024-01-02 09:30:00");
+        assert_eq!(timestamp("date: 2024-01-02").unwrap().to_string(), "2024-01-02 00:00:00");
+        timestamp("title: a").unwrap_err();
+    }
+
+    #[test]
+    fn get_remind_test() {
+        let config = Config::new(std::path::PathBuf::from("/wiki"), "%Y%m%d%H%M%S".to_string(), "%Y-%m-%d".to_string(), "%H:%M:%S".to_string());
+        let remind = |yaml: &str| frontmatter(yaml).expect("frontmatter should be valid").remind(&config);
+
+        assert_eq!(remind("remind: 2024-01-02 09:30:00").unwrap().to_string(), "2024-01-02 09:30:00");
+        assert_eq!(remind("remind: 2024-01-02").unwrap().to_string(), "2024-01-02 00:00:00");
+        remind("title: a").unwrap_err();
+    }
+
+    #[test]
+    fn pick_link_test() {
+        let contents = "[a](a.md) then [[b]] and [c](c.md)\nnext line [d](d.md)\n";
+        let spans = get_all_link_spans(&parse_markdown(contents).expect("markdown should parse"));
+        let first_line = 0..contents.find('\n').unwrap();
+        let target = |span: Option<&LinkSpan>| span.map(|span| span.target.clone());
+
+        assert_eq!(spans.iter().map(|span| span.target.as_str()).collect::<Vec<_>>(), ["a.md", "b", "c.md", "d.md"]);
+        // on a link
+        assert_eq!(target(pick_link(&spans, 1, first_line.clone(), 3)), Some("a.md".to_string()));
+        // between links, nearest wins
+        assert_eq!(target(pick_link(&spans, 11, first_line.clone(), 0)), Some("a.md".to_string()));
+        assert_eq!(target(pick_link(&spans, 14, first_line.clone(), 0)), Some("b".to_string()));
+        // with a count
+        assert_eq!(target(pick_link(&spans, 11, first_line.clone(), 3)), Some("c.md".to_string()));
+        assert_eq!(target(pick_link(&spans, 11, first_line, 4)), None);
+    }
+
+    #[test]
+    fn get_all_wikilinks_test() {
+        let md = parse_markdown("see [[note]] and [[other|the other one]]\n\n`[[not a link]]`\n").expect("markdown should parse");
+        assert_eq!(
+            get_all_wikilinks(&md),
+            vec![
+                Wikilink { target: "note".to_string(), text: None, start_offset: 4, end_offset: 12 },
+                Wikilink { target: "other".to_string(), text: Some("the other one".to_string()), start_offset: 17, end_offset: 40 },
+            ]
+        );
+    }
+
+    #[test]
+    fn get_all_typed_links_test() {
+        let md = parse_markdown("[up](a.md \"rel=parent\") [b](b.md) [source::c](c.md) [[d|contradicts::d]] [[e]]\n").expect("markdown should parse");
+        let typed: Vec<_> = get_all_typed_links(&md).into_iter().map(|link| (link.target, link.relation)).collect();
+        assert_eq!(
+            typed,
+            [
+                ("a.md".to_string(), "parent".to_string()),
+                ("c.md".to_string(), "source".to_string()),
+                ("d".to_string(), "contradicts".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn get_all_images_test() {
+        let md = parse_markdown("text ![a diagram](images/diagram.png)\n\n[not an image](note.md)\n").expect("markdown should parse");
+        let images = get_all_images(&md);
+        assert_eq!(images.len(), 1);
+        let (url, alt, position) = &images[0];
+        assert_eq!((url.as_str(), alt.as_str()), ("images/diagram.png", "a diagram"));
+        assert_eq!((position.start.offset, position.end.offset, position.start.line), (5, 37, 1));
+    }
+
+    #[test]
+    fn get_headings_test() {
+        let md = parse_markdown("# One\n\ntext\n\n## Two *emphasis*\n").expect("markdown should parse");
+        assert_eq!(
+            get_headings(&md).into_iter().map(|(depth, text, position)| (depth, text, position.start.line)).collect::<Vec<_>>(),
+            vec![(1, "One".to_string(), 1), (2, "Two emphasis".to_string(), 5)]
+        );
+    }
+}
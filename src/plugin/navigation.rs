@@ -0,0 +1,68 @@
+use std::{cell::RefCell, collections::HashMap, path::PathBuf};
+
+use crate::plugin::Config;
+
+/// The notes visited with `follow_link`, kept separately from the jumplist so that other motions do not get mixed in.
+#[derive(Debug, Default)]
+pub struct NavigationHistory {
+    back: Vec<PathBuf>,
+    forward: Vec<PathBuf>,
+}
+
+impl NavigationHistory {
+    /// Records that a link was followed away from `from`. This discards the forward history, like in a web browser.
+    pub fn visit(&mut self, from: PathBuf) {
+        self.back.push(from);
+        self.forward.clear();
+    }
+
+    pub fn go_back(&mut self, current: Option<PathBuf>) -> Option<PathBuf> {
+        let target = self.back.pop()?;
+        self.forward.extend(current);
+        Some(target)
+    }
+
+    pub fn go_forward(&mut self, current: Option<PathBuf>) -> Option<PathBuf> {
+        let target = self.forward.pop()?;
+        self.back.extend(current);
+        Some(target)
+    }
+}
+
+thread_local! {
+    // neovim only ever calls into the plugin from the main thread, so this does not need to be shared between threads
+    static HISTORIES: RefCell<HashMap<PathBuf, NavigationHistory>> = RefCell::new(HashMap::new());
+}
+
+/// Runs `f` with the navigation history of the wiki, which lasts until neovim exits.
+pub fn with_history<R>(config: &Config, f: impl FnOnce(&mut NavigationHistory) -> R) -> R {
+    HISTORIES.with(|histories| f(histories.borrow_mut().entry(config.home_path.clone()).or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward_test() {
+        let mut history = NavigationHistory::default();
+        history.visit(PathBuf::from("a"));
+        history.visit(PathBuf::from("b"));
+
+        assert_eq!(history.go_back(Some(PathBuf::from("c"))), Some(PathBuf::from("b")));
+        assert_eq!(history.go_back(Some(PathBuf::from("b"))), Some(PathBuf::from("a")));
+        assert_eq!(history.go_back(Some(PathBuf::from("a"))), None);
+        assert_eq!(history.go_forward(Some(PathBuf::from("a"))), Some(PathBuf::from("b")));
+        assert_eq!(history.go_forward(Some(PathBuf::from("b"))), Some(PathBuf::from("c")));
+        assert_eq!(history.go_forward(Some(PathBuf::from("c"))), None);
+    }
+
+    #[test]
+    fn visit_clears_forward_test() {
+        let mut history = NavigationHistory::default();
+        history.visit(PathBuf::from("a"));
+        history.go_back(Some(PathBuf::from("b")));
+        history.visit(PathBuf::from("a"));
+        assert_eq!(history.go_forward(Some(PathBuf::from("d"))), None);
+    }
+}
@@ -62,13 +62,18 @@ error_union! {
 
 impl PhysicalNote {
     pub fn parse_from_filepath(config: &Config, path: &Path) -> Result<PhysicalNote, ParseFromFilepathError> {
+        PhysicalNote::parse_from_filepath_in(&config.home_path, path)
+    }
+
+    /// Like [`PhysicalNote::parse_from_filepath`], for when there is only a home path and not a whole config.
+    pub fn parse_from_filepath_in(home_path: &Path, path: &Path) -> Result<PhysicalNote, ParseFromFilepathError> {
         let path_abs_canon = if path.is_absolute() {
             path.canonicalize().map_err(ParseFromFilepathError::CannotCanonicalize)?
         } else {
-            config.home_path.join(path).canonicalize().map_err(ParseFromFilepathError::CannotCanonicalize)?
+            home_path.join(path).canonicalize().map_err(ParseFromFilepathError::CannotCanonicalize)?
         };
-        let directories_path = if path_abs_canon.starts_with(&config.home_path) {
-            path_abs_canon.strip_prefix(&config.home_path).expect("strip_prefix should return Ok if starts_with returns true")
+        let directories_path = if path_abs_canon.starts_with(home_path) {
+            path_abs_canon.strip_prefix(home_path).expect("strip_prefix should return Ok if starts_with returns true")
         } else {
             Err(ParseFromFilepathError::FileNotWithinWikiDir)?
         };
@@ -103,7 +108,7 @@ impl PhysicalNote {
         if let Some(buffer_contents) = self.read_contents_in_nvim(config)? {
             Ok(buffer_contents)
         } else {
-            Ok(std::fs::read_to_string(self.path(config))?)
+            Ok(config.store.read(self)?)
         }
     }
 
@@ -229,6 +234,16 @@ impl Tag {
     pub fn parse_from_str(s: &str) -> Tag {
         Tag(s.split("::").map(ToString::to_string).collect())
     }
+
+    /// The parts of the tag that are separated by `::`, from the outermost parent to the tag itself.
+    pub fn components(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Whether this tag is `ancestor` or nested somewhere inside it.
+    pub fn is_within(&self, ancestor: &Tag) -> bool {
+        self.0.starts_with(&ancestor.0)
+    }
 }
 impl Display for Tag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -240,6 +255,16 @@ impl Display for Tag {
 mod tests {
     use super::*;
 
+    #[test]
+    fn tag_is_within_test() {
+        let tag = Tag::parse_from_str("project::wiki::links");
+        assert!(tag.is_within(&Tag::parse_from_str("project")));
+        assert!(tag.is_within(&Tag::parse_from_str("project::wiki")));
+        assert!(tag.is_within(&tag));
+        assert!(!tag.is_within(&Tag::parse_from_str("project::wik")));
+        assert!(!Tag::parse_from_str("project").is_within(&tag));
+    }
+
     #[test]
     fn parse_from_filepath_relative_test() {
         let config = Config {
@@ -247,6 +272,7 @@ mod tests {
             note_id_timestamp_format: String::new(),
             date_format: String::new(),
             time_format: String::new(),
+            ..Default::default()
         };
 
         let note_parsed = PhysicalNote::parse_from_filepath(&config, Path::new("dir1/dir2/note.md")).expect("parse from filepath should work");
@@ -260,6 +286,7 @@ mod tests {
             note_id_timestamp_format: String::new(),
             date_format: String::new(),
             time_format: String::new(),
+            ..Default::default()
         };
 
         let note_parsed =
@@ -274,6 +301,7 @@ mod tests {
             note_id_timestamp_format: String::new(),
             date_format: String::new(),
             time_format: String::new(),
+            ..Default::default()
         };
 
         PhysicalNote::parse_from_filepath(&config, Path::new("/some/other/directory/note.md"))
@@ -0,0 +1,145 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// the creation time in `note_id_timestamp_format`
+    Timestamp,
+    /// the title, lowercased with dashes between words
+    Slug,
+    /// a random version 4 UUID
+    Uuid,
+    /// one more than the biggest numeric id in the directory
+    Sequential,
+}
+
+impl IdStrategy {
+    pub fn parse_from_str(s: &str) -> Option<IdStrategy> {
+        match s {
+            "timestamp" => Some(IdStrategy::Timestamp),
+            "slug" => Some(IdStrategy::Slug),
+            "uuid" => Some(IdStrategy::Uuid),
+            "sequential" => Some(IdStrategy::Sequential),
+            _ => None,
+        }
+    }
+}
+
+/// The id that the slug strategy gives a note with this title. Characters that are awkward in file names are dropped, and a title with
+/// nothing left is called `untitled`.
+pub fn slug_id(title: &str) -> String {
+    let slug = title
+        .split_whitespace()
+        .map(|word| word.to_lowercase().chars().filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_').collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Formats 16 random bytes as a version 4 UUID.
+pub fn uuid_id(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// The id after the biggest of `ids` that is a number, padded to the same width as the widest of them so that they keep sorting in order.
+/// Ids that are not numbers are skipped.
+pub fn sequential_id<'ids>(ids: impl IntoIterator<Item = &'ids str>) -> String {
+    let numeric: Vec<(u64, usize)> =
+        ids.into_iter().filter(|id| id.chars().all(|c| c.is_ascii_digit())).filter_map(|id| Some((id.parse().ok()?, id.len()))).collect();
+    let next = numeric.iter().map(|(number, _)| number + 1).max().unwrap_or(1);
+    let width = numeric.iter().map(|(_, width)| *width).max().unwrap_or(0);
+    format!("{next:0width$}")
+}
+
+/// `id` if it is not taken, or otherwise `id` with the first suffix (`-2`, `-3`, ...) that is not.
+pub fn unique_id(id: String, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(&id) {
+        return id;
+    }
+    (2..).map(|suffix| format!("{id}-{suffix}")).find(|suffixed| !is_taken(suffixed)).expect("there are more suffixes than notes")
+}
+
+/// Whether `id` is a timestamp in `format`. Formats with only a date in them count, even though they do not parse as a date and time.
+pub fn is_timestamp_id(id: &str, format: &str) -> bool {
+    let mut parsed = chrono::format::Parsed::new();
+    chrono::format::parse(&mut parsed, id, chrono::format::StrftimeItems::new(format)).is_ok()
+}
+
+fn is_uuid(id: &str) -> bool {
+    let groups: Vec<&str> = id.split('-').collect();
+    groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12]) && groups.iter().all(|group| group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether `strategy` could have given a note this id, including with a suffix from [`unique_id`], so that `remap_note_ids` can leave
+/// the notes that already have one alone.
+pub fn is_id(id: &str, strategy: IdStrategy, timestamp_format: &str) -> bool {
+    let unsuffixed =
+        id.rsplit_once('-').filter(|(_, suffix)| !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit())).map(|(unsuffixed, _)| unsuffixed);
+    std::iter::once(id).chain(unsuffixed).any(|id| match strategy {
+        IdStrategy::Timestamp => is_timestamp_id(id, timestamp_format),
+        IdStrategy::Slug => slug_id(id) == id,
+        IdStrategy::Uuid => is_uuid(id),
+        IdStrategy::Sequential => !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_id_test() {
+        assert_eq!(slug_id("  Some Note: a Plan!  "), "some-note-a-plan");
+        assert_eq!(slug_id("???"), "untitled");
+    }
+
+    #[test]
+    fn uuid_id_test() {
+        assert_eq!(uuid_id([0xff; 16]), "ffffffff-ffff-4fff-bfff-ffffffffffff");
+        assert_eq!(uuid_id([0; 16]), "00000000-0000-4000-8000-000000000000");
+    }
+
+    #[test]
+    fn sequential_id_test() {
+        assert_eq!(sequential_id(["0001", "0009", "index"]), "0010");
+        assert_eq!(sequential_id(["1", "2"]), "3");
+        assert_eq!(sequential_id([]), "1");
+    }
+
+    #[test]
+    fn unique_id_test() {
+        let taken = ["note", "note-2"];
+        assert_eq!(unique_id("note".to_string(), |id| taken.contains(&id)), "note-3");
+        assert_eq!(unique_id("other".to_string(), |id| taken.contains(&id)), "other");
+    }
+
+    #[test]
+    fn is_timestamp_id_test() {
+        assert!(is_timestamp_id("20240105", "%Y%m%d"));
+        assert!(is_timestamp_id("20240105143000", "%Y%m%d%H%M%S"));
+        assert!(!is_timestamp_id("20240105", "%Y%m%d%H%M%S"));
+        assert!(!is_timestamp_id("shopping list", "%Y%m%d"));
+    }
+
+    #[test]
+    fn is_id_test() {
+        assert!(is_id("20240105-2", IdStrategy::Timestamp, "%Y%m%d"));
+        assert!(is_id("2024-01-05", IdStrategy::Timestamp, "%Y-%m-%d"));
+        assert!(!is_id("Shopping List", IdStrategy::Timestamp, "%Y%m%d"));
+
+        assert!(is_id("shopping-list", IdStrategy::Slug, "%Y%m%d"));
+        assert!(!is_id("Shopping List", IdStrategy::Slug, "%Y%m%d"));
+
+        assert!(is_id("ffffffff-ffff-4fff-bfff-ffffffffffff", IdStrategy::Uuid, "%Y%m%d"));
+        assert!(is_id("ffffffff-ffff-4fff-bfff-ffffffffffff-2", IdStrategy::Uuid, "%Y%m%d"));
+        assert!(!is_id("shopping-list", IdStrategy::Uuid, "%Y%m%d"));
+
+        assert!(is_id("0012", IdStrategy::Sequential, "%Y%m%d"));
+        assert!(!is_id("shopping-list", IdStrategy::Sequential, "%Y%m%d"));
+    }
+}
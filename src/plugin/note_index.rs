@@ -0,0 +1,70 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use ::markdown::mdast;
+
+use crate::plugin::{
+    links, list_all_physical_notes, markdown,
+    note::{Note, PhysicalNote, Tag},
+    Config, ListAllPhysicalNotesError,
+};
+
+/// A note with everything that is commonly needed about it already parsed out.
+pub struct IndexedNote {
+    pub note: PhysicalNote,
+    pub path: PathBuf,
+    pub md: mdast::Node,
+    pub title: Option<String>,
+    pub tags: Vec<Tag>,
+    pub aliases: Vec<String>,
+    /// the files that this note links to, with heading anchors removed
+    pub links: BTreeSet<PathBuf>,
+}
+
+pub struct NoteIndex {
+    pub notes: Vec<IndexedNote>,
+}
+
+impl IndexedNote {
+    pub fn new(config: &Config, note: PhysicalNote, md: mdast::Node) -> IndexedNote {
+        let frontmatter = markdown::parse_frontmatter(&md).ok();
+        let title = frontmatter.as_ref().and_then(|frontmatter| frontmatter.title.clone());
+        let tags = frontmatter.as_ref().map(|frontmatter| frontmatter.tags.clone()).unwrap_or_default();
+        let aliases = frontmatter.map(|frontmatter| frontmatter.aliases).unwrap_or_default();
+        let links = resolved_links(config, &Note::Physical(note.clone()), &md);
+        IndexedNote { path: note.path(config), note, md, title, tags, aliases, links }
+    }
+}
+
+impl NoteIndex {
+    /// Reads and parses every note in the wiki. Notes that cannot be read or parsed are skipped.
+    pub fn build(config: &Config) -> Result<NoteIndex, ListAllPhysicalNotesError> {
+        let mut notes = Vec::new();
+        for note in list_all_physical_notes(config)? {
+            let md = note
+                .read_contents(config)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| markdown::parse_markdown(&contents).map_err(|e| e.to_string()));
+            match md {
+                Ok(md) => notes.push(IndexedNote::new(config, note, md)),
+                Err(e) => log::warn!("skipping {} in index: {e}", note.path(config).display()),
+            }
+        }
+        Ok(NoteIndex { notes })
+    }
+
+    /// Counts how many notes link to both of the given paths.
+    pub fn cocitations(&self, a: &Path, b: &Path) -> usize {
+        self.notes.iter().filter(|note| note.links.contains(a) && note.links.contains(b)).count()
+    }
+}
+
+pub fn resolved_links(config: &Config, note: &Note, md: &mdast::Node) -> BTreeSet<PathBuf> {
+    markdown::get_all_link_targets(md)
+        .into_iter()
+        .filter(|target| !links::is_external_link(config, target))
+        .filter_map(|target| links::resolve_link_path(config, note, &target).ok())
+        .collect()
+}
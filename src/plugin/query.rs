@@ -0,0 +1,376 @@
+use crate::plugin::{
+    filter::Filter,
+    markdown::Frontmatter,
+    note::{PhysicalNote, Tag},
+};
+
+/// What an autogenerated list looks like if its query does not say otherwise.
+pub const DEFAULT_FORMAT: &str = "- {icon}{link}{badges}";
+
+/// Something that can be shown after every link in an autogenerated list with the `{badges}` placeholder, which the `autogenerate_badges`
+/// config picks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Badge {
+    Date,
+    Tags,
+}
+
+impl Badge {
+    pub fn parse_from_str(s: &str) -> Option<Badge> {
+        match s {
+            "date" => Some(Badge::Date),
+            "tags" => Some(Badge::Tags),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QueryParseError(String);
+impl std::error::Error for QueryParseError {}
+impl std::fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid query: {}", self.0)
+    }
+}
+
+/// A query for the `query` autogenerate command, like
+/// `from dir:"projects" recursive where tag:"active" and status:"draft" sort:modified desc limit:20 format:"- [{title}]({path}) ({date})"`.
+/// Every clause is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub source: Source,
+    pub filters: Vec<Filter>,
+    /// if there is no sort, the notes stay in the order that the source lists them in
+    pub sort: Option<Sort>,
+    pub limit: Option<usize>,
+    pub format: Option<String>,
+}
+
+/// Which notes a query starts from, before any filters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// every note in the wiki
+    All,
+    /// the notes directly inside the directory, or anywhere inside it if `recursive` is set
+    Directory { directories: Vec<String>, recursive: bool },
+    /// the notes that link to the current note
+    Backlinks,
+    /// every note that can be reached by following links from the current note, in the direction given, up to `max_depth` links away
+    Explore { max_depth: Option<usize>, direction: ExploreDirection },
+    /// the notes that are related to the current note by a typed link, in either direction
+    Related(String),
+}
+
+/// Which links `explore` follows: the ones out of each note, the ones into it, or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExploreDirection {
+    Out,
+    In,
+    Both,
+}
+
+impl ExploreDirection {
+    pub fn parse_from_str(s: &str) -> Option<ExploreDirection> {
+        match s {
+            "out" => Some(ExploreDirection::Out),
+            "in" => Some(ExploreDirection::In),
+            "both" => Some(ExploreDirection::Both),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// notes without titles are sorted by their id among the titled notes
+    Title,
+    Id,
+    Created,
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Query {
+    pub fn new(source: Source) -> Query {
+        Query { source, filters: Vec::new(), sort: None, limit: None, format: None }
+    }
+}
+
+impl SortKey {
+    pub fn parse_from_str(s: &str) -> Option<SortKey> {
+        match s {
+            "title" => Some(SortKey::Title),
+            "id" => Some(SortKey::Id),
+            "date" | "created" => Some(SortKey::Created),
+            "modified" => Some(SortKey::Modified),
+            _ => None,
+        }
+    }
+}
+
+/// A note that a query selected, along with everything that it can be sorted by or formatted with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryRow {
+    pub note: PhysicalNote,
+    pub frontmatter: Option<Frontmatter>,
+    pub created: Option<chrono::NaiveDateTime>,
+    pub modified: Option<chrono::NaiveDateTime>,
+}
+
+impl QueryRow {
+    pub fn title(&self) -> Option<&str> {
+        self.frontmatter.as_ref().and_then(|frontmatter| frontmatter.title.as_deref())
+    }
+
+    /// The value of a `{name}` placeholder in a format string, other than the ones that need the plugin to work out (`icon`, `link`, and
+    /// `path`). Anything that is not built in is looked up in the frontmatter, with or without a `field.` prefix.
+    pub fn value(&self, name: &str, date_format: &str) -> Option<String> {
+        match name {
+            "id" => Some(self.note.id.clone()),
+            "title" => Some(self.title().unwrap_or(&self.note.id).to_string()),
+            "author" => Some(self.frontmatter.as_ref().and_then(|f| f.author.clone()).unwrap_or_else(|| "unknown author".to_string())),
+            "date" | "created" => self.created.map(|created| created.format(date_format).to_string()),
+            "modified" => self.modified.map(|modified| modified.format(date_format).to_string()),
+            "directory" => Some(self.note.directories.join("/")),
+            "tags" => self.frontmatter.as_ref().map(|f| f.tags.iter().map(Tag::to_string).collect::<Vec<_>>().join(" ")),
+            _ => self.frontmatter.as_ref().and_then(|f| f.field_str(name.strip_prefix("field.").unwrap_or(name))),
+        }
+    }
+
+    /// The text of the `{badges}` placeholder: each badge that the note has something for, set off from the link and from each other.
+    pub fn badges(&self, badges: &[Badge], date_format: &str) -> String {
+        badges
+            .iter()
+            .filter_map(|badge| match badge {
+                Badge::Date => self.value("date", date_format),
+                Badge::Tags => {
+                    let tags = self.frontmatter.as_ref().map(|f| f.tags.iter().map(|tag| format!("#{tag}")).collect::<Vec<_>>().join(" "));
+                    tags.filter(|tags| !tags.is_empty())
+                }
+            })
+            .map(|badge| format!(" · {badge}"))
+            .collect()
+    }
+}
+
+/// Splits a query into words, keeping quoted text (which can contain spaces) together and taking the quotes out.
+fn tokenize(s: &str) -> Result<Vec<String>, QueryParseError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+    for c in s.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err(QueryParseError("unterminated quote".to_string()));
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Clause {
+    From,
+    Where,
+}
+
+pub fn parse_query(s: &str) -> Result<Query, QueryParseError> {
+    let mut query = Query::new(Source::All);
+    let mut clause = None;
+    for token in tokenize(s)? {
+        match (token.as_str(), token.split_once(':')) {
+            ("from", _) => clause = Some(Clause::From),
+            ("where", _) => clause = Some(Clause::Where),
+            ("and", _) if clause == Some(Clause::Where) => {}
+            ("asc" | "desc", _) => match &mut query.sort {
+                Some(sort) => sort.descending = token == "desc",
+                None => return Err(QueryParseError(format!("'{token}' must come after a sort"))),
+            },
+            (_, Some(("sort", key))) => {
+                let key = SortKey::parse_from_str(key).ok_or_else(|| QueryParseError(format!("cannot sort by '{key}'")))?;
+                query.sort = Some(Sort { key, descending: false });
+            }
+            (_, Some(("limit", limit))) => {
+                query.limit = Some(limit.parse().map_err(|_| QueryParseError(format!("invalid limit '{limit}'")))?);
+            }
+            (_, Some(("format", format))) => query.format = Some(format.to_string()),
+
+            ("all", _) if clause == Some(Clause::From) => query.source = Source::All,
+            ("backlinks", _) if clause == Some(Clause::From) => query.source = Source::Backlinks,
+            ("explore", _) if clause == Some(Clause::From) => query.source = Source::Explore { max_depth: None, direction: ExploreDirection::Out },
+            (word, _) if clause == Some(Clause::From) && ExploreDirection::parse_from_str(word).is_some() => match &mut query.source {
+                Source::Explore { direction, .. } => *direction = ExploreDirection::parse_from_str(word).unwrap_or(*direction),
+                _ => return Err(QueryParseError(format!("'{word}' must come after explore"))),
+            },
+            (_, Some(("depth", depth))) if clause == Some(Clause::From) => {
+                let depth = depth.parse().map_err(|_| QueryParseError(format!("invalid depth '{depth}'")))?;
+                match &mut query.source {
+                    Source::Explore { max_depth, .. } => *max_depth = Some(depth),
+                    _ => return Err(QueryParseError("'depth' must come after explore".to_string())),
+                }
+            }
+            ("recursive", _) if clause == Some(Clause::From) => match &mut query.source {
+                Source::Directory { recursive, .. } => *recursive = true,
+                _ => return Err(QueryParseError("'recursive' must come after a directory".to_string())),
+            },
+            (_, Some(("rel", relation))) if clause == Some(Clause::From) => query.source = Source::Related(relation.to_string()),
+            (_, Some(("dir", directory))) if clause == Some(Clause::From) => {
+                let directories = directory.split('/').filter(|d| !d.is_empty()).map(ToString::to_string).collect();
+                query.source = Source::Directory { directories, recursive: false };
+            }
+
+            (_, Some(("tag", tag))) if clause == Some(Clause::Where) => query.filters.push(Filter::Tag(Tag::parse_from_str(tag))),
+            (_, Some((name, value))) if clause == Some(Clause::Where) && !name.is_empty() => {
+                let name = name.strip_prefix("field.").unwrap_or(name).to_string();
+                query.filters.push(Filter::Field { name, value: value.to_string() });
+            }
+
+            _ => return Err(QueryParseError(format!("unexpected '{token}'"))),
+        }
+    }
+    Ok(query)
+}
+
+pub fn sort_rows(rows: &mut [QueryRow], sort: Sort) {
+    rows.sort_by(|a, b| {
+        let primary = match sort.key {
+            SortKey::Title => a.title().unwrap_or(&a.note.id).cmp(b.title().unwrap_or(&b.note.id)),
+            SortKey::Id => a.note.id.cmp(&b.note.id),
+            SortKey::Created => a.created.cmp(&b.created),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+        };
+        // ties are broken by the note itself so that the output never depends on the order that the notes were listed in
+        let ordering = primary.then_with(|| a.note.cmp(&b.note));
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Replaces every `{name}` in `format` with what `lookup` gives for it, or nothing if it gives `None`. A `{` without a matching `}` is left
+/// as it is.
+pub fn fill_format(format: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::new();
+    let mut rest = format;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..open]);
+        result.push_str(&lookup(&rest[open + 1..open + close]).unwrap_or_default());
+        rest = &rest[open + close + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, title: Option<&str>, modified: Option<&str>) -> QueryRow {
+        QueryRow {
+            note: PhysicalNote { directories: Vec::new(), id: id.to_string() },
+            frontmatter: Some(Frontmatter { title: title.map(ToString::to_string), ..Frontmatter::default() }),
+            created: None,
+            modified: modified.map(|t| chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M").expect("test timestamp should parse")),
+        }
+    }
+
+    #[test]
+    fn parse_full_query_test() {
+        let query = r#"from dir:"projects" recursive where tag:"active" and status:draft sort:modified desc limit:20 format:"- {title} ({date})""#;
+        let query = parse_query(query).expect("query should parse");
+        assert_eq!(
+            query,
+            Query {
+                source: Source::Directory { directories: vec!["projects".to_string()], recursive: true },
+                filters: vec![
+                    Filter::Tag(Tag::parse_from_str("active")),
+                    Filter::Field { name: "status".to_string(), value: "draft".to_string() }
+                ],
+                sort: Some(Sort { key: SortKey::Modified, descending: true }),
+                limit: Some(20),
+                format: Some("- {title} ({date})".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_minimal_query_test() {
+        assert_eq!(parse_query("").expect("empty query should parse"), Query::new(Source::All));
+        assert_eq!(parse_query("from backlinks").expect("query should parse"), Query::new(Source::Backlinks));
+        assert_eq!(parse_query("from rel:parent").expect("query should parse"), Query::new(Source::Related("parent".to_string())));
+        assert_eq!(
+            parse_query("from explore depth:2 both").expect("query should parse"),
+            Query::new(Source::Explore { max_depth: Some(2), direction: ExploreDirection::Both })
+        );
+        let query = parse_query("where field.kind:\"meeting notes\" sort:title").expect("query should parse");
+        assert_eq!(query.filters, [Filter::Field { name: "kind".to_string(), value: "meeting notes".to_string() }]);
+        assert_eq!(query.sort, Some(Sort { key: SortKey::Title, descending: false }));
+    }
+
+    #[test]
+    fn parse_invalid_query_test() {
+        assert!(parse_query("from dir:\"projects").is_err());
+        assert!(parse_query("sort:size").is_err());
+        assert!(parse_query("limit:many").is_err());
+        assert!(parse_query("desc").is_err());
+        assert!(parse_query("tag:a").is_err());
+        assert!(parse_query("from recursive").is_err());
+        assert!(parse_query("from all depth:2").is_err());
+        assert!(parse_query("from explore depth:far").is_err());
+    }
+
+    #[test]
+    fn sort_rows_test() {
+        let mut rows = vec![row("1", Some("b"), Some("2024-01-02 00:00")), row("2", None, None), row("3", Some("a"), Some("2024-01-03 00:00"))];
+        sort_rows(&mut rows, Sort { key: SortKey::Title, descending: false });
+        assert_eq!(rows.iter().map(|r| r.note.id.as_str()).collect::<Vec<_>>(), ["2", "3", "1"]);
+        sort_rows(&mut rows, Sort { key: SortKey::Modified, descending: true });
+        assert_eq!(rows.iter().map(|r| r.note.id.as_str()).collect::<Vec<_>>(), ["3", "1", "2"]);
+    }
+
+    #[test]
+    fn badges_test() {
+        let mut row = row("20240101", None, None);
+        row.created = Some(chrono::NaiveDateTime::parse_from_str("2024-01-01 00:00", "%Y-%m-%d %H:%M").expect("test timestamp should parse"));
+        assert_eq!(row.badges(&[Badge::Date, Badge::Tags], "%Y-%m-%d"), " · 2024-01-01");
+        row.frontmatter.as_mut().expect("test row has frontmatter").tags = vec![Tag::parse_from_str("a"), Tag::parse_from_str("b::c")];
+        assert_eq!(row.badges(&[Badge::Tags, Badge::Date], "%Y-%m-%d"), " · #a #b::c · 2024-01-01");
+        assert_eq!(row.badges(&[], "%Y-%m-%d"), "");
+    }
+
+    #[test]
+    fn fill_format_test() {
+        let row = row("20240101", Some("A Note"), Some("2024-01-02 00:00"));
+        assert_eq!(fill_format("- {title} ({modified}) {missing}", |name| row.value(name, "%Y-%m-%d")), "- A Note (2024-01-02) ");
+        assert_eq!(fill_format("{id} {unclosed", |name| row.value(name, "%Y-%m-%d")), "20240101 {unclosed");
+    }
+}
@@ -0,0 +1,47 @@
+/// The relation that a link is qualified with, either in its title (`[text](note.md "rel=parent")`) or with a `rel::` prefix on its text
+/// (`[parent::text](note.md)` or `[[note|parent::text]]`). The title takes priority.
+pub fn link_relation(title: Option<&str>, text: &str) -> Option<String> {
+    let from_title = title.and_then(|title| title.split_whitespace().find_map(|word| word.strip_prefix("rel=")));
+    let from_text = || text.split_once("::").map(|(relation, _)| relation.trim());
+    from_title.or_else(from_text).filter(|relation| is_relation_name(relation)).map(ToString::to_string)
+}
+
+fn is_relation_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// What a link with `relation` means when it is seen from the note that it points to, so that `child` can be found from either side of
+/// a `parent` link. Relations without an inverse only count from the note that the link is in.
+pub fn inverse(relation: &str) -> Option<&'static str> {
+    match relation {
+        "parent" => Some("child"),
+        "child" => Some("parent"),
+        "source" => Some("cited_by"),
+        "cited_by" => Some("source"),
+        "contradicts" => Some("contradicts"),
+        "supports" => Some("supported_by"),
+        "supported_by" => Some("supports"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_relation_test() {
+        assert_eq!(link_relation(Some("rel=parent"), "text"), Some("parent".to_string()));
+        assert_eq!(link_relation(Some("a title rel=source"), "contradicts::text"), Some("source".to_string()));
+        assert_eq!(link_relation(Some("a title"), "contradicts:: text"), Some("contradicts".to_string()));
+        assert_eq!(link_relation(None, "plain text"), None);
+        assert_eq!(link_relation(None, "see a:: b"), None);
+    }
+
+    #[test]
+    fn inverse_test() {
+        assert_eq!(inverse("parent").and_then(inverse), Some("parent"));
+        assert_eq!(inverse("contradicts"), Some("contradicts"));
+        assert_eq!(inverse("mentions"), None);
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+/// The name of the state file that maps the paths that notes had before `remap_note_ids` gave them ids to the paths that they have now.
+pub const REMAP_STATE_FILE: &str = "id_remap";
+
+/// Old paths to new paths. Both are relative to the wiki home directory, with `/` between directories, and include the `.md`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemapTable {
+    by_old_path: BTreeMap<String, String>,
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+impl RemapTable {
+    /// Each line is an old path and a new path separated by a tab. Lines that are not are skipped.
+    pub fn parse(contents: &str) -> RemapTable {
+        let by_old_path = contents
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(old_path, new_path)| (old_path.to_string(), new_path.to_string()))
+            .collect();
+        RemapTable { by_old_path }
+    }
+
+    pub fn format(&self) -> String {
+        self.by_old_path.iter().map(|(old_path, new_path)| format!("{old_path}\t{new_path}\n")).collect()
+    }
+
+    /// Records that the note at `old_path` is now at `new_path`. Notes that were remapped to `old_path` before are remapped to `new_path`
+    /// too, so that following the table never takes more than one step.
+    pub fn insert(&mut self, old_path: String, new_path: String) {
+        for target in self.by_old_path.values_mut() {
+            if *target == old_path {
+                target.clone_from(&new_path);
+            }
+        }
+        self.by_old_path.insert(old_path, new_path);
+    }
+
+    /// Finds where a note that used to be at `old_path` is now. The `.md` can be left off, and since tools that name notes by title often
+    /// link to them by name alone, a path that is only a file name also matches an old path in any directory, as long as only one does.
+    pub fn lookup(&self, old_path: &str) -> Option<&str> {
+        let with_extension = if old_path.ends_with(".md") { old_path.to_string() } else { format!("{old_path}.md") };
+        if let Some(new_path) = self.by_old_path.get(&with_extension) {
+            return Some(new_path);
+        }
+        if with_extension.contains('/') {
+            return None;
+        }
+        let mut same_name = self.by_old_path.iter().filter(|(other_old_path, _)| file_name(other_old_path) == with_extension);
+        match (same_name.next(), same_name.next()) {
+            (Some((_, new_path)), None) => Some(new_path),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_format_roundtrip_test() {
+        let contents = "imported/Some Note.md\timported/20240101120000.md\nother.md\t20240101120001.md\n";
+        assert_eq!(RemapTable::parse(contents).format(), contents);
+        assert_eq!(RemapTable::parse("not a mapping\n"), RemapTable::default());
+    }
+
+    #[test]
+    fn lookup_test() {
+        let mut table = RemapTable::default();
+        table.insert("imported/Some Note.md".to_string(), "imported/1.md".to_string());
+        table.insert("a/Same.md".to_string(), "a/2.md".to_string());
+        table.insert("b/Same.md".to_string(), "b/3.md".to_string());
+        assert_eq!(table.lookup("imported/Some Note.md"), Some("imported/1.md"));
+        assert_eq!(table.lookup("imported/Some Note"), Some("imported/1.md"));
+        assert_eq!(table.lookup("Some Note"), Some("imported/1.md"));
+        assert_eq!(table.lookup("Same"), None);
+        assert_eq!(table.lookup("other/Some Note.md"), None);
+    }
+
+    #[test]
+    fn insert_follows_chains_test() {
+        let mut table = RemapTable::default();
+        table.insert("Old.md".to_string(), "Middle.md".to_string());
+        table.insert("Middle.md".to_string(), "1.md".to_string());
+        assert_eq!(table.lookup("Old.md"), Some("1.md"));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::plugin::note::PhysicalNote;
+
+/// The name of the state file that records when reminders were last checked, so that each reminder only goes off once.
+pub const LAST_CHECKED_STATE_FILE: &str = "reminders_last_checked";
+const LAST_CHECKED_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub note: PhysicalNote,
+    pub title: Option<String>,
+    pub at: chrono::NaiveDateTime,
+}
+
+/// Returns the reminders that came due after the last check and no later than now, earliest first.
+///
+/// If reminders have never been checked before, every reminder that is already due is returned.
+pub fn due_reminders(mut reminders: Vec<Reminder>, last_checked: Option<chrono::NaiveDateTime>, now: chrono::NaiveDateTime) -> Vec<Reminder> {
+    reminders.retain(|reminder| reminder.at <= now && last_checked.is_none_or(|last_checked| reminder.at > last_checked));
+    reminders.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.note.cmp(&b.note)));
+    reminders
+}
+
+pub fn parse_last_checked(contents: &[u8]) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(std::str::from_utf8(contents).ok()?.trim(), LAST_CHECKED_FORMAT).ok()
+}
+
+pub fn format_last_checked(last_checked: chrono::NaiveDateTime) -> String {
+    last_checked.format(LAST_CHECKED_FORMAT).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").expect("test datetime should parse")
+    }
+    fn reminder(id: &str, at: &str) -> Reminder {
+        Reminder { note: PhysicalNote { directories: vec![], id: id.to_string() }, title: None, at: datetime(at) }
+    }
+
+    #[test]
+    fn due_reminders_test() {
+        let reminders = vec![
+            reminder("future", "2024-01-03 00:00"),
+            reminder("already_notified", "2024-01-01 00:00"),
+            reminder("due", "2024-01-02 09:00"),
+            reminder("due_earlier", "2024-01-02 08:00"),
+        ];
+        let due = due_reminders(reminders, Some(datetime("2024-01-01 12:00")), datetime("2024-01-02 12:00"));
+        assert_eq!(due.iter().map(|r| r.note.id.as_str()).collect::<Vec<_>>(), ["due_earlier", "due"]);
+    }
+
+    #[test]
+    fn due_reminders_never_checked_test() {
+        let due = due_reminders(vec![reminder("old", "2020-01-01 00:00")], None, datetime("2024-01-02 12:00"));
+        assert_eq!(due.len(), 1);
+    }
+
+    #[test]
+    fn last_checked_round_trip_test() {
+        let now = datetime("2024-01-02 12:34");
+        assert_eq!(parse_last_checked(format_last_checked(now).as_bytes()), Some(now));
+        assert_eq!(parse_last_checked(b"garbage"), None);
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use nvim_oxi::api;
+
+use crate::plugin::{
+    context, list_all_physical_notes, markdown,
+    note::{self, PhysicalNote},
+    state, Config, ListAllPhysicalNotesError, NonUtf8Path,
+};
+
+// one line per note: the hash of the contents that the embedding was computed from, the path relative to the wiki home, and the vector
+pub const SEMANTIC_INDEX_STATE_FILE: &str = "semantic_index";
+
+#[derive(Debug)]
+pub struct NoEmbeddingCommand;
+impl std::error::Error for NoEmbeddingCommand {}
+impl std::fmt::Display for NoEmbeddingCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "semantic search needs embedding_command to be configured")
+    }
+}
+#[derive(Debug)]
+pub struct EmbeddingCommandFailed(String);
+impl std::error::Error for EmbeddingCommandFailed {}
+impl std::fmt::Display for EmbeddingCommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "embedding command failed: {}", self.0)
+    }
+}
+#[derive(Debug)]
+pub struct InvalidEmbeddingOutput;
+impl std::error::Error for InvalidEmbeddingOutput {}
+impl std::fmt::Display for InvalidEmbeddingOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "embedding command did not print a list of numbers")
+    }
+}
+error_union! {
+    pub enum SemanticSearchError {
+        ApiError(api::Error),
+        ListAllPhysicalNotesError(ListAllPhysicalNotesError),
+        ReadContentsError(note::ReadContentsError),
+        StateFileError(state::StateFileError),
+        IoError(std::io::Error),
+        NonUtf8Path(NonUtf8Path),
+        NoEmbeddingCommand(NoEmbeddingCommand),
+        EmbeddingCommandFailed(EmbeddingCommandFailed),
+        InvalidEmbeddingOutput(InvalidEmbeddingOutput),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding {
+    pub hash: u64,
+    pub vector: Vec<f64>,
+}
+
+/// Brings the stored embeddings up to date, only running the embedding command for notes that changed since they were last embedded.
+pub fn update_semantic_index(config: &Config) -> Result<(), SemanticSearchError> {
+    let (_, embedded_count) = update_index(config)?;
+    api::command(&format!(r#"echo "embedded {embedded_count} changed note(s)""#))?;
+    Ok(())
+}
+
+/// Returns the path, title, and similarity score of the `count` notes whose meaning is closest to the query, best first. The index is
+/// updated first so that the results include recent changes.
+pub(crate) fn semantic_search(config: &Config, query: String, count: usize) -> Result<Vec<[(&'static str, String); 3]>, SemanticSearchError> {
+    let (index, _) = update_index(config)?;
+    let query_vector = embed(config, &query)?;
+
+    let mut results = Vec::new();
+    for (path, score) in nearest(&index, &query_vector, count) {
+        let absolute_path = config.home_path.join(&path);
+        let title = std::fs::read_to_string(&absolute_path)
+            .ok()
+            .and_then(|contents| markdown::parse_markdown(&contents).ok())
+            .and_then(|md| markdown::parse_frontmatter(&md).ok())
+            .and_then(|frontmatter| frontmatter.title)
+            .unwrap_or_default();
+        results.push([("path", absolute_path.to_str().ok_or(NonUtf8Path)?.to_string()), ("title", title), ("score", format!("{score:.3}"))]);
+    }
+    Ok(results)
+}
+
+/// Returns the up to date index and the number of notes that had to be embedded.
+fn update_index(config: &Config) -> Result<(BTreeMap<PathBuf, Embedding>, usize), SemanticSearchError> {
+    let old_index = state::read_state_file(config, SEMANTIC_INDEX_STATE_FILE)?
+        .map(|contents| parse_index(&String::from_utf8_lossy(&contents)))
+        .unwrap_or_default();
+
+    // notes that no longer exist are dropped by only carrying over the notes that are listed now
+    let mut index = BTreeMap::new();
+    let mut embedded_count = 0;
+    for note in list_all_physical_notes(config)? {
+        let contents = note.read_contents(config)?;
+        let hash = content_hash(&contents);
+        let path = relative_path(config, &note);
+        let embedding = match old_index.get(&path) {
+            Some(embedding) if embedding.hash == hash => embedding.clone(),
+            _ => {
+                embedded_count += 1;
+                Embedding { hash, vector: embed(config, &context::strip_for_context(&contents))? }
+            }
+        };
+        index.insert(path, embedding);
+    }
+
+    if embedded_count > 0 || index.len() != old_index.len() {
+        // embedding can take a long time, so the lock is only taken to write, and the index is read again under it so that the notes that
+        // another instance embedded in the meantime are kept
+        let lock = state::lock_state(config)?;
+        let latest = lock.read(SEMANTIC_INDEX_STATE_FILE)?.map(|contents| parse_index(&String::from_utf8_lossy(&contents))).unwrap_or_default();
+        let mut merged: BTreeMap<PathBuf, Embedding> =
+            latest.into_iter().filter(|(path, _)| !index.contains_key(path) && config.home_path.join(path).is_file()).collect();
+        merged.extend(index.iter().map(|(path, embedding)| (path.clone(), embedding.clone())));
+        lock.write(SEMANTIC_INDEX_STATE_FILE, format_index(&merged).as_bytes())?;
+    }
+    Ok((index, embedded_count))
+}
+
+fn relative_path(config: &Config, note: &PhysicalNote) -> PathBuf {
+    let path = note.path(config);
+    path.strip_prefix(&config.home_path).map(Path::to_path_buf).unwrap_or(path)
+}
+
+/// Runs the embedding command with the text on its standard input.
+fn embed(config: &Config, text: &str) -> Result<Vec<f64>, SemanticSearchError> {
+    let (program, args) = config.embedding_command.split_first().ok_or(NoEmbeddingCommand)?;
+    let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    child.stdin.take().expect("stdin should be piped").write_all(text.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        Err(EmbeddingCommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()))?;
+    }
+    Ok(parse_vector(&String::from_utf8_lossy(&output.stdout)).ok_or(InvalidEmbeddingOutput)?)
+}
+
+pub fn content_hash(contents: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Parses a vector of numbers separated by whitespace or commas, optionally surrounded by brackets so that json arrays work too.
+pub fn parse_vector(s: &str) -> Option<Vec<f64>> {
+    let s = s.trim();
+    let s = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(s);
+    let vector = s.split(|c: char| c == ',' || c.is_whitespace()).filter(|n| !n.is_empty()).map(|n| n.parse().ok()).collect::<Option<Vec<f64>>>()?;
+    if vector.is_empty() {
+        None
+    } else {
+        Some(vector)
+    }
+}
+
+pub fn parse_index(contents: &str) -> BTreeMap<PathBuf, Embedding> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+            let path = PathBuf::from(fields.next()?);
+            let vector = parse_vector(fields.next()?)?;
+            Some((path, Embedding { hash, vector }))
+        })
+        .collect()
+}
+
+pub fn format_index(index: &BTreeMap<PathBuf, Embedding>) -> String {
+    index
+        .iter()
+        .map(|(path, embedding)| {
+            let vector = embedding.vector.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+            format!("{:x}\t{}\t{vector}\n", embedding.hash, path.display())
+        })
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    let a_norm = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let b_norm = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if a_norm == 0.0 || b_norm == 0.0 {
+        0.0
+    } else {
+        dot / (a_norm * b_norm)
+    }
+}
+
+/// Returns the `count` notes with the most similar embeddings to the query, most similar first.
+pub fn nearest(index: &BTreeMap<PathBuf, Embedding>, query: &[f64], count: usize) -> Vec<(PathBuf, f64)> {
+    let mut scored: Vec<(PathBuf, f64)> =
+        index.iter().map(|(path, embedding)| (path.clone(), cosine_similarity(&embedding.vector, query))).collect();
+    scored.sort_by(|(a_path, a_score), (b_path, b_score)| b_score.total_cmp(a_score).then_with(|| a_path.cmp(b_path)));
+    scored.truncate(count);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vector_test() {
+        assert_eq!(parse_vector("[0.5, -1, 2e-1]\n"), Some(vec![0.5, -1.0, 0.2]));
+        assert_eq!(parse_vector("1 2\n3"), Some(vec![1.0, 2.0, 3.0]));
+        assert_eq!(parse_vector("[]"), None);
+        assert_eq!(parse_vector("error: model not found"), None);
+    }
+
+    #[test]
+    fn index_round_trip_test() {
+        let index = BTreeMap::from([
+            (PathBuf::from("a.md"), Embedding { hash: 0xabc, vector: vec![0.5, -1.0] }),
+            (PathBuf::from("dir/b.md"), Embedding { hash: 1, vector: vec![0.25, 3.0] }),
+        ]);
+        assert_eq!(parse_index(&format_index(&index)), index);
+    }
+
+    #[test]
+    fn parse_index_skips_malformed_lines_test() {
+        assert_eq!(parse_index("not a line\nzz\ta.md\t1 2\n1\tb.md\t1 2\n").keys().collect::<Vec<_>>(), [&PathBuf::from("b.md")]);
+    }
+
+    #[test]
+    fn nearest_test() {
+        let index = BTreeMap::from([
+            (PathBuf::from("same.md"), Embedding { hash: 0, vector: vec![1.0, 0.0] }),
+            (PathBuf::from("opposite.md"), Embedding { hash: 0, vector: vec![-1.0, 0.0] }),
+            (PathBuf::from("close.md"), Embedding { hash: 0, vector: vec![1.0, 1.0] }),
+        ]);
+        let nearest = nearest(&index, &[2.0, 0.0], 2);
+        assert_eq!(nearest.iter().map(|(path, _)| path.to_str().unwrap()).collect::<Vec<_>>(), ["same.md", "close.md"]);
+        assert!((nearest[0].1 - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cosine_similarity_mismatched_lengths_test() {
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
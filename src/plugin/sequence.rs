@@ -0,0 +1,75 @@
+use std::ops::Range;
+
+/// Splits an ordered list item into its indentation, the character after its number (`.` or `)`), and the rest of the line.
+fn ordered_item(line: &str) -> Option<(&str, char, &str)> {
+    let content = line.trim_start();
+    let indent = &line[..line.len() - content.len()];
+    let digits_len = content.find(|c: char| !c.is_ascii_digit())?;
+    if digits_len == 0 {
+        return None;
+    }
+    let delimiter = content[digits_len..].chars().next().filter(|c| *c == '.' || *c == ')')?;
+    let rest = &content[digits_len + 1..];
+    (rest.is_empty() || rest.starts_with(' ')).then_some((indent, delimiter, rest))
+}
+
+/// The lines of the ordered list that `row` is in, which is the run of lines around it that are either ordered list items or indented
+/// under one. A blank line ends the list.
+pub fn sequence_at(lines: &[String], row: usize) -> Option<Range<usize>> {
+    let in_list = |line: &String| !line.trim().is_empty() && (ordered_item(line).is_some() || line.starts_with([' ', '\t']));
+    if !lines.get(row).is_some_and(in_list) {
+        return None;
+    }
+    let start = (0..row).rev().take_while(|i| in_list(&lines[*i])).last().unwrap_or(row);
+    let end = (row..lines.len()).take_while(|i| in_list(&lines[*i])).last().map_or(row, |i| i + 1);
+    // indented lines before the first item belong to something else
+    let start = (start..end).find(|i| ordered_item(&lines[*i]).is_some())?;
+    Some(start..end)
+}
+
+/// Numbers the items of a list from 1, leaving the items nested in them alone. Returns the new lines, and the index of the line of each
+/// item in order.
+pub fn renumber(lines: &[String]) -> (Vec<String>, Vec<usize>) {
+    let Some(top_indent) = lines.iter().find_map(|line| ordered_item(line).map(|(indent, _, _)| indent.to_string())) else {
+        return (lines.to_vec(), Vec::new());
+    };
+    let mut item_lines = Vec::new();
+    let new_lines = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match ordered_item(line) {
+            Some((indent, delimiter, rest)) if indent == top_indent => {
+                item_lines.push(i);
+                format!("{indent}{}{delimiter}{rest}", item_lines.len())
+            }
+            _ => line.clone(),
+        })
+        .collect();
+    (new_lines, item_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn sequence_at_test() {
+        let lines = to_lines("# outline\n\n1. [a](a.md)\n   1. nested\n3. [b](b.md)\n\n- other");
+        assert_eq!(sequence_at(&lines, 3), Some(2..5));
+        assert_eq!(sequence_at(&lines, 2), Some(2..5));
+        assert_eq!(sequence_at(&lines, 0), None);
+        assert_eq!(sequence_at(&lines, 6), None);
+    }
+
+    #[test]
+    fn renumber_test() {
+        let lines = to_lines("1. [a](a.md)\n   7. nested\n1. [new](new.md)\n2) [b](b.md)\n12.not an item");
+        let (new_lines, item_lines) = renumber(&lines);
+        assert_eq!(new_lines, to_lines("1. [a](a.md)\n   7. nested\n2. [new](new.md)\n3) [b](b.md)\n12.not an item"));
+        assert_eq!(item_lines, [0, 2, 3]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use crate::plugin::{markdown, note::Tag};
+
+/// The parts of a note that are compared when summarizing how the wiki changed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NoteSnapshot {
+    pub title: Option<String>,
+    pub tags: BTreeSet<Tag>,
+}
+
+impl NoteSnapshot {
+    pub fn from_contents(contents: &str) -> NoteSnapshot {
+        let frontmatter = markdown::parse_markdown(contents).ok().and_then(|md| markdown::parse_frontmatter(&md).ok());
+        NoteSnapshot {
+            title: frontmatter.as_ref().and_then(|frontmatter| frontmatter.title.clone()),
+            tags: frontmatter.as_ref().map(|frontmatter| frontmatter.tags.clone()).unwrap_or_default().into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Retitled {
+    pub path: PathBuf,
+    pub old_title: Option<String>,
+    pub new_title: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Retagged {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub added_tags: Vec<Tag>,
+    pub removed_tags: Vec<Tag>,
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct SnapshotDiff {
+    pub added: Vec<(PathBuf, Option<String>)>,
+    pub removed: Vec<(PathBuf, Option<String>)>,
+    pub retitled: Vec<Retitled>,
+    pub retagged: Vec<Retagged>,
+}
+
+pub fn diff_snapshots(old: &BTreeMap<PathBuf, NoteSnapshot>, new: &BTreeMap<PathBuf, NoteSnapshot>) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+
+    for (path, old_snapshot) in old {
+        if !new.contains_key(path) {
+            diff.removed.push((path.clone(), old_snapshot.title.clone()));
+        }
+    }
+
+    for (path, new_snapshot) in new {
+        let Some(old_snapshot) = old.get(path) else {
+            diff.added.push((path.clone(), new_snapshot.title.clone()));
+            continue;
+        };
+
+        if old_snapshot.title != new_snapshot.title {
+            diff.retitled.push(Retitled { path: path.clone(), old_title: old_snapshot.title.clone(), new_title: new_snapshot.title.clone() });
+        }
+        if old_snapshot.tags != new_snapshot.tags {
+            diff.retagged.push(Retagged {
+                path: path.clone(),
+                title: new_snapshot.title.clone(),
+                added_tags: new_snapshot.tags.difference(&old_snapshot.tags).cloned().collect(),
+                removed_tags: old_snapshot.tags.difference(&new_snapshot.tags).cloned().collect(),
+            });
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(title: Option<&str>, tags: &[&str]) -> NoteSnapshot {
+        NoteSnapshot { title: title.map(ToString::to_string), tags: tags.iter().map(|tag| Tag::parse_from_str(tag)).collect() }
+    }
+
+    #[test]
+    fn from_contents_test() {
+        assert_eq!(NoteSnapshot::from_contents("---\ntitle: a\ntags: x y\n---\nbody\n"), snapshot(Some("a"), &["x", "y"]));
+        assert_eq!(NoteSnapshot::from_contents("no frontmatter"), snapshot(None, &[]));
+    }
+
+    #[test]
+    fn diff_snapshots_test() {
+        let old = BTreeMap::from([
+            (PathBuf::from("/wiki/removed.md"), snapshot(Some("removed"), &[])),
+            (PathBuf::from("/wiki/retitled.md"), snapshot(Some("old"), &["x"])),
+            (PathBuf::from("/wiki/retagged.md"), snapshot(Some("retagged"), &["x", "y"])),
+            (PathBuf::from("/wiki/same.md"), snapshot(Some("same"), &["x"])),
+        ]);
+        let new = BTreeMap::from([
+            (PathBuf::from("/wiki/added.md"), snapshot(Some("added"), &[])),
+            (PathBuf::from("/wiki/retitled.md"), snapshot(Some("new"), &["x"])),
+            (PathBuf::from("/wiki/retagged.md"), snapshot(Some("retagged"), &["y", "z"])),
+            (PathBuf::from("/wiki/same.md"), snapshot(Some("same"), &["x"])),
+        ]);
+
+        let diff = diff_snapshots(&old, &new);
+        assert_eq!(diff.added, vec![(PathBuf::from("/wiki/added.md"), Some("added".to_string()))]);
+        assert_eq!(diff.removed, vec![(PathBuf::from("/wiki/removed.md"), Some("removed".to_string()))]);
+        assert_eq!(
+            diff.retitled,
+            vec![Retitled { path: PathBuf::from("/wiki/retitled.md"), old_title: Some("old".to_string()), new_title: Some("new".to_string()) }]
+        );
+        assert_eq!(
+            diff.retagged,
+            vec![Retagged {
+                path: PathBuf::from("/wiki/retagged.md"),
+                title: Some("retagged".to_string()),
+                added_tags: vec![Tag::parse_from_str("z")],
+                removed_tags: vec![Tag::parse_from_str("x")],
+            }]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::plugin::{heatmap, markdown};
+
+/// A section that an oversized note could be split off at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPoint {
+    /// the 0 indexed line of the heading
+    pub line: usize,
+    pub heading: String,
+    /// links from other notes to the heading or to anything under it
+    pub inbound_links: usize,
+    pub words: usize,
+}
+
+/// The ATX headings that are not inside code blocks, as (0 indexed line, level, text).
+pub fn headings(lines: &[String]) -> Vec<(usize, usize, &str)> {
+    let mut in_code_block = false;
+    let mut headings = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if let Some((level, text)) = markdown::atx_heading(line).filter(|_| !in_code_block) {
+            headings.push((i, level, text));
+        }
+    }
+    headings
+}
+
+/// The end (exclusive) of the section under the heading on `heading_line`, which is the next heading at the same level or above.
+pub fn section_end(lines: &[String], heading_line: usize) -> usize {
+    let headings = headings(lines);
+    let Some(&(_, level, _)) = headings.iter().find(|(line, _, _)| *line == heading_line) else {
+        return lines.len();
+    };
+    headings.iter().find(|(line, other_level, _)| *line > heading_line && *other_level <= level).map_or(lines.len(), |(line, _, _)| *line)
+}
+
+/// The range of lines of the innermost section that `row` is in, starting at its heading, or `None` if `row` is above every heading.
+pub fn section_at(lines: &[String], row: usize) -> Option<(usize, usize)> {
+    let (heading_line, _, _) = headings(lines).into_iter().rev().find(|(line, _, _)| *line <= row)?;
+    Some((heading_line, section_end(lines, heading_line)))
+}
+
+/// The text of the headings that `row` is under, outermost first, like breadcrumbs. A heading counts as being under itself.
+pub fn heading_path(lines: &[String], row: usize) -> Vec<&str> {
+    let mut path: Vec<(usize, &str)> = Vec::new();
+    for (_, level, text) in headings(lines).into_iter().take_while(|(line, _, _)| *line <= row) {
+        while path.last().is_some_and(|(other_level, _)| *other_level >= level) {
+            path.pop();
+        }
+        path.push((level, text));
+    }
+    path.into_iter().map(|(_, text)| text).collect()
+}
+
+/// Proposes where to split a note: the sections under its highest level of heading (not counting a lone title at the top level), with
+/// the ones that the most links point at first and then the biggest. `anchors` are the anchors of the links into the note.
+pub fn split_points(lines: &[String], anchors: &[&str]) -> Vec<SplitPoint> {
+    let headings = headings(lines);
+    let Some(split_level) = split_level(&headings) else {
+        return Vec::new();
+    };
+
+    let link_counts = heatmap::section_link_counts(lines, anchors);
+    let mut points: Vec<SplitPoint> = headings
+        .iter()
+        .filter(|(_, level, _)| *level == split_level)
+        .map(|(line, _, heading)| {
+            let end = section_end(lines, *line);
+            SplitPoint {
+                line: *line,
+                heading: heading.to_string(),
+                inbound_links: link_counts.iter().filter(|(heading_line, _)| (*line..end).contains(heading_line)).map(|(_, count)| count).sum(),
+                words: lines[line + 1..end].iter().map(|line| line.split_whitespace().count()).sum(),
+            }
+        })
+        .collect();
+    points.sort_by(|a, b| b.inbound_links.cmp(&a.inbound_links).then(b.words.cmp(&a.words)).then(a.line.cmp(&b.line)));
+    points
+}
+
+/// The highest level of heading in a note, or the one under it if there is only one heading at that level, since that is the note's title.
+fn split_level(headings: &[(usize, usize, &str)]) -> Option<usize> {
+    let level_count = |level: usize| headings.iter().filter(|(_, other_level, _)| *other_level == level).count();
+    let split_level = headings.iter().map(|(_, level, _)| *level).min()?;
+    if level_count(split_level) == 1 {
+        headings.iter().map(|(_, level, _)| *level).filter(|level| *level > split_level).min()
+    } else {
+        Some(split_level)
+    }
+}
+
+/// The sections under the headings at `level`, or at the level that [`split_points`] splits at if it is not given, as the range of lines
+/// of each one starting at its heading, in order.
+pub fn sections_at_level(lines: &[String], level: Option<usize>) -> Vec<(usize, usize)> {
+    let headings = headings(lines);
+    let Some(level) = level.or_else(|| split_level(&headings)) else {
+        return Vec::new();
+    };
+    headings.iter().filter(|(_, other_level, _)| *other_level == level).map(|(line, _, _)| (*line, section_end(lines, *line))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn heading_path_test() {
+        let lines = to_lines("intro\n# A\n## B\nlink\n### C\n## D\nlink");
+        assert_eq!(heading_path(&lines, 0), Vec::<&str>::new());
+        assert_eq!(heading_path(&lines, 3), ["A", "B"]);
+        assert_eq!(heading_path(&lines, 4), ["A", "B", "C"]);
+        assert_eq!(heading_path(&lines, 6), ["A", "D"]);
+    }
+
+    #[test]
+    fn section_at_test() {
+        let lines = to_lines("intro\n# title\n## one\na\n```\n# code\n```\n### detail\nb\n## two\nc");
+        assert_eq!(section_at(&lines, 0), None);
+        assert_eq!(section_at(&lines, 3), Some((2, 9)));
+        assert_eq!(section_at(&lines, 5), Some((2, 9)));
+        assert_eq!(section_at(&lines, 8), Some((7, 9)));
+        assert_eq!(section_at(&lines, 10), Some((9, 11)));
+    }
+
+    #[test]
+    fn split_points_test() {
+        let lines = to_lines("# title\n## small\na\n## big\na b c\n### linked\nd\n## also big\na b c d");
+        let points = split_points(&lines, &["linked", "small"]);
+        let points: Vec<_> = points.iter().map(|point| (point.heading.as_str(), point.inbound_links, point.words)).collect();
+        assert_eq!(points, [("big", 1, 6), ("small", 1, 1), ("also big", 0, 4)]);
+    }
+
+    #[test]
+    fn split_points_without_title_test() {
+        let lines = to_lines("# one\na\n# two\nb");
+        assert_eq!(split_points(&lines, &[]).len(), 2);
+        assert!(split_points(&to_lines("# only\na"), &[]).is_empty());
+    }
+
+    #[test]
+    fn sections_at_level_test() {
+        let lines = to_lines("# title
+## one
+a
+### detail
+b
+## two
+c");
+        assert_eq!(sections_at_level(&lines, None), [(1, 5), (5, 7)]);
+        assert_eq!(sections_at_level(&lines, Some(3)), [(3, 5)]);
+        assert_eq!(sections_at_level(&lines, Some(1)), [(0, 7)]);
+        assert!(sections_at_level(&to_lines("no headings"), None).is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Key, XChaCha20Poly1305, XNonce,
+};
+
+use crate::plugin::Config;
+
+// every cache, history file, etc. that the plugin writes goes inside this directory so that nothing leaks out of the wiki home directory
+pub const STATE_DIR_NAME: &str = ".wikiplugin";
+
+// held while a state file is being updated, so that neovim instances that share the wiki take turns
+const LOCK_FILE_NAME: &str = "lock";
+// how long to wait for another instance to finish writing before giving up
+const LOCK_WAIT: Duration = Duration::from_secs(2);
+// updating a state file takes nowhere near this long, so a lock this old was left behind by an instance that crashed
+const STALE_LOCK_AGE: Duration = Duration::from_secs(60);
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[derive(Clone)]
+pub enum EncryptionKeySource {
+    Keyfile(PathBuf),
+    Passphrase(String),
+}
+
+#[derive(Debug)]
+pub struct KeyDerivationError(argon2::Error);
+impl std::error::Error for KeyDerivationError {}
+impl std::fmt::Display for KeyDerivationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not derive encryption key: {}", self.0)
+    }
+}
+#[derive(Debug)]
+pub struct EncryptionFailed;
+impl std::error::Error for EncryptionFailed {}
+impl std::fmt::Display for EncryptionFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not encrypt state file")
+    }
+}
+#[derive(Debug)]
+pub struct DecryptionFailed;
+impl std::error::Error for DecryptionFailed {}
+impl std::fmt::Display for DecryptionFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not decrypt state file (is the encryption key correct?)")
+    }
+}
+#[derive(Debug)]
+pub struct StateLocked {
+    lock_path: PathBuf,
+    holder: String,
+}
+impl std::error::Error for StateLocked {}
+impl std::fmt::Display for StateLocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the wiki state is locked by another neovim instance ({}); if no other instance is using this wiki, delete {}",
+            self.holder,
+            self.lock_path.display()
+        )
+    }
+}
+error_union! {
+    pub enum StateFileError {
+        IoError(std::io::Error),
+        StateLocked(StateLocked),
+        KeyDerivationError(KeyDerivationError),
+        EncryptionFailed(EncryptionFailed),
+        DecryptionFailed(DecryptionFailed),
+    }
+}
+
+pub fn state_dir(config: &Config) -> PathBuf {
+    config.home_path.join(STATE_DIR_NAME)
+}
+
+/// Holds the lock on the state directory until it is dropped. A state file that is read, changed, and written back has to be read and
+/// written through the same lock, so that another instance cannot write it in between and have its changes lost.
+pub struct StateLock {
+    path: PathBuf,
+    dir: PathBuf,
+    encryption: Option<EncryptionKeySource>,
+}
+
+impl std::fmt::Debug for StateLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the encryption key is left out on purpose
+        f.debug_struct("StateLock").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl StateLock {
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, StateFileError> {
+        read_from(&self.dir, self.encryption.as_ref(), name)
+    }
+
+    /// Writes a state file next to where it goes and then renames it into place, so that another instance reading it at the same time
+    /// sees either the old contents or the new contents and never half of each.
+    pub fn write(&self, name: &str, contents: &[u8]) -> Result<(), StateFileError> {
+        let data = match &self.encryption {
+            Some(key_source) => encrypt(key_source, contents)?,
+            None => contents.to_vec(),
+        };
+        let temporary_path = self.dir.join(format!("{name}.{}.tmp", std::process::id()));
+        std::fs::write(&temporary_path, data)?;
+        std::fs::rename(temporary_path, self.dir.join(name))?;
+        Ok(())
+    }
+}
+
+/// Takes the lock on the state directory of the wiki, which is held until the returned lock is dropped.
+pub fn lock_state(config: &Config) -> Result<StateLock, StateFileError> {
+    let dir = state_dir(config);
+    std::fs::create_dir_all(&dir)?;
+    lock_state_dir(&dir, config.encryption.clone(), LOCK_WAIT)
+}
+
+/// Takes the lock on the state directory, waiting up to `wait` for another instance to release it. A lock that has not been released
+/// for a long time is assumed to be left over from an instance that crashed and is taken over.
+fn lock_state_dir(dir: &Path, encryption: Option<EncryptionKeySource>, wait: Duration) -> Result<StateLock, StateFileError> {
+    let path = dir.join(LOCK_FILE_NAME);
+    let started = std::time::Instant::now();
+    loop {
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                use std::io::Write;
+                // the pid is only there to tell the user who is holding the lock
+                writeln!(file, "pid {}", std::process::id())?;
+                return Ok(StateLock { path, dir: dir.to_path_buf(), encryption });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let age = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok().and_then(|modified| modified.elapsed().ok());
+                if age.is_some_and(|age| age > STALE_LOCK_AGE) {
+                    let _ = std::fs::remove_file(&path);
+                } else if started.elapsed() >= wait {
+                    let holder = std::fs::read_to_string(&path).map_or_else(|_| "unknown".to_string(), |holder| holder.trim().to_string());
+                    Err(StateLocked { lock_path: path, holder })?;
+                } else {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+            }
+            Err(e) => Err(e)?,
+        }
+    }
+}
+
+/// Reads a state file without taking the lock, for when it is only being looked at. Files are always replaced whole, so this never sees
+/// half of a write.
+pub fn read_state_file(config: &Config, name: &str) -> Result<Option<Vec<u8>>, StateFileError> {
+    read_from(&state_dir(config), config.encryption.as_ref(), name)
+}
+
+fn read_from(dir: &Path, encryption: Option<&EncryptionKeySource>, name: &str) -> Result<Option<Vec<u8>>, StateFileError> {
+    let data = match std::fs::read(dir.join(name)) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => Err(e)?,
+    };
+
+    match encryption {
+        Some(key_source) => Ok(Some(decrypt(key_source, &data)?)),
+        None => Ok(Some(data)),
+    }
+}
+
+fn derive_key(key_source: &EncryptionKeySource, salt: &[u8]) -> Result<[u8; 32], StateFileError> {
+    let secret = match key_source {
+        EncryptionKeySource::Keyfile(path) => std::fs::read(path)?,
+        EncryptionKeySource::Passphrase(passphrase) => passphrase.as_bytes().to_vec(),
+    };
+
+    let mut key = [0; 32];
+    argon2::Argon2::default().hash_password_into(&secret, salt, &mut key).map_err(KeyDerivationError)?;
+    Ok(key)
+}
+
+// encrypted files are laid out as salt, then nonce, then ciphertext
+fn encrypt(key_source: &EncryptionKeySource, plaintext: &[u8]) -> Result<Vec<u8>, StateFileError> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let key = derive_key(key_source, &salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(|_| EncryptionFailed)?;
+
+    Ok([&salt[..], &nonce[..], &ciphertext[..]].concat())
+}
+
+fn decrypt(key_source: &EncryptionKeySource, data: &[u8]) -> Result<Vec<u8>, StateFileError> {
+    if data.len() < SALT_LEN + NONCE_LEN {
+        Err(DecryptionFailed)?;
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let key = derive_key(key_source, salt)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    Ok(cipher.decrypt(XNonce::from_slice(nonce), ciphertext).map_err(|_| DecryptionFailed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt_roundtrip_test() {
+        let key_source = EncryptionKeySource::Passphrase("correct horse battery staple".to_string());
+        let encrypted = encrypt(&key_source, b"note title").expect("encryption should work");
+
+        assert!(!encrypted.windows(b"note title".len()).any(|w| w == b"note title"));
+        assert_eq!(decrypt(&key_source, &encrypted).expect("decryption should work"), b"note title");
+    }
+
+    #[test]
+    fn decrypt_wrong_passphrase_test() {
+        let encrypted = encrypt(&EncryptionKeySource::Passphrase("right".to_string()), b"note title").expect("encryption should work");
+
+        decrypt(&EncryptionKeySource::Passphrase("wrong".to_string()), &encrypted).expect_err("decryption with the wrong key should not work");
+    }
+
+    #[test]
+    fn lock_state_dir_test() {
+        let dir = std::env::temp_dir().join(format!("wikiplugin-lock-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("creating the test directory should work");
+
+        let lock = lock_state_dir(&dir, None, Duration::ZERO).expect("locking an unlocked directory should work");
+        let error = lock_state_dir(&dir, None, Duration::from_millis(50)).expect_err("locking a locked directory should not work");
+        assert!(matches!(error, StateFileError::StateLocked(_)));
+        drop(lock);
+        drop(lock_state_dir(&dir, None, Duration::ZERO).expect("locking should work again after the lock is released"));
+
+        std::fs::remove_dir_all(&dir).expect("removing the test directory should work");
+    }
+
+    #[test]
+    fn interleaved_updates_test() {
+        let dir = std::env::temp_dir().join(format!("wikiplugin-interleaved-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("creating the test directory should work");
+        let append = |lock: &StateLock, text: &str| {
+            let mut contents = lock.read("updates").expect("reading should work").unwrap_or_default();
+            contents.extend_from_slice(text.as_bytes());
+            contents
+        };
+
+        // the first instance reads, and the second instance tries to update while the first is still working on its change
+        let first = lock_state_dir(&dir, None, Duration::ZERO).expect("locking an unlocked directory should work");
+        let first_contents = append(&first, "a");
+        let second = std::thread::spawn({
+            let dir = dir.clone();
+            move || {
+                let lock = lock_state_dir(&dir, None, Duration::from_secs(5)).expect("locking should work once the first instance is done");
+                let contents = append(&lock, "b");
+                lock.write("updates", &contents).expect("writing should work");
+            }
+        });
+        std::thread::sleep(Duration::from_millis(100));
+        first.write("updates", &first_contents).expect("writing should work");
+        drop(first);
+        second.join().expect("the second instance should not panic");
+
+        assert_eq!(read_from(&dir, None, "updates").expect("reading should work"), Some(b"ab".to_vec()));
+        std::fs::remove_dir_all(&dir).expect("removing the test directory should work");
+    }
+
+    #[test]
+    fn decrypt_truncated_test() {
+        decrypt(&EncryptionKeySource::Passphrase("pass".to_string()), &[0; 10]).expect_err("decryption of truncated data should not work");
+    }
+}
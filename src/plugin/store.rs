@@ -0,0 +1,174 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::plugin::{note::PhysicalNote, ListAllPhysicalNotesError, NonUtf8Path};
+
+/// Where the notes of a wiki are kept. Reading, writing, moving, and deleting notes goes through this instead of the filesystem so that
+/// notes can be kept somewhere else, and so that features can be tested against a [`MemoryStore`].
+///
+/// Neovim buffers always hold files, so opening notes and reading unsaved changes out of buffers still goes through the filesystem.
+pub trait NoteStore {
+    /// Every note in the wiki, in no particular order.
+    fn list(&self) -> Result<Vec<PhysicalNote>, ListAllPhysicalNotesError>;
+    fn exists(&self, note: &PhysicalNote) -> bool;
+    fn read(&self, note: &PhysicalNote) -> Result<String, std::io::Error>;
+    /// Creates the note if it does not exist yet, along with the directories that it is in.
+    fn write(&self, note: &PhysicalNote, contents: &str) -> Result<(), std::io::Error>;
+    fn rename(&self, from: &PhysicalNote, to: &PhysicalNote) -> Result<(), std::io::Error>;
+    fn delete(&self, note: &PhysicalNote) -> Result<(), std::io::Error>;
+    /// When the note was last written.
+    fn modified(&self, note: &PhysicalNote) -> Result<std::time::SystemTime, std::io::Error>;
+}
+
+/// Notes as markdown files under the wiki home directory.
+pub struct FsStore {
+    home_path: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(home_path: PathBuf) -> FsStore {
+        FsStore { home_path }
+    }
+
+    fn path(&self, note: &PhysicalNote) -> PathBuf {
+        let mut path = self.home_path.clone();
+        path.extend(&note.directories);
+        path.push(&note.id);
+        path.set_extension("md");
+        path
+    }
+
+    fn create_parent(path: &Path) -> Result<(), std::io::Error> {
+        match path.parent() {
+            Some(parent) => std::fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+    }
+}
+
+impl NoteStore for FsStore {
+    fn list(&self) -> Result<Vec<PhysicalNote>, ListAllPhysicalNotesError> {
+        glob::glob(&format!("{}/**/*.md", self.home_path.to_str().ok_or(NonUtf8Path)?))?
+            .map(|path| {
+                path.map_err(ListAllPhysicalNotesError::from)
+                    .and_then(|path| PhysicalNote::parse_from_filepath_in(&self.home_path, &path).map_err(ListAllPhysicalNotesError::from))
+            })
+            .collect()
+    }
+
+    fn exists(&self, note: &PhysicalNote) -> bool {
+        self.path(note).exists()
+    }
+
+    fn read(&self, note: &PhysicalNote) -> Result<String, std::io::Error> {
+        std::fs::read_to_string(self.path(note))
+    }
+
+    fn write(&self, note: &PhysicalNote, contents: &str) -> Result<(), std::io::Error> {
+        let path = self.path(note);
+        FsStore::create_parent(&path)?;
+        std::fs::write(path, contents)
+    }
+
+    fn rename(&self, from: &PhysicalNote, to: &PhysicalNote) -> Result<(), std::io::Error> {
+        let to_path = self.path(to);
+        FsStore::create_parent(&to_path)?;
+        std::fs::rename(self.path(from), to_path)
+    }
+
+    fn delete(&self, note: &PhysicalNote) -> Result<(), std::io::Error> {
+        std::fs::remove_file(self.path(note))
+    }
+
+    fn modified(&self, note: &PhysicalNote) -> Result<std::time::SystemTime, std::io::Error> {
+        std::fs::metadata(self.path(note))?.modified()
+    }
+}
+
+/// Notes kept in memory, with when they were written, for tests.
+#[derive(Default)]
+pub struct MemoryStore {
+    notes: RefCell<BTreeMap<PhysicalNote, (String, std::time::SystemTime)>>,
+}
+
+fn not_found(note: &PhysicalNote) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, format!("no note {}/{}", note.directories.join("/"), note.id))
+}
+
+impl NoteStore for MemoryStore {
+    fn list(&self) -> Result<Vec<PhysicalNote>, ListAllPhysicalNotesError> {
+        Ok(self.notes.borrow().keys().cloned().collect())
+    }
+
+    fn exists(&self, note: &PhysicalNote) -> bool {
+        self.notes.borrow().contains_key(note)
+    }
+
+    fn read(&self, note: &PhysicalNote) -> Result<String, std::io::Error> {
+        self.notes.borrow().get(note).map(|(contents, _)| contents.clone()).ok_or_else(|| not_found(note))
+    }
+
+    fn write(&self, note: &PhysicalNote, contents: &str) -> Result<(), std::io::Error> {
+        self.notes.borrow_mut().insert(note.clone(), (contents.to_string(), std::time::SystemTime::now()));
+        Ok(())
+    }
+
+    fn rename(&self, from: &PhysicalNote, to: &PhysicalNote) -> Result<(), std::io::Error> {
+        let note = self.notes.borrow_mut().remove(from).ok_or_else(|| not_found(from))?;
+        self.notes.borrow_mut().insert(to.clone(), note);
+        Ok(())
+    }
+
+    fn delete(&self, note: &PhysicalNote) -> Result<(), std::io::Error> {
+        self.notes.borrow_mut().remove(note).map(|_| ()).ok_or_else(|| not_found(note))
+    }
+
+    fn modified(&self, note: &PhysicalNote) -> Result<std::time::SystemTime, std::io::Error> {
+        self.notes.borrow().get(note).map(|(_, modified)| *modified).ok_or_else(|| not_found(note))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(directories: &[&str], id: &str) -> PhysicalNote {
+        PhysicalNote { directories: directories.iter().map(ToString::to_string).collect(), id: id.to_string() }
+    }
+
+    fn exercise_store(store: &dyn NoteStore) {
+        store.write(&note(&["dir"], "a"), "contents of a").unwrap();
+        store.write(&note(&[], "b"), "contents of b").unwrap();
+        let mut listed = store.list().unwrap();
+        listed.sort();
+        assert_eq!(listed, [note(&[], "b"), note(&["dir"], "a")]);
+
+        store.rename(&note(&["dir"], "a"), &note(&["other"], "a")).unwrap();
+        assert!(!store.exists(&note(&["dir"], "a")));
+        assert_eq!(store.read(&note(&["other"], "a")).unwrap(), "contents of a");
+        assert!(store.modified(&note(&["other"], "a")).unwrap() <= std::time::SystemTime::now());
+        store.modified(&note(&["dir"], "a")).unwrap_err();
+
+        store.delete(&note(&[], "b")).unwrap();
+        store.read(&note(&[], "b")).unwrap_err();
+        assert_eq!(store.list().unwrap(), [note(&["other"], "a")]);
+    }
+
+    #[test]
+    fn memory_store_test() {
+        exercise_store(&MemoryStore::default());
+    }
+
+    #[test]
+    fn fs_store_test() {
+        let home_path = std::env::temp_dir().join("wikiplugin_fs_store_test");
+        let _ = std::fs::remove_dir_all(&home_path);
+        std::fs::create_dir_all(&home_path).unwrap();
+        // parsing listed paths canonicalizes them, so the home path has to be canonical too
+        exercise_store(&FsStore::new(home_path.canonicalize().unwrap()));
+        std::fs::remove_dir_all(home_path).unwrap();
+    }
+}
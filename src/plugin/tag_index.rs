@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::plugin::note::{PhysicalNote, Tag};
+
+/// What `open_tag_index` fills a new persistent tag index note with.
+pub const TAG_INDEX_NOTE_TEMPLATE: &str = "\
+---
+title: tags
+---
+
+wikiplugin_autogenerate tag_index
+wikiplugin_autogenerate_end
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagIndexStyle {
+    /// a heading for every tag
+    Flat,
+    /// nested headings for tags that are nested with `::`
+    Tree,
+}
+
+impl TagIndexStyle {
+    pub fn parse_from_str(s: &str) -> Option<TagIndexStyle> {
+        match s {
+            "flat" => Some(TagIndexStyle::Flat),
+            "tree" => Some(TagIndexStyle::Tree),
+            _ => None,
+        }
+    }
+}
+
+pub struct TagIndexEntry {
+    pub note: PhysicalNote,
+    pub title: String,
+    pub tags: Vec<Tag>,
+}
+
+/// Keeps only the entries that have `filter` or a tag nested inside it, and drops their other tags so that only the relevant part of the
+/// index is rendered.
+pub fn filter_entries(entries: Vec<TagIndexEntry>, filter: &Tag) -> Vec<TagIndexEntry> {
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.tags.retain(|tag| tag.is_within(filter));
+            if entry.tags.is_empty() {
+                None
+            } else {
+                Some(entry)
+            }
+        })
+        .collect()
+}
+
+pub fn render_tag_index<E>(
+    entries: &[TagIndexEntry],
+    style: TagIndexStyle,
+    format_entry: impl FnMut(&TagIndexEntry) -> Result<Vec<String>, E>,
+) -> Result<Vec<String>, E> {
+    match style {
+        TagIndexStyle::Flat => render_flat(entries, format_entry),
+        TagIndexStyle::Tree => render_tree(entries, format_entry),
+    }
+}
+
+/// Renders a heading for each tag, followed by the lines that `format_entry` gives for each note with that tag.
+fn render_flat<E>(entries: &[TagIndexEntry], mut format_entry: impl FnMut(&TagIndexEntry) -> Result<Vec<String>, E>) -> Result<Vec<String>, E> {
+    let mut tag_table: BTreeMap<&Tag, Vec<&TagIndexEntry>> = BTreeMap::new();
+    for entry in entries {
+        for tag in &entry.tags {
+            tag_table.entry(tag).or_default().push(entry);
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (tag, tagged_entries) in tag_table {
+        lines.extend([format!("# {tag}"), "".to_string()]);
+        for entry in tagged_entries {
+            lines.extend(format_entry(entry)?);
+        }
+        lines.push("".to_string());
+    }
+    Ok(lines)
+}
+
+/// Renders a heading for every level of nesting in the tags, nested under the heading of its parent. Each heading has the number of notes
+/// with that tag or any tag nested inside it, followed by the notes that have exactly that tag.
+fn render_tree<E>(entries: &[TagIndexEntry], mut format_entry: impl FnMut(&TagIndexEntry) -> Result<Vec<String>, E>) -> Result<Vec<String>, E> {
+    // the index of each entry under every prefix of each of its tags; sets so that a note tagged with both a and a::b is only counted once
+    // for a
+    let mut aggregated: BTreeMap<&[String], BTreeSet<usize>> = BTreeMap::new();
+    let mut direct: BTreeMap<&[String], Vec<usize>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        for tag in &entry.tags {
+            let components = tag.components();
+            for depth in 1..=components.len() {
+                aggregated.entry(&components[..depth]).or_default().insert(i);
+            }
+            direct.entry(components).or_default().push(i);
+        }
+    }
+
+    // sorting the prefixes puts every parent directly before its children
+    let mut lines = Vec::new();
+    for (prefix, notes) in aggregated {
+        let name = prefix.last().expect("prefixes always have at least one component");
+        lines.extend([format!("{} {name} ({})", "#".repeat(prefix.len().min(6)), notes.len()), "".to_string()]);
+        if let Some(direct_notes) = direct.get(prefix) {
+            for &i in direct_notes {
+                lines.extend(format_entry(&entries[i])?);
+            }
+            lines.push("".to_string());
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, tags: &[&str]) -> TagIndexEntry {
+        TagIndexEntry {
+            note: PhysicalNote { directories: vec![], id: id.to_string() },
+            title: id.to_uppercase(),
+            tags: tags.iter().map(|tag| Tag::parse_from_str(tag)).collect(),
+        }
+    }
+
+    #[test]
+    fn render_tag_index_test() {
+        let entries = [entry("a", &["x", "y"]), entry("b", &["x"]), entry("c", &[])];
+        let lines =
+            render_tag_index(&entries, TagIndexStyle::Flat, |entry| Ok::<_, ()>(vec![format!("- [{}]({}.md)", entry.title, entry.note.id)])).unwrap();
+        assert_eq!(lines, ["# x", "", "- [A](a.md)", "- [B](b.md)", "", "# y", "", "- [A](a.md)", ""]);
+    }
+
+    #[test]
+    fn filter_entries_test() {
+        let entries = vec![entry("a", &["project::wiki", "x"]), entry("b", &["project::wiki::links"]), entry("c", &["project::other"])];
+        let filtered = filter_entries(entries, &Tag::parse_from_str("project::wiki"));
+        assert_eq!(
+            filtered.iter().map(|entry| (entry.note.id.as_str(), entry.tags.iter().map(ToString::to_string).collect::<Vec<_>>())).collect::<Vec<_>>(),
+            [("a", vec!["project::wiki".to_string()]), ("b", vec!["project::wiki::links".to_string()])]
+        );
+    }
+
+    #[test]
+    fn render_tag_tree_test() {
+        let entries = [entry("a", &["x::y", "x"]), entry("b", &["x::y::z"]), entry("c", &["w"])];
+        let lines = render_tag_index(&entries, TagIndexStyle::Tree, |entry| Ok::<_, ()>(vec![format!("- {}", entry.note.id)])).unwrap();
+        assert_eq!(
+            lines,
+            ["# w (1)", "", "- c", "", "# x (2)", "", "- a", "", "## y (2)", "", "- a", "", "### z (1)", "", "- b", ""]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+/// The placeholders that templates can use, written as `{title}` and so on.
+pub const PLACEHOLDERS: [&str; 4] = ["title", "date", "time", "author"];
+
+/// Replaces every placeholder that has a substitution. Placeholders without one are left as they are.
+pub fn substitute(contents: &str, substitutions: &[(&str, String)]) -> String {
+    let mut contents = contents.to_string();
+    for (placeholder, replacement) in substitutions {
+        contents = contents.replace(&format!("{{{placeholder}}}"), replacement);
+    }
+    contents
+}
+
+/// Finds everything that looks like a placeholder but is not one of [`PLACEHOLDERS`], as (line index, name) pairs. Autogenerate markers
+/// are skipped because query formats use `{link}` and the like.
+pub fn unknown_placeholders(contents: &str) -> Vec<(usize, String)> {
+    let mut unknown = Vec::new();
+    for (line_index, line) in contents.lines().enumerate() {
+        if line.contains("wikiplugin_autogenerate") {
+            continue;
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find('{') {
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find('}') else { break };
+            let name = &rest[..end];
+            let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if is_identifier && !PLACEHOLDERS.contains(&name) {
+                unknown.push((line_index, name.to_string()));
+            }
+        }
+    }
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_test() {
+        let substitutions = [("title", "Some Title".to_string()), ("date", "2024-01-01".to_string())];
+        assert_eq!(substitute("# {title}\n{date} {time}", &substitutions), "# Some Title\n2024-01-01 {time}");
+    }
+
+    #[test]
+    fn unknown_placeholders_test() {
+        let contents = "---\ntitle: {title}\ntags: {tags}\n---\n{ \"json\": 1 } {} {date}{auhtor}\n\
+            <!-- wikiplugin_autogenerate query from all | format \"- {link}\" -->";
+        assert_eq!(unknown_placeholders(contents), [(2, "tags".to_string()), (4, "auhtor".to_string())]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "before", "being", "but", "can", "could", "did", "does", "for",
+    "from", "had", "has", "have", "her", "here", "him", "his", "how", "into", "its", "just", "like", "more", "most", "not", "now", "only", "other",
+    "our", "out", "over", "same", "she", "should", "some", "such", "than", "that", "the", "their", "them", "then", "there", "these", "they",
+    "this", "those", "through", "too", "under", "very", "was", "were", "what", "when", "where", "which", "while", "who", "why", "will", "with",
+    "would", "you", "your",
+];
+
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3 && !word.chars().all(char::is_numeric))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+pub fn term_counts(text: &str) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for term in tokenize(text) {
+        *counts.entry(term).or_default() += 1;
+    }
+    counts
+}
+
+/// Counts how many documents in the corpus contain each term.
+pub fn document_frequencies(corpus: &[String]) -> BTreeMap<String, usize> {
+    let mut frequencies = BTreeMap::new();
+    for document in corpus {
+        for term in tokenize(document).collect::<BTreeSet<_>>() {
+            *frequencies.entry(term).or_default() += 1;
+        }
+    }
+    frequencies
+}
+
+pub fn idf(term: &str, document_frequencies: &BTreeMap<String, usize>, corpus_size: usize) -> f64 {
+    let document_frequency = document_frequencies.get(term).copied().unwrap_or(0);
+    ((corpus_size + 1) as f64 / (document_frequency + 1) as f64).ln() + 1.0
+}
+
+pub fn tfidf_vector(text: &str, document_frequencies: &BTreeMap<String, usize>, corpus_size: usize) -> BTreeMap<String, f64> {
+    term_counts(text)
+        .into_iter()
+        .map(|(term, term_count)| {
+            let score = term_count as f64 * idf(&term, document_frequencies, corpus_size);
+            (term, score)
+        })
+        .collect()
+}
+
+pub fn cosine_similarity(a: &BTreeMap<String, f64>, b: &BTreeMap<String, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(term, a_score)| b.get(term).map(|b_score| a_score * b_score)).sum();
+    let a_norm = a.values().map(|score| score * score).sum::<f64>().sqrt();
+    let b_norm = b.values().map(|score| score * score).sum::<f64>().sqrt();
+    if a_norm == 0.0 || b_norm == 0.0 {
+        0.0
+    } else {
+        dot / (a_norm * b_norm)
+    }
+}
+
+/// Returns the `count` terms of the document with the highest tf-idf scores relative to the corpus.
+pub fn extract_keywords(document: &str, corpus: &[String], count: usize) -> Vec<String> {
+    let document_frequencies = document_frequencies(corpus);
+
+    let mut scored: Vec<(f64, String)> =
+        tfidf_vector(document, &document_frequencies, corpus.len()).into_iter().map(|(term, score)| (score, term)).collect();
+    scored.sort_by(|(a_score, a_term), (b_score, b_term)| b_score.total_cmp(a_score).then_with(|| a_term.cmp(b_term)));
+
+    scored.into_iter().take(count).map(|(_, term)| term).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_test() {
+        assert_eq!(tokenize("The Rust borrow-checker, and 2024 of it").collect::<Vec<_>>(), ["rust", "borrow", "checker"]);
+    }
+
+    #[test]
+    fn extract_keywords_prefers_rare_terms_test() {
+        let corpus = vec!["notes about cooking".to_string(), "notes about gardening".to_string(), "notes about lifetimes".to_string()];
+        assert_eq!(extract_keywords("notes lifetimes", &corpus, 1), ["lifetimes"]);
+    }
+
+    #[test]
+    fn cosine_similarity_test() {
+        let corpus = vec!["rust lifetimes borrow".to_string(), "cooking pasta sauce".to_string()];
+        let document_frequencies = document_frequencies(&corpus);
+        let vector = |text| tfidf_vector(text, &document_frequencies, corpus.len());
+
+        assert!((cosine_similarity(&vector("rust lifetimes"), &vector("rust lifetimes")) - 1.0).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&vector("rust lifetimes"), &vector("pasta sauce")), 0.0);
+        assert!(cosine_similarity(&vector("rust lifetimes"), &vector("rust borrow")) > 0.0);
+        assert_eq!(cosine_similarity(&vector(""), &vector("rust")), 0.0);
+    }
+}
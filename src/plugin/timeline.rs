@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineGrouping {
+    Month,
+    /// ISO weeks, which start on monday
+    Week,
+}
+
+impl TimelineGrouping {
+    pub fn parse_from_str(s: &str) -> Option<TimelineGrouping> {
+        match s {
+            "month" => Some(TimelineGrouping::Month),
+            "week" => Some(TimelineGrouping::Week),
+            _ => None,
+        }
+    }
+
+    /// The heading of the group that `date` is in.
+    fn label(self, date: NaiveDate) -> String {
+        match self {
+            TimelineGrouping::Month => date.format("%B %Y").to_string(),
+            TimelineGrouping::Week => {
+                let week = date.iso_week();
+                let monday = NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Mon).unwrap_or(date);
+                format!("{}-W{:02}, week of {}", week.year(), week.week(), monday.format("%Y-%m-%d"))
+            }
+        }
+    }
+
+    /// Sorts groups in date order regardless of how their labels sort.
+    fn key(self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            TimelineGrouping::Month => (date.year(), date.month()),
+            TimelineGrouping::Week => (date.iso_week().year(), date.iso_week().week()),
+        }
+    }
+}
+
+/// The dates that a timeline covers, either of which can be left open, written as `from..until` (or just `from`) in the configured date
+/// format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimelineRange {
+    pub from: Option<NaiveDate>,
+    /// inclusive
+    pub until: Option<NaiveDate>,
+}
+
+impl TimelineRange {
+    pub fn parse_from_str(s: &str, date_format: &str) -> Option<TimelineRange> {
+        let parse_date = |date: &str| -> Option<Option<NaiveDate>> {
+            let date = date.trim();
+            if date.is_empty() {
+                Some(None)
+            } else {
+                NaiveDate::parse_from_str(date, date_format).ok().map(Some)
+            }
+        };
+        match s.split_once("..") {
+            Some((from, until)) => Some(TimelineRange { from: parse_date(from)?, until: parse_date(until)? }),
+            None => Some(TimelineRange { from: parse_date(s)?, until: None }),
+        }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| date >= from) && self.until.is_none_or(|until| date <= until)
+    }
+}
+
+/// Lays out the entries (each an already formatted list item with the timestamp that it is placed at) from oldest to newest under a
+/// heading for each month or week.
+pub fn timeline_lines(mut entries: Vec<(NaiveDateTime, String)>, grouping: TimelineGrouping) -> Vec<String> {
+    entries.sort_by(|(a_time, a_entry), (b_time, b_entry)| a_time.cmp(b_time).then_with(|| a_entry.cmp(b_entry)));
+    let mut groups: BTreeMap<(i32, u32), (String, Vec<String>)> = BTreeMap::new();
+    for (time, entry) in entries {
+        groups.entry(grouping.key(time.date())).or_insert_with(|| (grouping.label(time.date()), Vec::new())).1.push(entry);
+    }
+
+    let mut lines = Vec::new();
+    for (label, entries) in groups.into_values() {
+        lines.push(format!("## {label} ({})", entries.len()));
+        lines.push(String::new());
+        lines.extend(entries);
+        lines.push(String::new());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").expect("test timestamp should parse")
+    }
+
+    #[test]
+    fn parse_range_test() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        assert_eq!(
+            TimelineRange::parse_from_str("2024-01-01..2024-06-30", "%Y-%m-%d"),
+            Some(TimelineRange { from: date("2024-01-01"), until: date("2024-06-30") })
+        );
+        assert_eq!(TimelineRange::parse_from_str("..2024-06-30", "%Y-%m-%d"), Some(TimelineRange { from: None, until: date("2024-06-30") }));
+        assert_eq!(TimelineRange::parse_from_str("2024-01-01", "%Y-%m-%d"), Some(TimelineRange { from: date("2024-01-01"), until: None }));
+        assert_eq!(TimelineRange::parse_from_str("january", "%Y-%m-%d"), None);
+    }
+
+    #[test]
+    fn timeline_lines_by_month_test() {
+        let entries = vec![
+            (time("2024-02-01 09:00"), "- c".to_string()),
+            (time("2023-12-31 09:00"), "- a".to_string()),
+            (time("2024-02-01 08:00"), "- b".to_string()),
+        ];
+        assert_eq!(
+            timeline_lines(entries, TimelineGrouping::Month),
+            ["## December 2023 (1)", "", "- a", "", "## February 2024 (2)", "", "- b", "- c", ""]
+        );
+    }
+
+    #[test]
+    fn timeline_lines_by_week_test() {
+        // the last days of 2024 are in the first ISO week of 2025
+        let entries = vec![(time("2024-12-30 09:00"), "- a".to_string()), (time("2025-01-05 09:00"), "- b".to_string())];
+        assert_eq!(timeline_lines(entries, TimelineGrouping::Week), ["## 2025-W01, week of 2024-12-30 (2)", "", "- a", "- b", ""]);
+    }
+}
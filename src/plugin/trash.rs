@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+use crate::plugin::note::PhysicalNote;
+
+/// The directory inside the wiki that deleted notes are moved to, each under a directory named after when it was deleted, in the same
+/// directories that it was in before.
+pub const TRASH_DIRECTORY: &str = ".trash";
+
+/// The format of the names of the directories that deleted notes are put in, which sort in the order that the notes were deleted.
+pub const DELETED_AT_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// Where a note that is deleted at `deleted_at` goes in the trash.
+pub fn trashed_note(note: &PhysicalNote, deleted_at: &str) -> PhysicalNote {
+    let mut directories = vec![TRASH_DIRECTORY.to_string(), deleted_at.to_string()];
+    directories.extend(note.directories.iter().cloned());
+    PhysicalNote { directories, id: note.id.clone() }
+}
+
+/// When a note in the trash was deleted and where it was before, or `None` if the note is not in the trash.
+pub fn original_note(trashed: &PhysicalNote) -> Option<(&str, PhysicalNote)> {
+    match trashed.directories.as_slice() {
+        [trash, deleted_at, directories @ ..] if trash == TRASH_DIRECTORY => {
+            Some((deleted_at.as_str(), PhysicalNote { directories: directories.to_vec(), id: trashed.id.clone() }))
+        }
+        _ => None,
+    }
+}
+
+/// What happens to the links in other notes to a note that is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletedNoteLinks {
+    /// The links are left alone and put in the quickfix list.
+    Keep,
+    /// The links are struck through, so that they still work if the note is restored.
+    Strikethrough,
+    /// The links are replaced by their text.
+    Unlink,
+}
+
+impl DeletedNoteLinks {
+    pub fn parse_from_str(s: &str) -> Option<DeletedNoteLinks> {
+        match s {
+            "keep" => Some(DeletedNoteLinks::Keep),
+            "strikethrough" => Some(DeletedNoteLinks::Strikethrough),
+            "unlink" => Some(DeletedNoteLinks::Unlink),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrites the links at `links` (each the byte range of the whole link with the text of the link) the way `action` says to. The links
+/// have to be in order and not overlap.
+pub fn rewrite_deleted_links(contents: &str, links: &[(Range<usize>, String)], action: DeletedNoteLinks) -> String {
+    let mut new_contents = String::new();
+    let mut last_end = 0;
+    for (range, text) in links {
+        new_contents.push_str(&contents[last_end..range.start]);
+        match action {
+            DeletedNoteLinks::Keep => new_contents.push_str(&contents[range.clone()]),
+            DeletedNoteLinks::Strikethrough => new_contents.push_str(&format!("~~{}~~", &contents[range.clone()])),
+            DeletedNoteLinks::Unlink => new_contents.push_str(text),
+        }
+        last_end = range.end;
+    }
+    new_contents.push_str(&contents[last_end..]);
+    new_contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_roundtrip_test() {
+        let note = PhysicalNote { directories: vec!["projects".to_string()], id: "20240101120000".to_string() };
+        let trashed = trashed_note(&note, "20240601090000");
+        assert_eq!(trashed.directories, [".trash", "20240601090000", "projects"]);
+        assert_eq!(original_note(&trashed), Some(("20240601090000", note.clone())));
+        assert_eq!(original_note(&note), None);
+    }
+
+    #[test]
+    fn rewrite_deleted_links_test() {
+        let contents = "see [old](old.md) and [[old|that]]\n";
+        let links = [(4..17, "old".to_string()), (22..34, "that".to_string())];
+        assert_eq!(rewrite_deleted_links(contents, &links, DeletedNoteLinks::Keep), contents);
+        assert_eq!(
+            rewrite_deleted_links(contents, &links, DeletedNoteLinks::Strikethrough),
+            "see ~~[old](old.md)~~ and ~~[[old|that]]~~\n"
+        );
+        assert_eq!(rewrite_deleted_links(contents, &links, DeletedNoteLinks::Unlink), "see old and that\n");
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use yaml_rust::Yaml;
+
+use crate::plugin::markdown;
+
+/// The files at the root of a wiki that can hold settings for it, in the order that they are looked for. Only the first one that exists
+/// is read.
+pub const WIKI_CONFIG_FILE_NAMES: [&str; 3] = [".wikiplugin.toml", ".wikiplugin.yaml", ".wikiplugin.yml"];
+
+/// The settings that a wiki's own settings file can set. A wiki can come from anywhere, so nothing that runs a program, holds a secret,
+/// writes outside of the wiki, or changes how much the plugin asks before doing something is on this list; those have to be set in the
+/// lua config.
+const ALLOWED_KEYS: [&str; 36] = [
+    "alias_as_link_text",
+    "autogenerate_badges",
+    "autogenerate_marker_style",
+    "capture_template",
+    "create_missing_link_targets",
+    "date_format",
+    "default_template",
+    "deleted_note_links",
+    "directory_icons",
+    "directory_index_template",
+    "id_strategy",
+    "ignore",
+    "inbox_directory",
+    "index_filename",
+    "index_template",
+    "kind_icons",
+    "link_style",
+    "locale",
+    "max_image_size_kb",
+    "note_id_timestamp_format",
+    "preview_line_count",
+    "regenerate_on_save",
+    "resolve_links_by_alias",
+    "resolve_links_by_id",
+    "resolve_links_by_remap",
+    "resolve_links_by_title",
+    "respect_gitignore",
+    "split_heading_threshold",
+    "split_word_threshold",
+    "statuses",
+    "tag_index_note",
+    "tag_index_style",
+    "template_directory",
+    "time_format",
+    "timeline_grouping",
+    "virtual_sections",
+];
+
+/// Whether the wiki's own settings file is allowed to set `key`.
+pub fn is_allowed_key(key: &str) -> bool {
+    ALLOWED_KEYS.contains(&key)
+}
+
+#[derive(Debug)]
+pub struct WikiConfigFileError {
+    path: PathBuf,
+    message: String,
+}
+impl std::error::Error for WikiConfigFileError {}
+impl std::fmt::Display for WikiConfigFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid wiki config file {}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Reads the settings file at the root of the wiki, if there is one. The settings are a table of config keys, in the same shape as the lua
+/// config.
+pub fn read_wiki_config(home_path: &Path) -> Result<Option<Yaml>, WikiConfigFileError> {
+    let Some(path) = WIKI_CONFIG_FILE_NAMES.iter().map(|name| home_path.join(name)).find(|path| path.is_file()) else {
+        return Ok(None);
+    };
+    let error = |message: String| WikiConfigFileError { path: path.clone(), message };
+    let contents = std::fs::read_to_string(&path).map_err(|e| error(e.to_string()))?;
+    parse_wiki_config(&contents, path.extension().is_some_and(|extension| extension == "toml")).map(Some).map_err(error)
+}
+
+fn parse_wiki_config(contents: &str, is_toml: bool) -> Result<Yaml, String> {
+    let settings = if is_toml {
+        markdown::toml_to_yaml(toml::Value::Table(contents.parse().map_err(|e: toml::de::Error| e.to_string())?))
+    } else {
+        // an empty file has no documents
+        yaml_rust::YamlLoader::load_from_str(contents).map_err(|e| e.to_string())?.into_iter().next().unwrap_or(Yaml::Hash(Default::default()))
+    };
+    match settings {
+        Yaml::Hash(_) => Ok(settings),
+        _ => Err("settings should be a table".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wiki_config_test() {
+        let from_toml = parse_wiki_config("link_style = \"wikilink\"\nindex_filename = \"README.md\"\n", true).unwrap();
+        let from_yaml = parse_wiki_config("link_style: wikilink\nindex_filename: README.md\n", false).unwrap();
+        for settings in [from_toml, from_yaml] {
+            assert_eq!(settings["link_style"].as_str(), Some("wikilink"));
+            assert_eq!(settings["index_filename"].as_str(), Some("README.md"));
+        }
+        assert_eq!(parse_wiki_config("", false).unwrap(), Yaml::Hash(Default::default()));
+        parse_wiki_config("- a list", false).unwrap_err();
+        parse_wiki_config("not toml", true).unwrap_err();
+    }
+
+    #[test]
+    fn is_allowed_key_test() {
+        assert!(is_allowed_key("link_style"));
+        for key in ["home_path", "wikis", "external_opener", "reminder_command", "embedding_command", "encryption_passphrase", "buffer_options"] {
+            assert!(!is_allowed_key(key), "{key} should not be allowed");
+        }
+    }
+}
@@ -0,0 +1,42 @@
+/// The template that `setup_wizard` writes for new notes if asked to, with the frontmatter fields that the plugin reads.
+pub const DEFAULT_NOTE_TEMPLATE: &str = "\
+---
+title: {title}
+date: {date}
+time: {time}
+tags: []
+---
+
+";
+
+/// Where `setup_wizard` puts the default template, relative to the home path.
+pub const TEMPLATE_DIRECTORY: &str = "templates";
+pub const DEFAULT_TEMPLATE_PATH: &str = "templates/default.md";
+
+fn lua_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// A call to `setup` with the given settings, which are all strings, for pasting into a lua config.
+pub fn config_snippet(settings: &[(&str, String)]) -> String {
+    let mut snippet = String::from("require(\"wikiplugin\").setup({\n");
+    for (key, value) in settings {
+        snippet.push_str(&format!("    {key} = {},\n", lua_string(value)));
+    }
+    snippet.push_str("})\n");
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_snippet_test() {
+        let settings = [("home_path", "/home/me/wiki".to_string()), ("date_format", "%d \"%b\"".to_string())];
+        assert_eq!(
+            config_snippet(&settings),
+            "require(\"wikiplugin\").setup({\n    home_path = \"/home/me/wiki\",\n    date_format = \"%d \\\"%b\\\"\",\n})\n"
+        );
+    }
+}